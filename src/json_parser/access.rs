@@ -0,0 +1,99 @@
+use super::error::ParseError;
+use super::value::JsonValue;
+
+// Looks up `key` and converts it with `convert`, naming the `expected` type
+// in the error when the conversion fails.
+fn required<'a, T>(
+    value: &'a JsonValue,
+    key: &str,
+    expected: &'static str,
+    convert: impl FnOnce(&'a JsonValue) -> Option<T>,
+) -> Result<T, ParseError> {
+    convert(value.get_required(key)?).ok_or_else(|| ParseError::WrongType {
+        key: key.to_string(),
+        expected,
+    })
+}
+
+impl JsonValue {
+    /// Like [`get`](JsonValue::get), but a missing member is a
+    /// `ParseError::MissingField`, for use with `?` in extraction code.
+    pub fn get_required(&self, key: &str) -> Result<&JsonValue, ParseError> {
+        self.get(key).ok_or_else(|| ParseError::MissingField {
+            key: key.to_string(),
+        })
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool, ParseError> {
+        required(self, key, "a boolean", JsonValue::as_bool)
+    }
+
+    /// Only accepts `Integer` values; whole floats such as `90.0` are rejected.
+    pub fn get_i64(&self, key: &str) -> Result<i64, ParseError> {
+        required(self, key, "an integer", JsonValue::as_i64)
+    }
+
+    pub fn get_f64(&self, key: &str) -> Result<f64, ParseError> {
+        required(self, key, "a number", JsonValue::as_f64)
+    }
+
+    pub fn get_str(&self, key: &str) -> Result<&str, ParseError> {
+        required(self, key, "a string", JsonValue::as_str)
+    }
+
+    pub fn get_array(&self, key: &str) -> Result<&[JsonValue], ParseError> {
+        required(self, key, "an array", JsonValue::as_array)
+    }
+
+    pub fn get_object(&self, key: &str) -> Result<&[(String, JsonValue)], ParseError> {
+        required(self, key, "an object", JsonValue::as_object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[test]
+    fn typed_getters_return_values_of_the_right_type() {
+        let doc =
+            parse_json(r#"{"ok": true, "n": 3, "f": 2.5, "s": "x", "a": [1], "o": {}}"#).unwrap();
+        assert_eq!(doc.get_bool("ok"), Ok(true));
+        assert_eq!(doc.get_i64("n"), Ok(3));
+        assert_eq!(doc.get_f64("n"), Ok(3.0));
+        assert_eq!(doc.get_f64("f"), Ok(2.5));
+        assert_eq!(doc.get_str("s"), Ok("x"));
+        assert_eq!(doc.get_array("a"), Ok(&[JsonValue::Integer(1)][..]));
+        assert_eq!(doc.get_object("o"), Ok(&[][..]));
+    }
+
+    #[test]
+    fn typed_getters_name_the_key_on_failure() {
+        let doc = parse_json(r#"{"f": 2.0, "s": "x"}"#).unwrap();
+        assert_eq!(
+            doc.get_i64("f"),
+            Err(ParseError::WrongType {
+                key: "f".into(),
+                expected: "an integer"
+            })
+        );
+        assert_eq!(
+            doc.get_bool("s"),
+            Err(ParseError::WrongType {
+                key: "s".into(),
+                expected: "a boolean"
+            })
+        );
+        assert_eq!(
+            doc.get_str("missing"),
+            Err(ParseError::MissingField {
+                key: "missing".into()
+            })
+        );
+        assert_eq!(
+            JsonValue::Integer(1).get_required("a"),
+            Err(ParseError::MissingField { key: "a".into() })
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use super::value::JsonValue;
+
+/// A document with free-form `String` notes attached to its nodes, such as
+/// validation results. The notes live in a side table keyed by JSON Pointer,
+/// so the value itself is untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotated {
+    value: JsonValue,
+    annotations: BTreeMap<String, Vec<String>>,
+}
+
+impl Annotated {
+    pub fn new(value: JsonValue) -> Self {
+        Annotated {
+            value,
+            annotations: BTreeMap::new(),
+        }
+    }
+
+    pub fn value(&self) -> &JsonValue {
+        &self.value
+    }
+
+    /// Returns the document, dropping its annotations.
+    pub fn into_value(self) -> JsonValue {
+        self.value
+    }
+
+    /// Adds `message` to the notes of the node at `pointer`, after any it
+    /// already has. Returns false, storing nothing, if no node is there.
+    pub fn set_annotation(&mut self, pointer: &str, message: impl Into<String>) -> bool {
+        if self.value.pointer(pointer).is_none() {
+            return false;
+        }
+        self.annotations
+            .entry(pointer.to_string())
+            .or_default()
+            .push(message.into());
+        true
+    }
+
+    /// The notes of the node at `pointer` in the order added; empty if none.
+    pub fn annotations(&self, pointer: &str) -> &[String] {
+        self.annotations.get(pointer).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every annotated pointer with its notes, in pointer order.
+    pub fn all_annotations(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.annotations
+            .iter()
+            .map(|(pointer, notes)| (pointer.as_str(), notes.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn sample() -> Annotated {
+        Annotated::new(parse_json(r#"{"name": "", "items": [1, -2], "a/b": true}"#).unwrap())
+    }
+
+    #[test]
+    fn annotations_attach_to_existing_nodes_in_order() {
+        let mut doc = sample();
+        assert!(doc.set_annotation("/name", "must not be empty"));
+        assert!(doc.set_annotation("/items/1", "must be positive"));
+        assert!(doc.set_annotation("/name", "too short"));
+        assert!(doc.set_annotation("/a~1b", "checked"));
+        assert!(doc.set_annotation("", "2 errors"));
+        assert_eq!(doc.annotations("/name"), ["must not be empty", "too short"]);
+        assert_eq!(doc.annotations("/items/0"), [] as [String; 0]);
+        assert_eq!(
+            doc.all_annotations().map(|(p, _)| p).collect::<Vec<_>>(),
+            ["", "/a~1b", "/items/1", "/name"]
+        );
+    }
+
+    #[test]
+    fn annotations_on_missing_nodes_are_refused() {
+        let mut doc = sample();
+        assert!(!doc.set_annotation("/missing", "x"));
+        assert!(!doc.set_annotation("/items/2", "x"));
+        assert!(!doc.set_annotation("name", "x"));
+        assert_eq!(doc.all_annotations().count(), 0);
+        assert_eq!(doc.clone().into_value(), doc.value().clone());
+        assert_eq!(doc, sample());
+    }
+}
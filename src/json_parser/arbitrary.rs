@@ -0,0 +1,117 @@
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::value::JsonValue;
+
+// Containers stop nesting past this depth, keeping generated values small.
+const MAX_DEPTH: usize = 4;
+
+/// Generates values that survive a serialize-and-parse round trip unchanged,
+/// so `parse_json(&to_string(&v)?) == Ok(v)` can be checked directly.
+/// `Number`s are finite floats, whole ones included; those parse back as
+/// `Integer`s, which compare equal to them. Whole floats between 2^53 and
+/// 2^63 are left out, since their shortest text names a neighbouring integer
+/// rather than the float itself. `RawNumber` is never produced.
+impl<'a> Arbitrary<'a> for JsonValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, 0)
+    }
+}
+
+fn arbitrary_value(u: &mut Unstructured<'_>, depth: usize) -> Result<JsonValue> {
+    let kinds = if depth < MAX_DEPTH { 7 } else { 5 };
+    Ok(match u.choose_index(kinds)? {
+        0 => JsonValue::Null,
+        1 => JsonValue::Boolean(u.arbitrary()?),
+        2 => JsonValue::Integer(u.arbitrary()?),
+        3 => match f64::arbitrary(u)? {
+            n if round_trips(n) => JsonValue::Number(n),
+            _ => JsonValue::Integer(u.arbitrary()?),
+        },
+        4 => JsonValue::String(u.arbitrary()?),
+        5 => {
+            let len = u.arbitrary_len::<JsonValue>()?.min(8);
+            JsonValue::Array(
+                (0..len)
+                    .map(|_| arbitrary_value(u, depth + 1))
+                    .collect::<Result<_>>()?,
+            )
+        }
+        _ => {
+            let len = u.arbitrary_len::<(String, JsonValue)>()?.min(8);
+            JsonValue::Object(
+                (0..len)
+                    .map(|_| Ok((u.arbitrary()?, arbitrary_value(u, depth + 1)?)))
+                    .collect::<Result<_>>()?,
+            )
+        }
+    })
+}
+
+// Whether `n` reads back as itself, or as an `Integer` equal to it.
+fn round_trips(n: f64) -> bool {
+    n.is_finite() && (n.fract() != 0.0 || n.abs() <= 2f64.powi(53) || n.abs() >= 2f64.powi(63))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+    use crate::json_parser::serializer::to_string;
+
+    // Deterministic pseudo-random bytes, so failures reproduce.
+    fn bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn depth(value: &JsonValue) -> usize {
+        match value {
+            JsonValue::Array(array) => 1 + array.iter().map(depth).max().unwrap_or(0),
+            JsonValue::Object(object) => {
+                1 + object.iter().map(|(_, v)| depth(v)).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn generated_values_stay_within_the_depth_bound() {
+        let mut deepest = 0;
+        for seed in 0..500 {
+            let data = bytes(seed, 4096);
+            let value = JsonValue::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            assert!(depth(&value) <= MAX_DEPTH, "{value:?}");
+            deepest = deepest.max(depth(&value));
+        }
+        assert!(deepest > 1);
+    }
+
+    #[test]
+    fn generates_whole_valued_numbers() {
+        let whole = (0..500).any(|seed| {
+            let data = bytes(seed, 64);
+            let value = JsonValue::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            matches!(value, JsonValue::Number(n) if n.fract() == 0.0)
+        });
+        assert!(whole);
+    }
+
+    #[test]
+    fn generated_values_round_trip_through_text() {
+        for seed in 0..500 {
+            let data = bytes(seed, 4096);
+            let value = JsonValue::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            let text = to_string(&value).unwrap();
+            assert_eq!(parse_json(&text), Ok(value), "{text}");
+        }
+        // Exhausted input still yields a value.
+        assert!(JsonValue::arbitrary(&mut Unstructured::new(&[])).is_ok());
+    }
+}
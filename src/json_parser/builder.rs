@@ -0,0 +1,100 @@
+use std::fmt;
+
+use super::value::JsonValue;
+
+/// Builds an object with unique keys, which `JsonValue::Object` itself does
+/// not enforce. [`insert`](ObjectBuilder::insert) replaces the value of a key
+/// already present, keeping its original position; use
+/// [`try_insert`](ObjectBuilder::try_insert) to treat a repeat as a bug.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjectBuilder {
+    members: Vec<(String, JsonValue)>,
+}
+
+/// The key passed to [`ObjectBuilder::try_insert`] was already present.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateKey {
+    pub key: String,
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Duplicate key '{}'", self.key)
+    }
+}
+
+impl std::error::Error for DuplicateKey {}
+
+impl ObjectBuilder {
+    pub fn new() -> Self {
+        ObjectBuilder::default()
+    }
+
+    pub fn insert(mut self, key: impl Into<String>, value: impl Into<JsonValue>) -> Self {
+        let (key, value) = (key.into(), value.into());
+        match self.members.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => *existing = value,
+            None => self.members.push((key, value)),
+        }
+        self
+    }
+
+    /// Like [`insert`](ObjectBuilder::insert), but fails if `key` is already
+    /// present, leaving the builder unchanged.
+    pub fn try_insert(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<JsonValue>,
+    ) -> Result<&mut Self, DuplicateKey> {
+        let key = key.into();
+        if self.members.iter().any(|(k, _)| *k == key) {
+            return Err(DuplicateKey { key });
+        }
+        self.members.push((key, value.into()));
+        Ok(self)
+    }
+
+    pub fn build(self) -> JsonValue {
+        JsonValue::Object(self.members)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[test]
+    fn insert_replaces_repeated_keys_in_place() {
+        let object = ObjectBuilder::new()
+            .insert("a", 1i64)
+            .insert("b", "two")
+            .insert("a", true)
+            .insert(String::from("c"), 2.5)
+            .build();
+        assert_eq!(
+            object,
+            parse_json(r#"{"a": true, "b": "two", "c": 2.5}"#).unwrap()
+        );
+        assert_eq!(ObjectBuilder::new().build(), JsonValue::Object(vec![]));
+    }
+
+    #[test]
+    fn try_insert_rejects_repeated_keys() {
+        let mut builder = ObjectBuilder::new();
+        builder
+            .try_insert("a", 1i64)
+            .unwrap()
+            .try_insert("b", 2i64)
+            .unwrap();
+        let error = builder.try_insert("a", 3i64).unwrap_err();
+        assert_eq!(
+            error,
+            DuplicateKey {
+                key: "a".to_string()
+            }
+        );
+        assert_eq!(error.to_string(), "Duplicate key 'a'");
+        assert_eq!(builder.build(), parse_json(r#"{"a": 1, "b": 2}"#).unwrap());
+    }
+}
@@ -0,0 +1,551 @@
+use std::cmp::Ordering;
+
+use super::pointer::escape_token;
+use super::serializer::{to_string_with_options, NonFinitePolicy, SerializerOptions};
+use super::value::{JsonType, JsonValue};
+
+fn type_rank(value: &JsonValue) -> u8 {
+    match value {
+        JsonValue::Null => 0,
+        JsonValue::Boolean(_) => 1,
+        JsonValue::Integer(_) | JsonValue::Number(_) | JsonValue::RawNumber(_) => 2,
+        JsonValue::String(_) => 3,
+        JsonValue::Array(_) => 4,
+        JsonValue::Object(_) => 5,
+    }
+}
+
+fn number_rank(value: &JsonValue) -> u8 {
+    match value {
+        JsonValue::Integer(_) => 0,
+        JsonValue::Number(_) => 1,
+        _ => 2,
+    }
+}
+
+fn raw_f64(raw: &str) -> f64 {
+    raw.parse().unwrap_or(f64::NAN)
+}
+
+/// A total order over values: first by type (null, boolean, number, string,
+/// array, object), then by content. Objects compare by their key-sorted entries.
+pub fn total_cmp(a: &JsonValue, b: &JsonValue) -> Ordering {
+    match (a, b) {
+        (JsonValue::Boolean(x), JsonValue::Boolean(y)) => x.cmp(y),
+        (JsonValue::Integer(x), JsonValue::Integer(y)) => x.cmp(y),
+        (JsonValue::Number(x), JsonValue::Number(y)) => x.total_cmp(y),
+        (JsonValue::RawNumber(x), JsonValue::RawNumber(y)) => {
+            raw_f64(x).total_cmp(&raw_f64(y)).then_with(|| x.cmp(y))
+        }
+        // Mixed number representations compare by value, then by representation.
+        _ if type_rank(a) == 2 && type_rank(b) == 2 => {
+            let value = |v: &JsonValue| v.as_f64().unwrap_or(f64::NAN);
+            value(a)
+                .total_cmp(&value(b))
+                .then_with(|| number_rank(a).cmp(&number_rank(b)))
+        }
+        (JsonValue::String(x), JsonValue::String(y)) => x.cmp(y),
+        (JsonValue::Array(x), JsonValue::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| total_cmp(x, y))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            let (x, y) = (sorted_entries(x), sorted_entries(y));
+            x.iter()
+                .zip(&y)
+                .map(|((kx, vx), (ky, vy))| kx.cmp(ky).then_with(|| total_cmp(vx, vy)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| x.len().cmp(&y.len()))
+        }
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn sorted_entries(object: &[(String, JsonValue)]) -> Vec<(&String, &JsonValue)> {
+    let mut entries: Vec<_> = object.iter().map(|(k, v)| (k, v)).collect();
+    entries.sort_by(|(kx, vx), (ky, vy)| kx.cmp(ky).then_with(|| total_cmp(vx, vy)));
+    entries
+}
+
+// Sorts object keys and array elements everywhere so that order no longer matters.
+fn canonicalize(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Array(array) => {
+            let mut array: Vec<_> = array.iter().map(canonicalize).collect();
+            array.sort_by(total_cmp);
+            JsonValue::Array(array)
+        }
+        JsonValue::Object(object) => {
+            let mut object: Vec<_> = object
+                .iter()
+                .map(|(k, v)| (k.clone(), canonicalize(v)))
+                .collect();
+            object.sort_by(|(kx, vx), (ky, vy)| kx.cmp(ky).then_with(|| total_cmp(vx, vy)));
+            JsonValue::Object(object)
+        }
+        other => other.clone(),
+    }
+}
+
+/// Compares two documents treating arrays as multisets and objects as unordered.
+///
+/// Unlike `==`, which respects both array order and object key order,
+/// `[1, 2, 3]` and `[3, 1, 2]` are equal here.
+pub fn equal_unordered(a: &JsonValue, b: &JsonValue) -> bool {
+    canonicalize(a) == canonicalize(b)
+}
+
+/// Like `==`, but numbers of any representation are equal when they differ
+/// by at most `epsilon`, so `3.1400001` matches `3.14` at `1e-6`. Everything
+/// else, including array and object key order, must match exactly.
+pub fn approx_equal(a: &JsonValue, b: &JsonValue, epsilon: f64) -> bool {
+    match (a, b) {
+        (JsonValue::Array(x), JsonValue::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| approx_equal(x, y, epsilon))
+        }
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .zip(y)
+                    .all(|((kx, vx), (ky, vy))| kx == ky && approx_equal(vx, vy, epsilon))
+        }
+        _ if type_rank(a) == 2 && type_rank(b) == 2 => match (a.as_f64(), b.as_f64()) {
+            (Some(x), Some(y)) => x == y || (x - y).abs() <= epsilon,
+            _ => false,
+        },
+        _ => a == b,
+    }
+}
+
+/// Like `==`, but object members whose key is in `ignore_keys` are left out
+/// on both sides at any depth, so volatile fields such as `updated_at` may
+/// differ or be missing. The remaining members must match in order.
+pub fn equal_ignoring(a: &JsonValue, b: &JsonValue, ignore_keys: &[&str]) -> bool {
+    match (a, b) {
+        (JsonValue::Array(x), JsonValue::Array(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .zip(y)
+                    .all(|(x, y)| equal_ignoring(x, y, ignore_keys))
+        }
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            let (x, y) = (kept_members(x, ignore_keys), kept_members(y, ignore_keys));
+            x.len() == y.len()
+                && x.iter()
+                    .zip(y)
+                    .all(|((kx, vx), (ky, vy))| kx == ky && equal_ignoring(vx, vy, ignore_keys))
+        }
+        _ => a == b,
+    }
+}
+
+fn kept_members<'a>(
+    object: &'a [(String, JsonValue)],
+    ignore_keys: &[&str],
+) -> Vec<&'a (String, JsonValue)> {
+    object
+        .iter()
+        .filter(|(key, _)| !ignore_keys.contains(&key.as_str()))
+        .collect()
+}
+
+/// The top-level keys only in `a` and those only in `b`, each in source order.
+/// A non-object has no keys.
+pub fn key_diff(a: &JsonValue, b: &JsonValue) -> (Vec<String>, Vec<String>) {
+    let (mut only_a, mut only_b) = (Vec::new(), Vec::new());
+    collect_key_diff(a, b, "", false, &mut only_a, &mut only_b);
+    (only_a, only_b)
+}
+
+/// Like [`key_diff`], but also compares the members of objects present on both
+/// sides, reporting nested keys as dotted paths such as `address.zip`. Nested
+/// differences are listed before the top-level keys that follow their parent
+/// in `a`.
+pub fn key_diff_recursive(a: &JsonValue, b: &JsonValue) -> (Vec<String>, Vec<String>) {
+    let (mut only_a, mut only_b) = (Vec::new(), Vec::new());
+    collect_key_diff(a, b, "", true, &mut only_a, &mut only_b);
+    (only_a, only_b)
+}
+
+fn collect_key_diff(
+    a: &JsonValue,
+    b: &JsonValue,
+    prefix: &str,
+    recursive: bool,
+    only_a: &mut Vec<String>,
+    only_b: &mut Vec<String>,
+) {
+    let (a, b) = (a.as_object().unwrap_or(&[]), b.as_object().unwrap_or(&[]));
+    let path = |key: &str| format!("{}{}", prefix, key);
+    for (key, value) in a {
+        match b.iter().find(|(k, _)| k == key) {
+            None => only_a.push(path(key)),
+            Some((_, other)) if recursive && value.as_object().is_some() => {
+                let prefix = format!("{}.", path(key));
+                collect_key_diff(value, other, &prefix, true, only_a, only_b);
+            }
+            Some(_) => {}
+        }
+    }
+    only_b.extend(
+        b.iter()
+            .filter(|(key, _)| !a.iter().any(|(k, _)| k == key))
+            .map(|(key, _)| path(key)),
+    );
+}
+
+/// The keys present in both `a` and `b`, as dotted paths in the order of `a`.
+/// Objects under a shared key are compared member by member, so
+/// `address.city` is listed after `address` when both sides have it. Arrays
+/// are not descended into.
+pub fn common_keys(a: &JsonValue, b: &JsonValue) -> Vec<String> {
+    let mut common = Vec::new();
+    collect_common_keys(a, b, "", &mut common);
+    common
+}
+
+fn collect_common_keys(a: &JsonValue, b: &JsonValue, prefix: &str, common: &mut Vec<String>) {
+    let (a, b) = (a.as_object().unwrap_or(&[]), b.as_object().unwrap_or(&[]));
+    for (key, value) in a {
+        if let Some((_, other)) = b.iter().find(|(k, _)| k == key) {
+            let path = format!("{}{}", prefix, key);
+            common.push(path.clone());
+            collect_common_keys(value, other, &format!("{}.", path), common);
+        }
+    }
+}
+
+/// A line diff of the pretty-printed documents for test failure output:
+/// every line of either side, prefixed `-` if only in `a`, `+` if only in
+/// `b` and a space if in both, as in a unified diff without hunk headers.
+/// [`create_patch`](super::patch::create_patch) is the structured counterpart.
+pub fn diff_text(a: &JsonValue, b: &JsonValue) -> String {
+    let options = SerializerOptions::pretty()
+        .max_depth(None)
+        .non_finite(NonFinitePolicy::Null);
+    let (a, b) = (
+        to_string_with_options(a, &options).unwrap_or_default(),
+        to_string_with_options(b, &options).unwrap_or_default(),
+    );
+    let (a, b): (Vec<_>, Vec<_>) = (a.lines().collect(), b.lines().collect());
+    // common[i][j] is the length of the longest common subsequence of a[i..] and b[j..].
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let mut out = String::new();
+    let mut line = |prefix: char, text: &str| {
+        out.push(prefix);
+        out.push_str(text);
+        out.push('\n');
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            line(' ', a[i]);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && common[i + 1][j] >= common[i][j + 1]) {
+            line('-', a[i]);
+            i += 1;
+        } else {
+            line('+', b[j]);
+            j += 1;
+        }
+    }
+    out
+}
+
+/// A breaking change found by [`schema_compatible`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incompatibility {
+    /// The member at this JSON Pointer exists in the old document only.
+    Removed { path: String },
+    /// The value at this JSON Pointer is of a different type.
+    TypeChanged {
+        path: String,
+        old: JsonType,
+        new: JsonType,
+    },
+}
+
+/// Reports the changes from `old` to `new` that could break a consumer of
+/// documents shaped like `old`: members that were removed and values whose
+/// type changed, including to or from `null`. Added members are compatible.
+/// Arrays are taken to hold elements of one shape, so only their first
+/// elements are compared, at index `0`, when both have one. Integers and
+/// floats are both numbers.
+pub fn schema_compatible(old: &JsonValue, new: &JsonValue) -> Vec<Incompatibility> {
+    let mut incompatibilities = Vec::new();
+    compare_schema_at(old, new, &mut String::new(), &mut incompatibilities);
+    incompatibilities
+}
+
+fn compare_schema_at(
+    old: &JsonValue,
+    new: &JsonValue,
+    path: &mut String,
+    incompatibilities: &mut Vec<Incompatibility>,
+) {
+    match (old, new) {
+        (JsonValue::Object(old), JsonValue::Object(new)) => {
+            for (key, value) in old {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_token(key));
+                match new.iter().find(|(k, _)| k == key) {
+                    Some((_, other)) => compare_schema_at(value, other, path, incompatibilities),
+                    None => incompatibilities.push(Incompatibility::Removed { path: path.clone() }),
+                }
+                path.truncate(len);
+            }
+        }
+        (JsonValue::Array(old), JsonValue::Array(new)) => {
+            if let (Some(old), Some(new)) = (old.first(), new.first()) {
+                let len = path.len();
+                path.push_str("/0");
+                compare_schema_at(old, new, path, incompatibilities);
+                path.truncate(len);
+            }
+        }
+        _ if old.json_type() != new.json_type() => {
+            incompatibilities.push(Incompatibility::TypeChanged {
+                path: path.clone(),
+                old: old.json_type(),
+                new: new.json_type(),
+            })
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn equal_unordered_ignores_array_and_key_order() {
+        assert!(equal_unordered(
+            &json(r#"{"a": [1, 2, [3, 4]], "b": {"x": 1, "y": 2}}"#),
+            &json(r#"{"b": {"y": 2, "x": 1}, "a": [[4, 3], 2, 1]}"#),
+        ));
+        assert!(!equal_unordered(&json("[1, 1, 2]"), &json("[1, 2, 2]")));
+        assert!(!equal_unordered(&json("[1, 2]"), &json("[1, 2, 3]")));
+        assert!(!equal_unordered(
+            &json(r#"{"a": 1}"#),
+            &json(r#"{"a": "1"}"#)
+        ));
+    }
+
+    #[test]
+    fn total_cmp_orders_across_types_and_nan() {
+        let mut values = [
+            json(r#"{"a": 1}"#),
+            json(r#""s""#),
+            JsonValue::Number(f64::NAN),
+            json("[1]"),
+            json("true"),
+            JsonValue::Number(1.5),
+            json("null"),
+            json("1"),
+        ];
+        values.sort_by(total_cmp);
+        assert_eq!(values[0], JsonValue::Null);
+        assert_eq!(values[1], JsonValue::Boolean(true));
+        assert_eq!(values[2], JsonValue::Integer(1));
+        assert_eq!(values[3], JsonValue::Number(1.5));
+        assert!(matches!(values[4], JsonValue::Number(n) if n.is_nan()));
+        assert_eq!(values[5], JsonValue::String("s".into()));
+        assert_eq!(values[6], json("[1]"));
+        assert_eq!(values[7], json(r#"{"a": 1}"#));
+
+        let nan = JsonValue::Number(f64::NAN);
+        assert_eq!(total_cmp(&nan, &nan), Ordering::Equal);
+        assert_eq!(
+            total_cmp(&JsonValue::Integer(1), &JsonValue::Number(1.0)),
+            Ordering::Less
+        );
+        assert_eq!(
+            total_cmp(&json(r#"{"b": 1, "a": 2}"#), &json(r#"{"a": 2, "b": 1}"#)),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn key_diff_compares_top_level_keys() {
+        let a = json(r#"{"id": 1, "name": "a", "address": {"zip": 1}}"#);
+        let b = json(r#"{"email": "e", "id": 2, "address": {"city": "c"}}"#);
+        assert_eq!(
+            key_diff(&a, &b),
+            (vec!["name".to_string()], vec!["email".to_string()])
+        );
+        assert_eq!(
+            key_diff_recursive(&a, &b),
+            (
+                vec!["name".to_string(), "address.zip".to_string()],
+                vec!["address.city".to_string(), "email".to_string()]
+            )
+        );
+        assert_eq!(key_diff(&a, &a), (vec![], vec![]));
+        assert_eq!(
+            key_diff(&json("[1]"), &json(r#"{"x": 1}"#)),
+            (vec![], vec!["x".to_string()])
+        );
+    }
+
+    #[test]
+    fn approx_equal_tolerates_small_numeric_differences() {
+        let a = json(r#"{"pi": 3.1400001, "n": [1, 2.0000001], "s": "x"}"#);
+        let b = json(r#"{"pi": 3.14, "n": [1.0, 2], "s": "x"}"#);
+        assert!(approx_equal(&a, &b, 1e-6));
+        assert!(!approx_equal(&a, &b, 1e-9));
+        let raw = JsonValue::RawNumber("10.0000000001".to_string());
+        assert!(approx_equal(&raw, &json("10"), 1e-6));
+        let inf = JsonValue::Number(f64::INFINITY);
+        assert!(approx_equal(&inf, &inf, 0.0));
+    }
+
+    #[test]
+    fn approx_equal_is_exact_for_everything_else() {
+        assert!(!approx_equal(
+            &json(r#"["a", 1]"#),
+            &json(r#"[1, "a"]"#),
+            10.0
+        ));
+        assert!(!approx_equal(
+            &json(r#"{"a": 1, "b": 2}"#),
+            &json(r#"{"b": 2, "a": 1}"#),
+            0.1
+        ));
+        assert!(!approx_equal(&json(r#""1""#), &json("1"), 1.0));
+        assert!(!approx_equal(&json("[1]"), &json("[1, 1]"), 1.0));
+        assert!(!approx_equal(&json("true"), &json("1"), 1.0));
+    }
+
+    #[test]
+    fn common_keys_lists_shared_paths_in_the_order_of_a() {
+        let a = json(
+            r#"{"name": 1, "address": {"city": "x", "zip": 1}, "only_a": 2, "tags": [{"t": 1}]}"#,
+        );
+        let b = json(r#"{"tags": [{"t": 1}], "address": {"zip": 2, "street": "y"}, "name": "n"}"#);
+        assert_eq!(
+            common_keys(&a, &b),
+            ["name", "address", "address.zip", "tags"]
+        );
+    }
+
+    #[test]
+    fn common_keys_stops_where_either_side_is_not_an_object() {
+        let a = json(r#"{"address": {"city": "x"}}"#);
+        let b = json(r#"{"address": "Main St"}"#);
+        assert_eq!(common_keys(&a, &b), ["address"]);
+        assert!(common_keys(&json("[1]"), &json("[1]")).is_empty());
+        assert!(common_keys(&a, &json("{}")).is_empty());
+    }
+
+    #[test]
+    fn equal_ignoring_skips_volatile_keys_at_any_depth() {
+        let a = json(r#"{"id": 1, "updated_at": "mon", "items": [{"n": 1, "updated_at": 5}]}"#);
+        let b = json(r#"{"id": 1, "items": [{"updated_at": 6, "n": 1}], "updated_at": "tue"}"#);
+        assert!(equal_ignoring(&a, &b, &["updated_at"]));
+        assert!(!equal_ignoring(&a, &b, &[]));
+        assert!(equal_ignoring(&json("[1, 2]"), &json("[1, 2]"), &["x"]));
+    }
+
+    #[test]
+    fn equal_ignoring_still_compares_everything_else() {
+        let a = json(r#"{"id": 1, "ts": 1, "name": "a"}"#);
+        assert!(!equal_ignoring(
+            &a,
+            &json(r#"{"id": 2, "ts": 1, "name": "a"}"#),
+            &["ts"]
+        ));
+        assert!(!equal_ignoring(
+            &a,
+            &json(r#"{"name": "a", "id": 1}"#),
+            &["ts"]
+        ));
+        assert!(!equal_ignoring(&a, &json(r#"{"id": 1}"#), &["ts"]));
+        assert!(!equal_ignoring(&json("[1, 2]"), &json("[2, 1]"), &[]));
+    }
+
+    #[test]
+    fn diff_text_marks_removed_and_added_lines() {
+        let a = json(r#"{"name": "Ann", "age": 30}"#);
+        let b = json(r#"{"name": "Ann", "age": 31}"#);
+        assert_eq!(
+            diff_text(&a, &b),
+            " {\n   \"name\": \"Ann\",\n-  \"age\": 30\n+  \"age\": 31\n }\n"
+        );
+    }
+
+    #[test]
+    fn diff_text_of_equal_documents_has_no_changes() {
+        let a = json(r#"[1, {"x": [true]}]"#);
+        let diff = diff_text(&a, &a);
+        assert!(diff.lines().all(|line| line.starts_with(' ')), "{diff}");
+        assert_eq!(diff.lines().count(), 8);
+        assert_eq!(diff_text(&json("[]"), &json("null")), "-[]\n+null\n");
+    }
+
+    #[test]
+    fn schema_compatible_reports_removed_members_and_type_changes() {
+        let old =
+            json(r#"{"id": 1, "name": "a", "a/b": true, "tags": [{"x": 1}], "meta": {"v": null}}"#);
+        assert_eq!(schema_compatible(&old, &old), vec![]);
+        let new = json(
+            r#"{"id": 1.5, "tags": [{"x": "1"}], "a/b": "yes", "meta": {"v": 2}, "added": 0}"#,
+        );
+        assert_eq!(
+            schema_compatible(&old, &new),
+            vec![
+                Incompatibility::Removed {
+                    path: "/name".into()
+                },
+                Incompatibility::TypeChanged {
+                    path: "/a~1b".into(),
+                    old: JsonType::Boolean,
+                    new: JsonType::String,
+                },
+                Incompatibility::TypeChanged {
+                    path: "/tags/0/x".into(),
+                    old: JsonType::Number,
+                    new: JsonType::String,
+                },
+                Incompatibility::TypeChanged {
+                    path: "/meta/v".into(),
+                    old: JsonType::Null,
+                    new: JsonType::Number,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_compatible_compares_only_first_array_elements() {
+        let old = json(r#"{"items": [1, "two"]}"#);
+        assert_eq!(schema_compatible(&old, &json(r#"{"items": [3]}"#)), vec![]);
+        assert_eq!(schema_compatible(&old, &json(r#"{"items": []}"#)), vec![]);
+        assert_eq!(
+            schema_compatible(&old, &json(r#"{"items": {}}"#)),
+            vec![Incompatibility::TypeChanged {
+                path: "/items".into(),
+                old: JsonType::Array,
+                new: JsonType::Object,
+            }]
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use std::fmt;
+
+use super::error::{line_col, ParseError};
+use super::parser::parse_json;
+use super::value::JsonValue;
+
+/// Whitespace or a comment, kept verbatim so the tree can be written back unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    LineComment(String),
+    BlockComment(String),
+}
+
+/// A value together with the trivia immediately before and after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstNode {
+    pub leading: Vec<Trivia>,
+    pub kind: CstKind,
+    pub trailing: Vec<Trivia>,
+}
+
+/// Scalars keep their source text; `dangling` holds trivia inside an empty container.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstKind {
+    Null,
+    Boolean(bool),
+    Number(String),
+    String(String),
+    Array {
+        elements: Vec<CstNode>,
+        dangling: Vec<Trivia>,
+    },
+    Object {
+        members: Vec<CstMember>,
+        dangling: Vec<Trivia>,
+    },
+}
+
+/// An object member; trivia after the colon belongs to `value.leading`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstMember {
+    pub leading: Vec<Trivia>,
+    pub key: String,
+    pub before_colon: Vec<Trivia>,
+    pub value: CstNode,
+}
+
+/// A lossless syntax tree: `to_string()` reproduces the parsed input exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cst {
+    pub root: CstNode,
+}
+
+impl Cst {
+    pub fn to_value(&self) -> Result<JsonValue, ParseError> {
+        self.root.to_value()
+    }
+}
+
+impl CstNode {
+    pub fn to_value(&self) -> Result<JsonValue, ParseError> {
+        match &self.kind {
+            CstKind::Null => Ok(JsonValue::Null),
+            CstKind::Boolean(b) => Ok(JsonValue::Boolean(*b)),
+            CstKind::Number(raw) | CstKind::String(raw) => parse_json(raw),
+            CstKind::Array { elements, .. } => elements
+                .iter()
+                .map(CstNode::to_value)
+                .collect::<Result<_, _>>()
+                .map(JsonValue::Array),
+            CstKind::Object { members, .. } => {
+                let mut object = Vec::new();
+                for member in members {
+                    let key = match parse_json(&member.key)? {
+                        JsonValue::String(key) => key,
+                        _ => unreachable!("object keys are scanned as strings"),
+                    };
+                    object.push((key, member.value.to_value()?));
+                }
+                Ok(JsonValue::Object(object))
+            }
+        }
+    }
+}
+
+struct CstParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> CstParser<'a> {
+    fn new(input: &'a str) -> Self {
+        CstParser { input, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::syntax_at(self.input, self.pos, message)
+    }
+
+    fn mismatched(&self, expected: char, found: char) -> ParseError {
+        let (line, column) = line_col(self.input, self.pos);
+        ParseError::MismatchedBracket {
+            expected,
+            found,
+            line,
+            column,
+        }
+    }
+
+    fn trivia(&mut self) -> Result<Vec<Trivia>, ParseError> {
+        let mut trivia = Vec::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let (len, item) = if rest.starts_with("//") {
+                let len = rest.find('\n').unwrap_or(rest.len());
+                (len, Trivia::LineComment(rest[..len].to_string()))
+            } else if let Some(body) = rest.strip_prefix("/*") {
+                let len = match body.find("*/") {
+                    Some(i) => i + 4,
+                    None => return Err(self.error("Unterminated block comment")),
+                };
+                (len, Trivia::BlockComment(rest[..len].to_string()))
+            } else {
+                let len = rest.len() - rest.trim_start_matches(char::is_whitespace).len();
+                if len == 0 {
+                    return Ok(trivia);
+                }
+                (len, Trivia::Whitespace(rest[..len].to_string()))
+            };
+            self.pos += len;
+            trivia.push(item);
+        }
+    }
+
+    fn parse_node(&mut self) -> Result<CstNode, ParseError> {
+        let leading = self.trivia()?;
+        self.parse_node_after(leading)
+    }
+
+    fn parse_node_after(&mut self, leading: Vec<Trivia>) -> Result<CstNode, ParseError> {
+        let kind = self.parse_kind()?;
+        let trailing = self.trivia()?;
+        Ok(CstNode {
+            leading,
+            kind,
+            trailing,
+        })
+    }
+
+    fn parse_kind(&mut self) -> Result<CstKind, ParseError> {
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') => self.parse_string().map(CstKind::String),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            Some(b't') => self.parse_literal("true", CstKind::Boolean(true)),
+            Some(b'f') => self.parse_literal("false", CstKind::Boolean(false)),
+            Some(b'n') => self.parse_literal("null", CstKind::Null),
+            Some(_) => {
+                let c = self.input[self.pos..].chars().next().unwrap();
+                Err(self.error(format!("Unexpected character: {}", c)))
+            }
+            None => Err(ParseError::UnexpectedEof),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<CstKind, ParseError> {
+        self.pos += 1; // Consume '{'
+        let mut members = Vec::new();
+        let mut leading = self.trivia()?;
+        match self.peek() {
+            Some(b'}') => {
+                self.pos += 1;
+                return Ok(CstKind::Object {
+                    members,
+                    dangling: leading,
+                });
+            }
+            Some(b']') => return Err(self.mismatched('}', ']')),
+            _ => {}
+        }
+
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expected string key in object"));
+            }
+            let key = self.parse_string()?;
+            let before_colon = self.trivia()?;
+            if self.peek() != Some(b':') {
+                return Err(self.error("Expected ':' in object"));
+            }
+            self.pos += 1;
+
+            let value = self.parse_node()?;
+            members.push(CstMember {
+                leading,
+                key,
+                before_colon,
+                value,
+            });
+
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(CstKind::Object {
+                        members,
+                        dangling: Vec::new(),
+                    });
+                }
+                Some(b']') => return Err(self.mismatched('}', ']')),
+                _ => return Err(self.error("Expected ',' or '}' in object")),
+            }
+            leading = self.trivia()?;
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<CstKind, ParseError> {
+        self.pos += 1; // Consume '['
+        let mut elements = Vec::new();
+        let mut leading = self.trivia()?;
+        match self.peek() {
+            Some(b']') => {
+                self.pos += 1;
+                return Ok(CstKind::Array {
+                    elements,
+                    dangling: leading,
+                });
+            }
+            Some(b'}') => return Err(self.mismatched(']', '}')),
+            _ => {}
+        }
+
+        loop {
+            elements.push(self.parse_node_after(leading)?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(CstKind::Array {
+                        elements,
+                        dangling: Vec::new(),
+                    });
+                }
+                Some(b'}') => return Err(self.mismatched(']', '}')),
+                _ => return Err(self.error("Expected ',' or ']' in array")),
+            }
+            leading = self.trivia()?;
+        }
+    }
+
+    // Scalars are delimited here and then validated by the value parser, so
+    // both trees agree on what is well-formed.
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        self.pos += 1; // Consume opening '"'
+        loop {
+            match self.peek() {
+                Some(b'"') => break,
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => {
+                    let (line, column) = line_col(self.input, start);
+                    return Err(ParseError::UnterminatedString { line, column });
+                }
+            }
+        }
+        self.pos += 1;
+        self.validate(start)
+    }
+
+    fn parse_number(&mut self) -> Result<CstKind, ParseError> {
+        let start = self.pos;
+        while let Some(b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-') = self.peek() {
+            self.pos += 1;
+        }
+        self.validate(start).map(CstKind::Number)
+    }
+
+    fn validate(&self, start: usize) -> Result<String, ParseError> {
+        let raw = &self.input[start..self.pos];
+        match parse_json(raw) {
+            Ok(_) => Ok(raw.to_string()),
+            Err(ParseError::Syntax { message, .. }) => {
+                Err(ParseError::syntax_at(self.input, start, message))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, kind: CstKind) -> Result<CstKind, ParseError> {
+        if self.input[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(kind)
+        } else {
+            Err(self.error(format!("Expected '{}'", literal)))
+        }
+    }
+}
+
+/// Parses `input` into a [`Cst`], accepting `//` and `/* */` comments as trivia.
+pub fn parse_cst(input: &str) -> Result<Cst, ParseError> {
+    let mut parser = CstParser::new(input);
+    let leading = parser.trivia()?;
+    if parser.pos == input.len() {
+        return Err(ParseError::EmptyInput);
+    }
+    let root = parser.parse_node_after(leading)?;
+    if parser.pos < input.len() {
+        Err(ParseError::trailing_at(input, parser.pos))
+    } else {
+        Ok(Cst { root })
+    }
+}
+
+impl fmt::Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trivia::Whitespace(text) | Trivia::LineComment(text) | Trivia::BlockComment(text) => {
+                f.write_str(text)
+            }
+        }
+    }
+}
+
+fn write_trivia(f: &mut fmt::Formatter<'_>, trivia: &[Trivia]) -> fmt::Result {
+    trivia.iter().try_for_each(|t| write!(f, "{}", t))
+}
+
+impl fmt::Display for CstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_trivia(f, &self.leading)?;
+        match &self.kind {
+            CstKind::Null => f.write_str("null")?,
+            CstKind::Boolean(b) => write!(f, "{}", b)?,
+            CstKind::Number(raw) | CstKind::String(raw) => f.write_str(raw)?,
+            CstKind::Array { elements, dangling } => {
+                f.write_str("[")?;
+                write_trivia(f, dangling)?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                f.write_str("]")?;
+            }
+            CstKind::Object { members, dangling } => {
+                f.write_str("{")?;
+                write_trivia(f, dangling)?;
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_trivia(f, &member.leading)?;
+                    f.write_str(&member.key)?;
+                    write_trivia(f, &member.before_colon)?;
+                    write!(f, ":{}", member.value)?;
+                }
+                f.write_str("}")?;
+            }
+        }
+        write_trivia(f, &self.trailing)
+    }
+}
+
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::options::ParserOptions;
+    use crate::json_parser::parser::{parse_json, parse_json_with_options};
+
+    #[test]
+    fn display_reproduces_the_input_exactly() {
+        let input = "// config\n{ \"a\" : [1, 2 /* two */],\n  \"b\": null }\n";
+        let cst = parse_cst(input).unwrap();
+        assert_eq!(cst.to_string(), input);
+        assert_eq!(
+            cst.to_value().unwrap(),
+            parse_json(r#"{"a": [1, 2], "b": null}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trips_nested_containers_escapes_and_comments() {
+        let inputs = [
+            "[[], {}, [[1, [2]], {\"a\": {\"b\": [true, false]}}]]",
+            "{\"q\\\"uote\": \"tab\\t\\u00e9\\\\\", \"\\u0041\": \"/\\/\"}",
+            "[ /* empty */ ]",
+            "{ // nothing\n}",
+            "{\"a\" /* key */ : /* value */ 1 // after\n, \"b\":[-0.5e+3 ,\n\t2]}",
+            "\n\t /* leading */ // more\n  null  // trailing\n\n",
+            "  \"lone string\"  ",
+        ];
+        let comments = ParserOptions::default().allow_comments(true);
+        for input in inputs {
+            let cst = parse_cst(input).unwrap();
+            assert_eq!(cst.to_string(), input);
+            assert_eq!(
+                cst.to_value(),
+                parse_json_with_options(input, &comments),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn trivia_is_attached_to_the_surrounding_node() {
+        let cst = parse_cst(" /* a */ [ 1 ] // b\n").unwrap();
+        assert_eq!(
+            cst.root.leading,
+            [
+                Trivia::Whitespace(" ".to_string()),
+                Trivia::BlockComment("/* a */".to_string()),
+                Trivia::Whitespace(" ".to_string()),
+            ]
+        );
+        assert_eq!(
+            cst.root.trailing,
+            [
+                Trivia::Whitespace(" ".to_string()),
+                Trivia::LineComment("// b".to_string()),
+                Trivia::Whitespace("\n".to_string()),
+            ]
+        );
+        let CstKind::Array { elements, dangling } = &cst.root.kind else {
+            panic!("expected an array");
+        };
+        assert!(dangling.is_empty());
+        assert_eq!(elements[0].leading, [Trivia::Whitespace(" ".to_string())]);
+        assert_eq!(elements[0].kind, CstKind::Number("1".to_string()));
+        let empty = parse_cst("{ /* none */ }").unwrap();
+        assert!(matches!(
+            &empty.root.kind,
+            CstKind::Object { members, dangling } if members.is_empty() && dangling.len() == 3
+        ));
+    }
+
+    #[test]
+    fn unterminated_block_comment_is_rejected() {
+        assert_eq!(
+            parse_cst("[1,\n  /* never closed"),
+            Err(ParseError::Syntax {
+                message: "Unterminated block comment".to_string(),
+                line: 2,
+                column: 3,
+            })
+        );
+    }
+}
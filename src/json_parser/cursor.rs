@@ -0,0 +1,110 @@
+use super::pointer::escape_token;
+use super::value::JsonValue;
+
+/// A position inside a document that can move down into children and back up,
+/// keeping track of its JSON Pointer as it goes.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    root: &'a JsonValue,
+    // The escaped pointer token and value of each level entered, outermost first.
+    stack: Vec<(String, &'a JsonValue)>,
+}
+
+impl<'a> Cursor<'a> {
+    /// Starts at the root of `root`.
+    pub fn new(root: &'a JsonValue) -> Self {
+        Cursor {
+            root,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn current(&self) -> &'a JsonValue {
+        self.stack.last().map_or(self.root, |(_, value)| *value)
+    }
+
+    /// The JSON Pointer of the current value; the root is `""`.
+    pub fn path(&self) -> String {
+        self.stack
+            .iter()
+            .map(|(token, _)| format!("/{}", token))
+            .collect()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Moves to the member `key` of the current object. Returns false, leaving
+    /// the cursor where it was, if there is no such member.
+    pub fn enter_key(&mut self, key: &str) -> bool {
+        match self.current().get(key) {
+            Some(child) => {
+                self.stack.push((escape_token(key), child));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves to element `index` of the current array. Returns false, leaving
+    /// the cursor where it was, if there is no such element.
+    pub fn enter_index(&mut self, index: usize) -> bool {
+        match self.current().get_index(index) {
+            Some(child) => {
+                self.stack.push((index.to_string(), child));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves up one level. Returns false if the cursor is already at the root.
+    pub fn parent(&mut self) -> bool {
+        self.stack.pop().is_some()
+    }
+
+    /// Moves back to the root.
+    pub fn to_root(&mut self) {
+        self.stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[test]
+    fn cursor_moves_down_and_up_tracking_the_pointer() {
+        let doc = parse_json(r#"{"a/b": [10, {"c": true}]}"#).unwrap();
+        let mut cursor = Cursor::new(&doc);
+        assert_eq!(cursor.path(), "");
+        assert!(cursor.enter_key("a/b"));
+        assert!(cursor.enter_index(1));
+        assert!(cursor.enter_key("c"));
+        assert_eq!(cursor.path(), "/a~1b/1/c");
+        assert_eq!(cursor.depth(), 3);
+        assert_eq!(cursor.current(), &JsonValue::Boolean(true));
+        assert_eq!(doc.pointer(&cursor.path()), Some(cursor.current()));
+
+        assert!(cursor.parent());
+        assert_eq!(cursor.path(), "/a~1b/1");
+        cursor.to_root();
+        assert_eq!(cursor.current(), &doc);
+        assert!(!cursor.parent());
+    }
+
+    #[test]
+    fn cursor_stays_put_on_failed_moves() {
+        let doc = parse_json(r#"{"a": [1]}"#).unwrap();
+        let mut cursor = Cursor::new(&doc);
+        assert!(!cursor.enter_key("missing"));
+        assert!(!cursor.enter_index(0));
+        assert!(cursor.enter_key("a"));
+        assert!(!cursor.enter_index(1));
+        assert!(!cursor.enter_key("0"));
+        assert_eq!(cursor.path(), "/a");
+        assert_eq!(cursor.depth(), 1);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::pointer::escape_token;
+use super::value::JsonValue;
+
+/// An RFC 3339 timestamp such as `2020-01-02T03:04:05Z`, as recognised in
+/// string values. Fields are kept as written, without normalizing to UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    /// Up to 60, to allow for leap seconds.
+    pub second: u8,
+    pub nanosecond: u32,
+    /// Offset from UTC in minutes; `Z` is 0.
+    pub offset_minutes: i16,
+}
+
+impl DateTime {
+    /// Parses `date-time` from RFC 3339, section 5.6. Fractional seconds beyond
+    /// nanosecond precision are truncated.
+    pub fn parse(s: &str) -> Option<DateTime> {
+        let b = s.as_bytes();
+        if b.len() < 20
+            || b[4] != b'-'
+            || b[7] != b'-'
+            || !matches!(b[10], b'T' | b't')
+            || b[13] != b':'
+            || b[16] != b':'
+        {
+            return None;
+        }
+        let year = digits(&b[0..4])? as u16;
+        let month = digits(&b[5..7])? as u8;
+        let day = digits(&b[8..10])? as u8;
+        let hour = digits(&b[11..13])? as u8;
+        let minute = digits(&b[14..16])? as u8;
+        let second = digits(&b[17..19])? as u8;
+        if !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return None;
+        }
+
+        let mut rest = &b[19..];
+        let mut nanosecond = 0;
+        if let Some(fraction) = rest.strip_prefix(b".") {
+            let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+            if len == 0 {
+                return None;
+            }
+            let kept = &fraction[..len.min(9)];
+            nanosecond = digits(kept)? * 10u32.pow(9 - kept.len() as u32);
+            rest = &fraction[len..];
+        }
+
+        let offset_minutes = match rest {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+                let (hours, minutes) = (digits(&[*h1, *h2])?, digits(&[*m1, *m2])?);
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                let offset = (hours * 60 + minutes) as i16;
+                if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            _ => return None,
+        };
+
+        Some(DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond,
+            offset_minutes,
+        })
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.nanosecond > 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        match self.offset_minutes {
+            0 => write!(f, "Z"),
+            offset => write!(
+                f,
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.unsigned_abs() / 60,
+                offset.unsigned_abs() % 60
+            ),
+        }
+    }
+}
+
+// Whether `Display` would say the same as `s`, a timestamp `DateTime::parse`
+// accepted, which truncates the fraction and reads `-00:00` as `Z`.
+fn canonical_is_lossless(s: &str) -> bool {
+    let fraction = s[19..].strip_prefix('.').unwrap_or("");
+    let digits = &fraction[..fraction.bytes().take_while(u8::is_ascii_digit).count()];
+    digits.trim_end_matches('0').len() <= 9 && !s.ends_with("-00:00")
+}
+
+fn digits(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0, |n, &c| {
+        c.is_ascii_digit().then(|| n * 10 + u32::from(c - b'0'))
+    })
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl JsonValue {
+    /// The string as an RFC 3339 timestamp, if it is one.
+    pub fn as_datetime(&self) -> Option<DateTime> {
+        self.as_str().and_then(DateTime::parse)
+    }
+
+    /// Finds every string in the document that is an RFC 3339 timestamp,
+    /// keyed by JSON Pointer. The document itself is left alone: timestamps are
+    /// reported alongside it rather than stored in a new variant, so code that
+    /// matches on `JsonValue` is unaffected.
+    pub fn find_dates(&self) -> BTreeMap<String, DateTime> {
+        let mut dates = BTreeMap::new();
+        self.find_dates_at(&mut String::new(), &mut dates);
+        dates
+    }
+
+    /// Rewrites every RFC 3339 timestamp string in place in the canonical form
+    /// written by `DateTime`'s `Display`, so equal instants written alike
+    /// compare equal as text: `2020-01-02t03:04:05.500z` becomes
+    /// `2020-01-02T03:04:05.5Z`. Offsets are kept, not converted to UTC.
+    /// Timestamps that the canonical form would change in meaning are left as
+    /// written: fractions with more than nine significant digits, and the
+    /// `-00:00` offset, which RFC 3339 uses for an unknown local offset rather
+    /// than UTC. Returns every timestamp found, rewritten or not, as
+    /// [`find_dates`](JsonValue::find_dates) does, to tag them for typed
+    /// processing. Other strings are left alone.
+    pub fn coerce_dates(&mut self) -> BTreeMap<String, DateTime> {
+        let dates = self.find_dates();
+        for (pointer, date) in &dates {
+            if let Some(value) = self.pointer_mut(pointer) {
+                if value.as_str().is_some_and(canonical_is_lossless) {
+                    *value = JsonValue::String(date.to_string());
+                }
+            }
+        }
+        dates
+    }
+
+    fn find_dates_at(&self, pointer: &mut String, dates: &mut BTreeMap<String, DateTime>) {
+        let mut visit = |token: &str, child: &JsonValue| {
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(token);
+            child.find_dates_at(pointer, dates);
+            pointer.truncate(len);
+        };
+        match self {
+            JsonValue::Array(array) => {
+                for (i, child) in array.iter().enumerate() {
+                    visit(&i.to_string(), child);
+                }
+            }
+            JsonValue::Object(object) => {
+                for (key, child) in object {
+                    visit(&escape_token(key), child);
+                }
+            }
+            value => {
+                if let Some(date) = value.as_datetime() {
+                    dates.insert(pointer.clone(), date);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[test]
+    fn parse_accepts_rfc_3339_and_rejects_near_misses() {
+        assert_eq!(
+            DateTime::parse("2020-01-02T03:04:05.25+05:30"),
+            Some(DateTime {
+                year: 2020,
+                month: 1,
+                day: 2,
+                hour: 3,
+                minute: 4,
+                second: 5,
+                nanosecond: 250_000_000,
+                offset_minutes: 330,
+            })
+        );
+        assert!(DateTime::parse("2024-02-29T00:00:60Z").is_some());
+        for input in [
+            "2023-02-29T00:00:00Z",
+            "2020-01-02 03:04:05Z",
+            "2020-01-02T03:04:05",
+            "2020-13-02T03:04:05Z",
+            "2020-01-02T24:00:00Z",
+            "2020-01-02T03:04:05.Z",
+            "2020-01-02T03:04:05+5:30",
+            "2020-01-02",
+        ] {
+            assert_eq!(DateTime::parse(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn coerce_dates_canonicalizes_timestamps_in_place() {
+        let mut doc = parse_json(
+            r#"{"at": "2020-01-02T03:04:05Z", "log": [{"t": "2020-01-02t03:04:05.500z"}],
+                "off": "2020-01-02T03:04:05-08:00", "name": "2020", "n": 20200102}"#,
+        )
+        .unwrap();
+        let dates = doc.coerce_dates();
+        assert_eq!(
+            doc,
+            parse_json(
+                r#"{"at": "2020-01-02T03:04:05Z", "log": [{"t": "2020-01-02T03:04:05.5Z"}],
+                    "off": "2020-01-02T03:04:05-08:00", "name": "2020", "n": 20200102}"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            dates.keys().collect::<Vec<_>>(),
+            ["/at", "/log/0/t", "/off"]
+        );
+        assert_eq!(dates["/off"].offset_minutes, -480);
+        assert_eq!(dates, doc.find_dates());
+    }
+
+    #[test]
+    fn coerce_dates_leaves_timestamps_it_cannot_rewrite_exactly() {
+        let input = r#"["2020-01-02T03:04:05.1234567891Z", "2020-01-02T03:04:05-00:00",
+            "2020-01-02T03:04:05.1234567890000z", "2020-01-02T03:04:05+00:00",
+            "2020-01-02T03:04:05.1234567891+05:30"]"#;
+        let mut doc = parse_json(input).unwrap();
+        let dates = doc.coerce_dates();
+        assert_eq!(
+            doc,
+            parse_json(
+                r#"["2020-01-02T03:04:05.1234567891Z", "2020-01-02T03:04:05-00:00",
+                    "2020-01-02T03:04:05.123456789Z", "2020-01-02T03:04:05Z",
+                    "2020-01-02T03:04:05.1234567891+05:30"]"#
+            )
+            .unwrap()
+        );
+        assert_eq!(dates.len(), 5);
+        assert_eq!(dates["/0"].nanosecond, 123_456_789);
+        assert_eq!(dates["/1"].offset_minutes, 0);
+    }
+}
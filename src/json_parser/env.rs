@@ -0,0 +1,113 @@
+use super::error::ParseError;
+use super::serializer::{to_string_with_options, NonFinitePolicy, SerializerOptions};
+use super::value::JsonValue;
+
+/// Flattens a configuration object into environment variables such as
+/// `APP__DATABASE__PORT=5432` for `prefix` `APP` and `sep` `__`. Keys are
+/// uppercased and joined with `sep` after the prefix (if not empty); array
+/// elements use their index. Strings are used as they are and other leaves,
+/// including empty arrays and objects, as their compact JSON text.
+///
+/// Fails with `ParseError::WrongType` if `value` is not an object.
+pub fn to_env_map(
+    value: &JsonValue,
+    prefix: &str,
+    sep: &str,
+) -> Result<Vec<(String, String)>, ParseError> {
+    let object = value.as_object().ok_or_else(|| ParseError::WrongType {
+        key: String::new(),
+        expected: "an object",
+    })?;
+    let mut vars = Vec::new();
+    for (key, member) in object {
+        collect_env(member, env_name(prefix, sep, key), sep, &mut vars);
+    }
+    Ok(vars)
+}
+
+fn env_name(name: &str, sep: &str, segment: &str) -> String {
+    match name.is_empty() {
+        true => segment.to_uppercase(),
+        false => format!("{}{}{}", name, sep, segment.to_uppercase()),
+    }
+}
+
+fn collect_env(value: &JsonValue, name: String, sep: &str, vars: &mut Vec<(String, String)>) {
+    match value {
+        JsonValue::Array(array) if !array.is_empty() => {
+            for (i, element) in array.iter().enumerate() {
+                collect_env(element, env_name(&name, sep, &i.to_string()), sep, vars);
+            }
+        }
+        JsonValue::Object(object) if !object.is_empty() => {
+            for (key, member) in object {
+                collect_env(member, env_name(&name, sep, key), sep, vars);
+            }
+        }
+        JsonValue::String(s) => vars.push((name, s.clone())),
+        // Non-finite floats are written as `null`, so only values nested past
+        // the serializer's depth limit could fail, and leaves do not nest.
+        leaf => {
+            let options = SerializerOptions::default().non_finite(NonFinitePolicy::Null);
+            vars.push((
+                name,
+                to_string_with_options(leaf, &options).unwrap_or_default(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn to_env_map_flattens_nested_config() {
+        let config = json(
+            r#"{"database": {"port": 5432, "host": "db"}, "hosts": ["a", "b"],
+                "debug": true, "ratio": 0.5, "none": null, "empty": [], "opts": {}}"#,
+        );
+        assert_eq!(
+            to_env_map(&config, "APP", "__"),
+            Ok(vars(&[
+                ("APP__DATABASE__PORT", "5432"),
+                ("APP__DATABASE__HOST", "db"),
+                ("APP__HOSTS__0", "a"),
+                ("APP__HOSTS__1", "b"),
+                ("APP__DEBUG", "true"),
+                ("APP__RATIO", "0.5"),
+                ("APP__NONE", "null"),
+                ("APP__EMPTY", "[]"),
+                ("APP__OPTS", "{}"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn to_env_map_without_prefix_and_with_other_separators() {
+        let config = json(r#"{"log": {"level": "info"}, "items": [{"id": 1}]}"#);
+        assert_eq!(
+            to_env_map(&config, "", "_"),
+            Ok(vars(&[("LOG_LEVEL", "info"), ("ITEMS_0_ID", "1")]))
+        );
+        assert_eq!(
+            to_env_map(&json("[1]"), "APP", "_"),
+            Err(ParseError::WrongType {
+                key: String::new(),
+                expected: "an object"
+            })
+        );
+    }
+}
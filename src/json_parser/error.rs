@@ -0,0 +1,139 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The input was empty or contained only whitespace.
+    EmptyInput,
+    /// The input ended partway through a value.
+    UnexpectedEof,
+    /// More tokens were consumed than `ParserOptions::max_tokens` allows.
+    TooManyTokens { limit: usize },
+    /// The input bytes are not valid UTF-8 starting at byte `offset`.
+    InvalidUtf8 { offset: usize },
+    /// The input is not valid UTF-16 at code unit `offset`: an unpaired
+    /// surrogate, or a trailing odd byte.
+    InvalidUtf16 { offset: usize },
+    /// A complete value was followed by something other than whitespace. The
+    /// position is that of the first offending character.
+    TrailingData {
+        line: usize,
+        column: usize,
+        offset: usize,
+    },
+    /// A byte order mark (U+FEFF) appeared outside a string at byte `offset`,
+    /// typically where corrupted or concatenated files were joined.
+    UnexpectedBom { offset: usize },
+    /// Compressed input could not be decompressed.
+    Decompress { message: String },
+    /// Reading the input or writing the output failed.
+    Io { message: String },
+    /// The input ended inside a string. The position is that of its opening quote.
+    UnterminatedString { line: usize, column: usize },
+    /// A required object member was absent, or the value was not an object.
+    MissingField { key: String },
+    /// A value was not of the `expected` type. `key` names the object member
+    /// or array index holding it, and is empty for a whole document.
+    WrongType { key: String, expected: &'static str },
+    /// A container was closed by the other kind of bracket, as in `[1, 2}`.
+    /// `expected` is the bracket that would match the opener, and the
+    /// position is that of the one `found`.
+    MismatchedBracket {
+        expected: char,
+        found: char,
+        line: usize,
+        column: usize,
+    },
+    Syntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+}
+
+impl ParseError {
+    pub(crate) fn syntax_at(input: &str, offset: usize, message: impl Into<String>) -> Self {
+        let (line, column) = line_col(input, offset);
+        ParseError::Syntax {
+            message: message.into(),
+            line,
+            column,
+        }
+    }
+
+    pub(crate) fn trailing_at(input: &str, offset: usize) -> Self {
+        let (line, column) = line_col(input, offset);
+        ParseError::TrailingData {
+            line,
+            column,
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::EmptyInput => write!(f, "Empty input"),
+            ParseError::UnexpectedEof => write!(f, "Unexpected end of input"),
+            ParseError::TooManyTokens { limit } => {
+                write!(f, "Input exceeds the limit of {} tokens", limit)
+            }
+            ParseError::InvalidUtf8 { offset } => {
+                write!(f, "Invalid UTF-8 at byte offset {}", offset)
+            }
+            ParseError::InvalidUtf16 { offset } => {
+                write!(f, "Invalid UTF-16 at code unit {}", offset)
+            }
+            ParseError::TrailingData { line, column, .. } => write!(
+                f,
+                "Unexpected characters after JSON value at line {}, column {}",
+                line, column
+            ),
+            ParseError::UnexpectedBom { offset } => {
+                write!(f, "Unexpected byte order mark at byte offset {}", offset)
+            }
+            ParseError::Decompress { message } => write!(f, "Decompression failed: {}", message),
+            ParseError::Io { message } => write!(f, "I/O error: {}", message),
+            ParseError::UnterminatedString { line, column } => write!(
+                f,
+                "Unterminated string starting at line {}, column {}",
+                line, column
+            ),
+            ParseError::MissingField { key } => write!(f, "Missing field '{}'", key),
+            ParseError::WrongType { key, expected } if key.is_empty() => {
+                write!(f, "Value is not {}", expected)
+            }
+            ParseError::WrongType { key, expected } => {
+                write!(f, "Field '{}' is not {}", key, expected)
+            }
+            ParseError::MismatchedBracket {
+                expected,
+                found,
+                line,
+                column,
+            } => {
+                let opener = if *expected == ']' { '[' } else { '{' };
+                write!(
+                    f,
+                    "Expected '{}' to close '{}' but found '{}' at line {}, column {}",
+                    expected, opener, found, line, column
+                )
+            }
+            ParseError::Syntax {
+                message,
+                line,
+                column,
+            } => write!(f, "{} at line {}, column {}", message, line, column),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Lines and columns are 1-based; columns count chars, not bytes.
+pub(crate) fn line_col(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
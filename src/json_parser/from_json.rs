@@ -0,0 +1,240 @@
+use super::error::ParseError;
+use super::pointer::escape_token;
+use super::value::JsonValue;
+
+/// Typed extraction from a parsed value. Failures are
+/// `ParseError::MissingField` and `ParseError::WrongType` whose `key` is the
+/// JSON Pointer of the offending value relative to where extraction started,
+/// so `Vec::<User>::from_json` failing on the fourth user's age reports
+/// `/3/age`, and a root of the wrong type reports an empty key.
+///
+/// Implement it for a struct by reading each member with [`field`] or
+/// [`optional_field`], which extend the pointer as they descend.
+pub trait FromJson: Sized {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError>;
+}
+
+// Prefixes the pointer in `error` with `token`, as an error propagates out of
+// the child it names.
+fn within(token: &str, error: ParseError) -> ParseError {
+    let prefix = |pointer: String| format!("/{}{}", escape_token(token), pointer);
+    match error {
+        ParseError::MissingField { key } => ParseError::MissingField { key: prefix(key) },
+        ParseError::WrongType { key, expected } => ParseError::WrongType {
+            key: prefix(key),
+            expected,
+        },
+        other => other,
+    }
+}
+
+fn wrong_type(expected: &'static str) -> ParseError {
+    ParseError::WrongType {
+        key: String::new(),
+        expected,
+    }
+}
+
+/// Extracts the member `key` of an object as a `T`, failing if it is missing.
+pub fn field<T: FromJson>(value: &JsonValue, key: &str) -> Result<T, ParseError> {
+    let object = value.as_object().ok_or_else(|| wrong_type("an object"))?;
+    match object.iter().find(|(k, _)| k == key) {
+        Some((_, member)) => T::from_json(member).map_err(|e| within(key, e)),
+        None => Err(ParseError::MissingField {
+            key: format!("/{}", escape_token(key)),
+        }),
+    }
+}
+
+/// Like [`field`], but a missing or `null` member is `None`.
+pub fn optional_field<T: FromJson>(value: &JsonValue, key: &str) -> Result<Option<T>, ParseError> {
+    let object = value.as_object().ok_or_else(|| wrong_type("an object"))?;
+    match object.iter().find(|(k, _)| k == key) {
+        Some((_, member)) => Option::<T>::from_json(member).map_err(|e| within(key, e)),
+        None => Ok(None),
+    }
+}
+
+impl FromJson for JsonValue {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        value.as_bool().ok_or_else(|| wrong_type("a boolean"))
+    }
+}
+
+impl FromJson for i64 {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        value.as_i64().ok_or_else(|| wrong_type("an integer"))
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        value.as_f64().ok_or_else(|| wrong_type("a number"))
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| wrong_type("a string"))
+    }
+}
+
+/// `null` is `None`; anything else must be a `T`.
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+        value
+            .as_array()
+            .ok_or_else(|| wrong_type("an array"))?
+            .iter()
+            .enumerate()
+            .map(|(i, element)| T::from_json(element).map_err(|e| within(&i.to_string(), e)))
+            .collect()
+    }
+}
+
+/// Reads several members of an object at once with [`field`], returning them
+/// as a tuple in the order written: `extract!(value, { name: String, age: i64 })`
+/// is a `Result<(String, i64), ParseError>`. The first missing or mistyped
+/// member fails the whole extraction with its pointer, e.g. `/age`.
+#[macro_export]
+macro_rules! extract {
+    ($value:expr, { $($key:ident : $ty:ty),+ $(,)? }) => {{
+        let value: &$crate::json_parser::value::JsonValue = &$value;
+        (|| -> ::std::result::Result<($($ty,)+), $crate::json_parser::error::ParseError> {
+            Ok(($($crate::json_parser::from_json::field::<$ty>(value, stringify!($key))?,)+))
+        })()
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[derive(Debug, PartialEq)]
+    struct User {
+        name: String,
+        age: i64,
+        email: Option<String>,
+    }
+
+    impl FromJson for User {
+        fn from_json(value: &JsonValue) -> Result<Self, ParseError> {
+            Ok(User {
+                name: field(value, "name")?,
+                age: field(value, "age")?,
+                email: optional_field(value, "email")?,
+            })
+        }
+    }
+
+    #[test]
+    fn from_json_extracts_nested_structs() {
+        let users = parse_json(
+            r#"[{"name": "Ann", "age": 30, "email": "a@x"}, {"name": "Bo", "age": 4, "email": null}, {"name": "Cy", "age": 5}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Vec::<User>::from_json(&users),
+            Ok(vec![
+                User {
+                    name: "Ann".into(),
+                    age: 30,
+                    email: Some("a@x".into())
+                },
+                User {
+                    name: "Bo".into(),
+                    age: 4,
+                    email: None
+                },
+                User {
+                    name: "Cy".into(),
+                    age: 5,
+                    email: None
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn from_json_errors_carry_the_pointer_of_the_bad_value() {
+        let users =
+            parse_json(r#"[{"name": "Ann", "age": 30}, {"name": "Bo", "age": "4"}, {"age": 1}]"#)
+                .unwrap();
+        assert_eq!(
+            Vec::<User>::from_json(&users),
+            Err(ParseError::WrongType {
+                key: "/1/age".into(),
+                expected: "an integer"
+            })
+        );
+        let missing = parse_json(r#"{"a/b": [{"age": 1}]}"#).unwrap();
+        assert_eq!(
+            field::<Vec<User>>(&missing, "a/b"),
+            Err(ParseError::MissingField {
+                key: "/a~1b/0/name".into()
+            })
+        );
+        assert_eq!(
+            User::from_json(&parse_json("[]").unwrap()),
+            Err(ParseError::WrongType {
+                key: String::new(),
+                expected: "an object"
+            })
+        );
+        let bad_email = parse_json(r#"{"name": "A", "age": 1, "email": 5}"#).unwrap();
+        assert_eq!(
+            User::from_json(&bad_email),
+            Err(ParseError::WrongType {
+                key: "/email".into(),
+                expected: "a string"
+            })
+        );
+    }
+
+    #[test]
+    fn extract_reads_members_as_a_tuple() {
+        let value = parse_json(r#"{"name": "Ann", "age": 30, "tags": ["a"], "x": null}"#).unwrap();
+        assert_eq!(
+            extract!(value, { name: String, age: i64, tags: Vec<String>, x: Option<bool> }),
+            Ok(("Ann".to_string(), 30, vec!["a".to_string()], None))
+        );
+        assert_eq!(extract!(value, { age: f64, }), Ok((30.0,)));
+    }
+
+    #[test]
+    fn extract_fails_on_the_first_bad_member() {
+        let value = parse_json(r#"{"name": 1, "age": "x"}"#).unwrap();
+        assert_eq!(
+            extract!(value, { age: i64, name: String }),
+            Err(ParseError::WrongType {
+                key: "/age".into(),
+                expected: "an integer"
+            })
+        );
+        assert_eq!(
+            extract!(value, { missing: i64 }),
+            Err(ParseError::MissingField {
+                key: "/missing".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use super::compare::total_cmp;
+use super::pointer::escape_token;
+use super::serializer::{to_string_with_options, NonFinitePolicy, SerializerOptions};
+use super::value::JsonValue;
+
+// 64-bit FNV-1a: simple, dependency-free and stable across Rust releases,
+// unlike `DefaultHasher`. Not suitable where collisions must be resisted.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl JsonValue {
+    /// A hash of the canonical serialization, so documents that differ only in
+    /// object key order hash the same. Stable across runs and platforms.
+    pub fn content_hash(&self) -> u64 {
+        // Canonical output with no depth limit and non-finite floats as
+        // `null`, which cannot fail.
+        let options = SerializerOptions::default()
+            .sort_keys(true)
+            .max_depth(None)
+            .non_finite(NonFinitePolicy::Null);
+        let canonical = to_string_with_options(self, &options).unwrap_or_default();
+        fnv1a(canonical.as_bytes())
+    }
+
+    /// Removes repeated elements from every array in the tree, keeping the
+    /// first of each in place, so `[1, 2, 2, 3, 1]` becomes `[1, 2, 3]`. This
+    /// changes array contents and lengths. Elements are compared with `==`
+    /// after their own arrays are deduplicated, so `1` and `1.0` are repeats
+    /// but objects whose members differ only in order are both kept. Candidates are found by
+    /// [`content_hash`](JsonValue::content_hash) rather than compared
+    /// pairwise.
+    pub fn dedup_arrays(&mut self) {
+        match self {
+            JsonValue::Array(array) => {
+                array.iter_mut().for_each(JsonValue::dedup_arrays);
+                // The indices of the kept elements, by hash.
+                let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+                let keep: Vec<bool> = (0..array.len())
+                    .map(|i| {
+                        let same_hash = seen.entry(array[i].content_hash()).or_default();
+                        let repeated = same_hash.iter().any(|&j| array[j] == array[i]);
+                        if !repeated {
+                            same_hash.push(i);
+                        }
+                        !repeated
+                    })
+                    .collect();
+                let mut keep = keep.into_iter();
+                array.retain(|_| keep.next().unwrap_or(true));
+            }
+            JsonValue::Object(object) => object.iter_mut().for_each(|(_, v)| v.dedup_arrays()),
+            _ => {}
+        }
+    }
+}
+
+/// Like [`JsonValue::content_hash`], but object members whose key is in
+/// `ignore_keys` are left out at any depth, so records that differ only in
+/// volatile fields such as `id` or `updated_at` fingerprint the same.
+pub fn fingerprint(value: &JsonValue, ignore_keys: &[&str]) -> u64 {
+    without_keys(value, ignore_keys).content_hash()
+}
+
+fn without_keys(value: &JsonValue, ignore_keys: &[&str]) -> JsonValue {
+    match value {
+        JsonValue::Array(array) => {
+            JsonValue::Array(array.iter().map(|v| without_keys(v, ignore_keys)).collect())
+        }
+        JsonValue::Object(object) => JsonValue::Object(
+            object
+                .iter()
+                .filter(|(key, _)| !ignore_keys.contains(&key.as_str()))
+                .map(|(key, v)| (key.clone(), without_keys(v, ignore_keys)))
+                .collect(),
+        ),
+        scalar => scalar.clone(),
+    }
+}
+
+/// Finds non-empty arrays and objects that are structurally equal to an
+/// earlier one, ignoring object key order, as candidates for factoring out.
+/// Each pair is the pointer of the first occurrence and of a later copy, in
+/// document order. Copies are not searched further, so only the outermost
+/// duplicated subtree is reported, not every part of it.
+pub fn find_duplicate_subtrees(value: &JsonValue) -> Vec<(String, String)> {
+    let mut seen = HashMap::new();
+    let mut duplicates = Vec::new();
+    find_duplicates_at(value, &mut String::new(), &mut seen, &mut duplicates);
+    duplicates
+}
+
+fn find_duplicates_at<'a>(
+    value: &'a JsonValue,
+    pointer: &mut String,
+    seen: &mut HashMap<u64, Vec<(String, &'a JsonValue)>>,
+    duplicates: &mut Vec<(String, String)>,
+) {
+    match value {
+        JsonValue::Array(array) if !array.is_empty() => {}
+        JsonValue::Object(object) if !object.is_empty() => {}
+        _ => return,
+    }
+    let candidates = seen.entry(value.content_hash()).or_default();
+    if let Some((first, _)) = candidates
+        .iter()
+        .find(|(_, other)| total_cmp(value, other).is_eq())
+    {
+        duplicates.push((first.clone(), pointer.clone()));
+        return;
+    }
+    candidates.push((pointer.clone(), value));
+
+    let mut visit = |token: &str, child: &'a JsonValue| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(token);
+        find_duplicates_at(child, pointer, seen, duplicates);
+        pointer.truncate(len);
+    };
+    match value {
+        JsonValue::Array(array) => {
+            for (i, child) in array.iter().enumerate() {
+                visit(&i.to_string(), child);
+            }
+        }
+        JsonValue::Object(object) => {
+            for (key, child) in object {
+                visit(&escape_token(key), child);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn content_hash_ignores_key_order_only() {
+        let a = json(r#"{"b": {"y": 1, "x": [1, 2]}, "a": "s"}"#);
+        let b = json(r#"{"a": "s", "b": {"x": [1, 2], "y": 1}}"#);
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(
+            a.content_hash(),
+            json(r#"{"a": "s", "b": {"x": [2, 1], "y": 1}}"#).content_hash()
+        );
+        assert_ne!(json("1").content_hash(), json(r#""1""#).content_hash());
+        // FNV-1a of the output "null".
+        assert_eq!(JsonValue::Null.content_hash(), fnv1a(b"null"));
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn find_duplicate_subtrees_pairs_first_and_later_copies() {
+        let doc = json(
+            r#"{"a": {"x": 1, "y": [1, 2]}, "b": [{"y": [1, 2], "x": 1}, [1, 2]],
+                "c": {"x": 1, "y": [1, 2]}, "d": [], "e": []}"#,
+        );
+        assert_eq!(
+            find_duplicate_subtrees(&doc),
+            [
+                ("/a".to_string(), "/b/0".to_string()),
+                ("/a/y".to_string(), "/b/1".to_string()),
+                ("/a".to_string(), "/c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_duplicate_subtrees_ignores_scalars_and_near_misses() {
+        assert!(find_duplicate_subtrees(&json(r#"[1, 1, "a", "a", {}, {}]"#)).is_empty());
+        assert!(
+            find_duplicate_subtrees(&json(r#"[[1, 2], [2, 1], {"a": 1}, {"a": 1.5}]"#)).is_empty()
+        );
+    }
+
+    #[test]
+    fn dedup_arrays_keeps_the_first_of_each_element_at_any_depth() {
+        let mut value = json(
+            r#"{"a": [1, 2, 2, 3, 1], "b": [[1, 1], [1], {"x": [null, null]}, {"x": [null]}]}"#,
+        );
+        value.dedup_arrays();
+        assert_eq!(
+            value,
+            json(r#"{"a": [1, 2, 3], "b": [[1], {"x": [null]}]}"#)
+        );
+    }
+
+    #[test]
+    fn dedup_arrays_keeps_elements_that_only_hash_the_same() {
+        // Objects whose members differ only in order hash alike, but are not `==`.
+        let mut objects = json(r#"[{"a": 1, "b": 2}, {"b": 2, "a": 1}, {"a": 1, "b": 2}]"#);
+        objects.dedup_arrays();
+        assert_eq!(objects, json(r#"[{"a": 1, "b": 2}, {"b": 2, "a": 1}]"#));
+    }
+
+    #[test]
+    fn dedup_arrays_treats_equal_integers_and_floats_as_repeats() {
+        let mut numbers = JsonValue::Array(vec![
+            JsonValue::Integer(1),
+            JsonValue::Number(1.0),
+            JsonValue::Number(1.5),
+            JsonValue::Integer(1),
+        ]);
+        numbers.dedup_arrays();
+        assert!(matches!(
+            numbers.as_array().unwrap(),
+            [JsonValue::Integer(1), JsonValue::Number(n)] if *n == 1.5
+        ));
+    }
+
+    #[test]
+    fn fingerprint_ignores_listed_keys_at_any_depth() {
+        let a = json(
+            r#"{"id": 1, "name": "x", "items": [{"id": 5, "v": 1}], "meta": {"updated_at": "t1"}}"#,
+        );
+        let b = json(
+            r#"{"name": "x", "id": 2, "items": [{"v": 1, "id": 6}], "meta": {"updated_at": "t2"}}"#,
+        );
+        let ignore = ["id", "updated_at"];
+        assert_eq!(fingerprint(&a, &ignore), fingerprint(&b, &ignore));
+        assert_ne!(fingerprint(&a, &["id"]), fingerprint(&b, &["id"]));
+        assert_eq!(fingerprint(&a, &[]), a.content_hash());
+        // Other members still count.
+        let c = json(
+            r#"{"id": 1, "name": "y", "items": [{"id": 5, "v": 1}], "meta": {"updated_at": "t1"}}"#,
+        );
+        assert_ne!(fingerprint(&a, &ignore), fingerprint(&c, &ignore));
+        // Only object keys are ignored, not equal string values.
+        assert_ne!(
+            fingerprint(&json(r#"["id"]"#), &ignore),
+            fingerprint(&json("[]"), &ignore)
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use ::indexmap::IndexMap;
+
+use super::value::JsonValue;
+
+// The `indexmap` feature does not change how objects are stored: they stay
+// `Vec`-backed. Storing them in an `IndexMap` instead would break
+// `as_object`, which lends out `&[(String, JsonValue)]`, and `as_object_mut`,
+// which lends out the `Vec`; it would collapse the duplicate keys the parser
+// keeps; and, since features must be additive, it would break any crate that
+// builds `JsonValue::Object(vec![..])` as soon as another crate in the build
+// enabled the feature. It only adds conversions, so a caller doing many
+// lookups in one large object can pay O(n) once to get O(1) lookups from then
+// on.
+
+impl JsonValue {
+    /// Converts an object's members into an `IndexMap`, which keeps member
+    /// order and looks keys up in O(1). Building the map is O(n), so this
+    /// pays off only for repeated lookups. When a key repeats, the last value
+    /// wins but keeps the position of the first, whereas [`JsonValue::get`]
+    /// finds the first; without repeats the two agree. Nested objects are not
+    /// converted. `None` for anything but an object.
+    pub fn into_index_map(self) -> Option<IndexMap<String, JsonValue>> {
+        match self {
+            JsonValue::Object(object) => Some(object.into_iter().collect()),
+            _ => None,
+        }
+    }
+}
+
+/// An object holding the map's entries in map order.
+impl From<IndexMap<String, JsonValue>> for JsonValue {
+    fn from(map: IndexMap<String, JsonValue>) -> Self {
+        JsonValue::Object(map.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[test]
+    fn into_index_map_keeps_order_and_lets_the_last_duplicate_win() {
+        let object = parse_json(r#"{"b": 1, "a": {"x": 2}, "b": 3}"#).unwrap();
+        let map = object.into_index_map().unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!(map["b"], JsonValue::Integer(3));
+        assert_eq!(map["a"], parse_json(r#"{"x": 2}"#).unwrap());
+        assert_eq!(parse_json("[1]").unwrap().into_index_map(), None);
+    }
+
+    #[test]
+    fn index_map_lookups_agree_with_get() {
+        let object = JsonValue::Object(
+            (0..1000)
+                .map(|i| (format!("key{i}"), JsonValue::Integer(i)))
+                .collect(),
+        );
+        let map = object.clone().into_index_map().unwrap();
+        assert_eq!(map.len(), 1000);
+        for i in [0, 1, 499, 999] {
+            let key = format!("key{i}");
+            assert_eq!(map.get(&key), object.get(&key));
+            assert_eq!(map.get_index_of(&key), Some(i as usize));
+        }
+        assert_eq!(map.get("key1000"), None);
+        assert_eq!(object.get("key1000"), None);
+    }
+
+    #[test]
+    fn index_maps_convert_back_to_objects_in_map_order() {
+        let object = parse_json(r#"{"z": null, "a": [1, 2]}"#).unwrap();
+        let map = object.clone().into_index_map().unwrap();
+        assert_eq!(JsonValue::from(map), object);
+        assert_eq!(JsonValue::from(IndexMap::new()), JsonValue::Object(vec![]));
+    }
+}
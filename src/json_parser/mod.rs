@@ -1,2 +1,36 @@
+pub mod access;
+pub mod annotated;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+pub mod builder;
+pub mod compare;
+pub mod cst;
+pub mod cursor;
+pub mod datetime;
+pub mod env;
+pub mod error;
+pub mod from_json;
+pub mod hash;
+#[cfg(feature = "indexmap")]
+mod indexmap;
+pub mod options;
 pub mod parser;
+pub mod patch;
+pub mod pointer;
+pub mod query;
+pub mod repair;
+pub mod serializer;
+#[cfg(feature = "rc")]
+pub mod shared;
+pub mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+pub mod table;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod transform;
+#[cfg(feature = "unicode-normalization")]
+pub mod unicode;
 pub mod value;
+#[cfg(feature = "yaml")]
+pub mod yaml;
@@ -0,0 +1,373 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// What to do when an object repeats a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeyPolicy {
+    /// Keep every entry in source order.
+    #[default]
+    Allow,
+    /// Fail with a syntax error at the repeated key.
+    Error,
+    /// Collect the values of a repeated key, in source order, into an array
+    /// under its first occurrence, as HTTP headers serialized to JSON
+    /// expect. Keys that occur once keep their value as is.
+    Merge,
+}
+
+/// A function applied to every object key as it is parsed, behind an `Arc` so
+/// that [`ParserOptions`] stays cheap to clone. Two renamers are equal only if
+/// they share the same function.
+#[derive(Clone)]
+pub struct KeyRenamer(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl KeyRenamer {
+    pub fn new(rename: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        KeyRenamer(Arc::new(rename))
+    }
+
+    pub fn apply(&self, key: &str) -> String {
+        (self.0)(key)
+    }
+}
+
+impl fmt::Debug for KeyRenamer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyRenamer(..)")
+    }
+}
+
+impl PartialEq for KeyRenamer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The number token length limit of [`ParserOptions::default`]. Far longer
+/// than any `f64` needs, even written out in full.
+pub const DEFAULT_MAX_NUMBER_LEN: usize = 4096;
+
+/// Settings for [`parse_json_with_options`](super::parser::parse_json_with_options).
+/// The default accepts exactly what [`parse_json`](super::parser::parse_json) does.
+///
+/// Options can be set field by field or chained from the default:
+/// `ParserOptions::default().allow_comments(true).allow_trailing_commas(true)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// Upper bound on brackets, separators and scalar values consumed; `None` is unlimited.
+    pub max_tokens: Option<usize>,
+    /// Treat `// line` and `/* block */` comments as whitespace.
+    pub allow_comments: bool,
+    /// Accept a `,` directly before a closing `]` or `}`.
+    pub allow_trailing_commas: bool,
+    /// Treat `#` to the end of the line as a comment, as in shell-wrapped
+    /// configs that start with a `#!` line. Independent of `allow_comments`.
+    pub allow_hash_comments: bool,
+    /// Accept one `;` or `,` after the top-level value, as some tools append.
+    pub allow_trailing_punctuation: bool,
+    /// Accept BigInt-style integers such as `123n`, kept as `JsonValue::RawNumber`.
+    pub allow_bigint_suffix: bool,
+    /// Accept the bare words `yes` and `on` as `true`, and `no` and `off` as
+    /// `false`, in any case, as config files sometimes write them. `true` and
+    /// `false` themselves stay lowercase only.
+    pub allow_relaxed_booleans: bool,
+    /// Accept `_` between two digits of a number, as in `1_000_000`, ignoring
+    /// it. Underscores next to anything else, or doubled, are still errors.
+    pub allow_numeric_underscores: bool,
+    /// Accept integer parts with leading zeros such as `01`, read as `1`.
+    pub allow_leading_zeros: bool,
+    /// Accept bare number, boolean and null tokens as object keys, converting
+    /// them to their JSON text (`{1: "a"}` has the key `"1"`).
+    pub allow_non_string_keys: bool,
+    /// Object members with these keys are dropped at any depth. Their values
+    /// are still checked for syntax errors, but never built.
+    pub skip_keys: HashSet<String>,
+    /// Rewrites every object key before anything else sees it, e.g. to
+    /// normalize camelCase to snake_case. `skip_keys`, duplicate detection,
+    /// spans and the built object all use the renamed key.
+    pub rename_key: Option<KeyRenamer>,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Reject raw U+0000 to U+001F inside strings; they must be escaped.
+    pub reject_control_chars: bool,
+    /// Reject any raw non-ASCII character, inside strings or out, for
+    /// pipelines that need ASCII-only JSON; `\u00e9` escapes are still
+    /// accepted. The input is scanned before parsing starts.
+    pub require_ascii: bool,
+    /// Only treat space, tab, line feed and carriage return as whitespace,
+    /// rather than anything `char::is_whitespace` accepts.
+    pub json_whitespace_only: bool,
+    /// Read `""`, `[]` and `{}` as `null` wherever they appear, including at
+    /// the root. Whitespace-only strings are kept. The check applies to the
+    /// value as written, so `[[]]` becomes `[null]`, not `null`.
+    pub empty_as_null: bool,
+    /// Read string values that look like another type as that type, for APIs
+    /// that quote everything: exactly `"true"` and `"false"` become booleans,
+    /// and strings holding a whole JSON number become numbers as with
+    /// [`JsonValue::strings_to_numbers`](super::value::JsonValue::strings_to_numbers).
+    /// Keys are never converted, and `empty_as_null` applies first.
+    pub loose_typing: bool,
+    /// Keep every number as `JsonValue::RawNumber` holding its source text,
+    /// so serializing reproduces formatting such as `1.0` or `1e3` exactly.
+    /// Numbers are still validated; `normalize_negative_zero` does not apply.
+    pub preserve_number_text: bool,
+    /// Read negative zero as positive zero: `-0` as the integer `0` and
+    /// `-0.0` as `0.0`. By default the sign is kept, as a `Number`, so that
+    /// serializing reproduces `-0`.
+    pub normalize_negative_zero: bool,
+    /// Reject number tokens longer than this many characters, so a run of
+    /// millions of digits fails early instead of being buffered whole.
+    /// `None` is unlimited.
+    pub max_number_len: Option<usize>,
+    /// Reject numbers whose absolute value is greater than this.
+    pub max_number_abs: Option<f64>,
+    /// Reject numbers with more fractional digits than this once the exponent
+    /// is applied: `1.50` has two, `1.5e-10` eleven and `1.25e2` none.
+    /// Trailing zeros are counted as written.
+    pub max_fraction_digits: Option<usize>,
+    /// Keep only the first this many elements of every array and members of
+    /// every object, discarding the rest after checking their syntax, so
+    /// oversized input is accepted with bounded memory. This is lossy: the
+    /// parsed value no longer matches the input. `None` keeps everything.
+    pub truncate_containers_at: Option<usize>,
+    /// Initial capacity for every array's backing `Vec`. When documents hold
+    /// many arrays of a known, similar size this avoids repeated reallocation
+    /// as elements are pushed; it never changes the parsed result.
+    pub array_capacity_hint: Option<usize>,
+    /// Like `array_capacity_hint`, for the member list of every object.
+    pub object_capacity_hint: Option<usize>,
+    /// Initial capacity in bytes for every string and key, such as the
+    /// typical length in string-heavy documents. Longer strings still grow
+    /// as needed, and shorter ones keep the spare capacity.
+    pub string_capacity_hint: Option<usize>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            max_tokens: None,
+            allow_comments: false,
+            allow_trailing_commas: false,
+            allow_hash_comments: false,
+            allow_trailing_punctuation: false,
+            allow_bigint_suffix: false,
+            allow_relaxed_booleans: false,
+            allow_numeric_underscores: false,
+            allow_leading_zeros: false,
+            allow_non_string_keys: false,
+            skip_keys: HashSet::new(),
+            rename_key: None,
+            duplicate_keys: DuplicateKeyPolicy::Allow,
+            reject_control_chars: false,
+            require_ascii: false,
+            json_whitespace_only: false,
+            empty_as_null: false,
+            loose_typing: false,
+            preserve_number_text: false,
+            normalize_negative_zero: false,
+            max_number_len: Some(DEFAULT_MAX_NUMBER_LEN),
+            max_number_abs: None,
+            max_fraction_digits: None,
+            truncate_containers_at: None,
+            array_capacity_hint: None,
+            object_capacity_hint: None,
+            string_capacity_hint: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// The strictest settings: no duplicate keys, no raw control characters
+    /// and only the four JSON whitespace characters. The number grammar and the
+    /// single-value document rule of RFC 8259 are always enforced.
+    pub fn strict() -> Self {
+        ParserOptions {
+            duplicate_keys: DuplicateKeyPolicy::Error,
+            reject_control_chars: true,
+            json_whitespace_only: true,
+            ..ParserOptions::default()
+        }
+    }
+
+    pub fn max_tokens(mut self, max_tokens: Option<usize>) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn allow_comments(mut self, allow_comments: bool) -> Self {
+        self.allow_comments = allow_comments;
+        self
+    }
+
+    pub fn allow_trailing_commas(mut self, allow_trailing_commas: bool) -> Self {
+        self.allow_trailing_commas = allow_trailing_commas;
+        self
+    }
+
+    pub fn allow_hash_comments(mut self, allow_hash_comments: bool) -> Self {
+        self.allow_hash_comments = allow_hash_comments;
+        self
+    }
+
+    pub fn allow_trailing_punctuation(mut self, allow_trailing_punctuation: bool) -> Self {
+        self.allow_trailing_punctuation = allow_trailing_punctuation;
+        self
+    }
+
+    pub fn allow_bigint_suffix(mut self, allow_bigint_suffix: bool) -> Self {
+        self.allow_bigint_suffix = allow_bigint_suffix;
+        self
+    }
+
+    pub fn allow_relaxed_booleans(mut self, allow_relaxed_booleans: bool) -> Self {
+        self.allow_relaxed_booleans = allow_relaxed_booleans;
+        self
+    }
+
+    pub fn allow_numeric_underscores(mut self, allow_numeric_underscores: bool) -> Self {
+        self.allow_numeric_underscores = allow_numeric_underscores;
+        self
+    }
+
+    pub fn allow_leading_zeros(mut self, allow_leading_zeros: bool) -> Self {
+        self.allow_leading_zeros = allow_leading_zeros;
+        self
+    }
+
+    pub fn allow_non_string_keys(mut self, allow_non_string_keys: bool) -> Self {
+        self.allow_non_string_keys = allow_non_string_keys;
+        self
+    }
+
+    pub fn skip_keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.skip_keys = keys.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn rename_key(mut self, rename: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.rename_key = Some(KeyRenamer::new(rename));
+        self
+    }
+
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeyPolicy) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    pub fn reject_control_chars(mut self, reject_control_chars: bool) -> Self {
+        self.reject_control_chars = reject_control_chars;
+        self
+    }
+
+    pub fn require_ascii(mut self, require_ascii: bool) -> Self {
+        self.require_ascii = require_ascii;
+        self
+    }
+
+    pub fn json_whitespace_only(mut self, json_whitespace_only: bool) -> Self {
+        self.json_whitespace_only = json_whitespace_only;
+        self
+    }
+
+    pub fn empty_as_null(mut self, empty_as_null: bool) -> Self {
+        self.empty_as_null = empty_as_null;
+        self
+    }
+
+    pub fn loose_typing(mut self, loose_typing: bool) -> Self {
+        self.loose_typing = loose_typing;
+        self
+    }
+
+    pub fn preserve_number_text(mut self, preserve_number_text: bool) -> Self {
+        self.preserve_number_text = preserve_number_text;
+        self
+    }
+
+    pub fn normalize_negative_zero(mut self, normalize_negative_zero: bool) -> Self {
+        self.normalize_negative_zero = normalize_negative_zero;
+        self
+    }
+
+    pub fn max_number_len(mut self, max_number_len: Option<usize>) -> Self {
+        self.max_number_len = max_number_len;
+        self
+    }
+
+    pub fn max_number_abs(mut self, max_number_abs: Option<f64>) -> Self {
+        self.max_number_abs = max_number_abs;
+        self
+    }
+
+    pub fn max_fraction_digits(mut self, max_fraction_digits: Option<usize>) -> Self {
+        self.max_fraction_digits = max_fraction_digits;
+        self
+    }
+
+    pub fn truncate_containers_at(mut self, truncate_containers_at: Option<usize>) -> Self {
+        self.truncate_containers_at = truncate_containers_at;
+        self
+    }
+
+    pub fn array_capacity_hint(mut self, array_capacity_hint: Option<usize>) -> Self {
+        self.array_capacity_hint = array_capacity_hint;
+        self
+    }
+
+    pub fn object_capacity_hint(mut self, object_capacity_hint: Option<usize>) -> Self {
+        self.object_capacity_hint = object_capacity_hint;
+        self
+    }
+
+    pub fn string_capacity_hint(mut self, string_capacity_hint: Option<usize>) -> Self {
+        self.string_capacity_hint = string_capacity_hint;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::{parse_json, parse_json_with_options};
+
+    #[test]
+    fn setters_match_field_assignment() {
+        let chained = ParserOptions::default()
+            .allow_comments(true)
+            .allow_trailing_commas(true)
+            .max_tokens(Some(10));
+        let assigned = ParserOptions {
+            allow_comments: true,
+            allow_trailing_commas: true,
+            max_tokens: Some(10),
+            ..ParserOptions::default()
+        };
+        assert_eq!(chained, assigned);
+        assert_ne!(chained, ParserOptions::default());
+        assert_eq!(
+            ParserOptions::default().max_number_len,
+            Some(DEFAULT_MAX_NUMBER_LEN)
+        );
+    }
+
+    #[test]
+    fn allow_trailing_commas_and_comments_are_independent() {
+        let input = "[1, 2,] // done";
+        let commas = ParserOptions::default().allow_trailing_commas(true);
+        let comments = ParserOptions::default().allow_comments(true);
+        let both = commas.clone().allow_comments(true);
+        assert_eq!(parse_json_with_options(input, &both), parse_json("[1, 2]"));
+        assert!(parse_json_with_options(input, &commas).is_err());
+        assert!(parse_json_with_options(input, &comments).is_err());
+        assert_eq!(
+            parse_json_with_options(r#"{"a": 1,}"#, &commas),
+            parse_json(r#"{"a": 1}"#)
+        );
+        assert!(parse_json_with_options("[1,,]", &commas).is_err());
+        assert!(parse_json_with_options("[,]", &commas).is_err());
+    }
+}
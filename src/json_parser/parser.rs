@@ -1,203 +1,2990 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Read;
 use std::iter::Peekable;
+use std::ops::Range;
 use std::str::Chars;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
-use super::value::JsonValue;
+use super::error::ParseError;
+use super::options::{DuplicateKeyPolicy, ParserOptions};
+use super::patch::merge_patch;
+use super::pointer::escape_token;
+use super::serializer::{to_string_with_options, NonFinitePolicy, SerializerOptions};
+use super::value::{JsonType, JsonValue};
 
 struct Parser<'a> {
     chars: Peekable<Chars<'a>>,
+    options: &'a ParserOptions,
+    offset: usize,
+    line: usize,
+    column: usize,
+    tokens: usize,
+    // The line and column of the object key being parsed, for errors about
+    // the key that are only detected once it has been read.
+    key_position: (usize, usize),
+    recovering: bool,
+    errors: Vec<ParseError>,
+    // Only tracked by `parse_with_spans`: byte ranges keyed by JSON Pointer,
+    // and the escaped pointer tokens leading to the value being parsed.
+    spans: Option<BTreeMap<String, Range<usize>>>,
+    path: Vec<String>,
+    // Only tracked by `parse_json_with_comments`: comments keyed by the pointer
+    // of the member they precede, and those seen since the last token.
+    comments: Option<CommentMap>,
+    pending_comments: Vec<String>,
+    // Reported by `parse_json_timed`.
+    chars_read: usize,
+    lookaheads: usize,
 }
 
 impl<'a> Parser<'a> {
-    fn new(input: &'a str) -> Self {
+    fn new(input: &'a str, options: &'a ParserOptions) -> Self {
         Parser {
             chars: input.chars().peekable(),
+            options,
+            offset: 0,
+            line: 1,
+            column: 1,
+            tokens: 0,
+            key_position: (1, 1),
+            recovering: false,
+            errors: Vec::new(),
+            spans: None,
+            path: Vec::new(),
+            comments: None,
+            pending_comments: Vec::new(),
+            chars_read: 0,
+            lookaheads: 0,
         }
     }
 
-    fn parse_value(&mut self) -> Result<JsonValue, String> {
-        self.skip_whitespace();
-        match self.chars.peek() {
-            Some(&'{') => self.parse_object(),
-            Some(&'[') => self.parse_array(),
-            Some(&'"') => self.parse_string().map(JsonValue::String),
-            Some(&'-') | Some(&('0'..='9')) => self.parse_number(),
-            Some(&'t') | Some(&'f') => self.parse_boolean(),
-            Some(&'n') => self.parse_null(),
-            Some(&c) => Err(format!("Unexpected character: {}", c)),
-            None => Err("Unexpected end of input".to_string()),
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        self.chars_read += 1;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    // A copy of the remaining input to scan ahead without consuming it.
+    fn lookahead(&mut self) -> Peekable<Chars<'a>> {
+        self.lookaheads += 1;
+        self.chars.clone()
+    }
+
+    fn count_token(&mut self) -> Result<(), ParseError> {
+        self.pending_comments.clear();
+        self.tokens += 1;
+        match self.options.max_tokens {
+            Some(limit) if self.tokens > limit => Err(ParseError::TooManyTokens { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    // Consumes a single punctuation character, counting it as a token.
+    fn consume_token(&mut self) -> Result<(), ParseError> {
+        self.count_token()?;
+        self.next_char();
+        Ok(())
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        if self.at_bom() {
+            return ParseError::UnexpectedBom {
+                offset: self.offset,
+            };
         }
+        ParseError::Syntax {
+            message: message.into(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    // A BOM outside a string is never valid, so errors at one say so rather
+    // than reporting an unexpected character.
+    fn at_bom(&self) -> bool {
+        self.chars.clone().next() == Some('\u{FEFF}')
     }
 
-    fn skip_whitespace(&mut self) {
+    fn parse_value(&mut self) -> Result<JsonValue, ParseError> {
+        self.skip_whitespace()?;
+        if self.chars.peek().is_some() {
+            self.count_token()?;
+        }
+        let start = self.offset;
+        let value = match self.chars.peek().copied() {
+            _ if self.consume_relaxed_boolean(true) => Ok(JsonValue::Boolean(true)),
+            _ if self.consume_relaxed_boolean(false) => Ok(JsonValue::Boolean(false)),
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some('t' | 'f') => self.parse_boolean(),
+            Some('n') => self.parse_null(),
+            Some(c) => Err(self.error(format!("Unexpected character: {}", c))),
+            None => Err(ParseError::UnexpectedEof),
+        }?;
+        let value = match value {
+            JsonValue::String(s) if self.options.empty_as_null && s.is_empty() => JsonValue::Null,
+            JsonValue::Array(a) if self.options.empty_as_null && a.is_empty() => JsonValue::Null,
+            JsonValue::Object(o) if self.options.empty_as_null && o.is_empty() => JsonValue::Null,
+            JsonValue::String(s) if self.options.loose_typing => match s.as_str() {
+                "true" => JsonValue::Boolean(true),
+                "false" => JsonValue::Boolean(false),
+                _ => {
+                    let mut value = JsonValue::String(s);
+                    value.strings_to_numbers();
+                    value
+                }
+            },
+            value => value,
+        };
+        if let Some(spans) = &mut self.spans {
+            spans.insert(self.path.concat(), start..self.offset);
+        }
+        Ok(value)
+    }
+
+    // Runs `parse` with `token` appended to the path, when spans or comments
+    // are tracked.
+    fn with_path_token<T>(
+        &mut self,
+        token: impl FnOnce() -> String,
+        parse: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        if self.spans.is_none() && self.comments.is_none() {
+            return parse(self);
+        }
+        self.path.push(format!("/{}", token()));
+        let result = parse(self);
+        self.path.pop();
+        result
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        let token_line = self.line;
         while let Some(&c) = self.chars.peek() {
-            if !c.is_whitespace() {
+            let is_whitespace = match self.options.json_whitespace_only {
+                true => matches!(c, ' ' | '\t' | '\n' | '\r'),
+                false => c.is_whitespace(),
+            };
+            if is_whitespace {
+                self.next_char();
+            } else if (c == '/' && self.options.allow_comments)
+                || (c == '#' && self.options.allow_hash_comments)
+            {
+                let own_line = self.line > token_line;
+                match self.skip_comment()? {
+                    Some(text) if own_line && self.comments.is_some() => {
+                        self.pending_comments.push(text)
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            } else {
                 break;
             }
-            self.chars.next();
         }
+        Ok(())
     }
 
-    fn parse_object(&mut self) -> Result<JsonValue, String> {
-        self.chars.next(); // Consume '{'
-        let mut object = Vec::new();
+    // Skips a `#`, `//` or `/* */` comment at the cursor and returns its
+    // trimmed text, or returns None (consuming nothing) if a `/` does not start
+    // one. An unterminated block comment is an error at its opening `/*`.
+    fn skip_comment(&mut self) -> Result<Option<String>, ParseError> {
+        let mut lookahead = self.lookahead();
+        let block = match (lookahead.next(), lookahead.next()) {
+            (Some('#'), _) => false,
+            (_, Some('/')) => false,
+            (_, Some('*')) => true,
+            _ => return Ok(None),
+        };
+        let unterminated = self.error("Unterminated block comment");
+        if self.next_char() == Some('/') {
+            self.next_char();
+        }
+        let mut text = String::new();
+        while let Some(c) = self.next_char() {
+            if !block && c == '\n' {
+                break;
+            }
+            if block && c == '/' && text.ends_with('*') {
+                text.pop();
+                return Ok(Some(text.trim().to_string()));
+            }
+            text.push(c);
+        }
+        match block {
+            true => Err(unterminated),
+            false => Ok(Some(text.trim().to_string())),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, ParseError> {
+        let mut object = Vec::with_capacity(self.options.object_capacity_hint.unwrap_or(0));
+        // With `DuplicateKeyPolicy::Merge`, the members already turned into arrays.
+        let mut merged = Vec::new();
+        self.parse_members(|parser, key| {
+            if parser.options.skip_keys.contains(&key) || parser.is_full(object.len()) {
+                return parser.skip_value();
+            }
+            if parser.options.duplicate_keys == DuplicateKeyPolicy::Error
+                && object.iter().any(|(k, _)| *k == key)
+            {
+                let (line, column) = parser.key_position;
+                return Err(ParseError::Syntax {
+                    message: format!("Duplicate key '{}'", key),
+                    line,
+                    column,
+                });
+            }
+            let value = parser.with_path_token(
+                || escape_token(&key),
+                |parser| parser.parse_value_or_recover(),
+            )?;
+            if parser.options.duplicate_keys == DuplicateKeyPolicy::Merge {
+                if let Some(index) = object.iter().position(|(k, _)| *k == key) {
+                    let first = &mut object[index].1;
+                    if !merged.contains(&index) {
+                        *first = JsonValue::Array(vec![std::mem::replace(first, JsonValue::Null)]);
+                        merged.push(index);
+                    }
+                    if let JsonValue::Array(values) = first {
+                        values.push(value);
+                    }
+                    return Ok(());
+                }
+            }
+            object.push((key, value));
+            Ok(())
+        })?;
+        Ok(JsonValue::Object(object))
+    }
+
+    // Walks the punctuation of an object, handing each key to `member`, which
+    // must consume the value that follows it.
+    fn parse_members(
+        &mut self,
+        mut member: impl FnMut(&mut Self, String) -> Result<(), ParseError>,
+    ) -> Result<(), ParseError> {
+        const SEPARATOR: &str = "Expected ',' or '}' in object";
+        self.next_char(); // Consume '{'
+        let mut after_comma = false;
 
         loop {
-            self.skip_whitespace();
-            if let Some(&'}') = self.chars.peek() {
-                self.chars.next();
-                return Ok(JsonValue::Object(object));
+            self.skip_whitespace()?;
+            self.key_position = (self.line, self.column);
+            let key = match self.chars.peek() {
+                Some(&'}') => return self.close_container(after_comma, "Trailing comma in object"),
+                Some(&']') => return self.wrong_close('}'),
+                Some(&'"') => {
+                    let key = self.parse_string()?;
+                    let key = self.rename_key(key);
+                    self.attach_comments(&key);
+                    Some(key)
+                }
+                Some(&('-' | '0'..='9' | 't' | 'f' | 'n'))
+                    if self.options.allow_non_string_keys =>
+                {
+                    // A scalar never nests, and with non-finite floats
+                    // written as `null` writing it cannot fail.
+                    let options = SerializerOptions::default().non_finite(NonFinitePolicy::Null);
+                    let key =
+                        to_string_with_options(&self.parse_value()?, &options).unwrap_or_default();
+                    Some(self.rename_key(key))
+                }
+                Some(_) => {
+                    let error = self.error("Expected string key in object");
+                    self.recover(error)?;
+                    None
+                }
+                None => return Err(ParseError::UnexpectedEof),
+            };
+
+            if let Some(key) = key {
+                self.skip_whitespace()?;
+                match self.chars.peek() {
+                    Some(&':') => {
+                        self.consume_token()?;
+                        member(self, key)?;
+                    }
+                    Some(_) => {
+                        let error = self.error("Expected ':' in object");
+                        self.recover(error)?;
+                    }
+                    None => return Err(ParseError::UnexpectedEof),
+                };
+            }
+
+            if !self.parse_separator('}', SEPARATOR)? {
+                return Ok(());
+            }
+            after_comma = true;
+        }
+    }
+
+    fn rename_key(&self, key: String) -> String {
+        match &self.options.rename_key {
+            Some(rename) => rename.apply(&key),
+            None => key,
+        }
+    }
+
+    // Files the comments seen since the last token under the member `key`.
+    fn attach_comments(&mut self, key: &str) {
+        let Some(comments) = &mut self.comments else {
+            return;
+        };
+        if !self.pending_comments.is_empty() {
+            let pointer = format!("{}/{}", self.path.concat(), escape_token(key));
+            comments
+                .entry(pointer)
+                .or_default()
+                .append(&mut self.pending_comments);
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, ParseError> {
+        let mut array = Vec::with_capacity(self.options.array_capacity_hint.unwrap_or(0));
+        self.parse_elements(|parser| {
+            if parser.is_full(array.len()) {
+                return parser.skip_value();
             }
+            let index = array.len();
+            let value = parser.with_path_token(
+                || index.to_string(),
+                |parser| parser.parse_value_or_recover(),
+            )?;
+            array.push(value);
+            Ok(())
+        })?;
+        Ok(JsonValue::Array(array))
+    }
+
+    // Whether a container already holding `len` entries keeps no more.
+    fn is_full(&self, len: usize) -> bool {
+        self.options
+            .truncate_containers_at
+            .is_some_and(|max| len >= max)
+    }
 
-            let key = self.parse_string()?;
-            self.skip_whitespace();
+    // Walks the punctuation of an array; `element` must consume each value.
+    fn parse_elements(
+        &mut self,
+        mut element: impl FnMut(&mut Self) -> Result<(), ParseError>,
+    ) -> Result<(), ParseError> {
+        self.next_char(); // Consume '['
+        let mut after_comma = false;
 
-            if self.chars.next() != Some(':') {
-                return Err("Expected ':' in object".to_string());
+        loop {
+            self.skip_whitespace()?;
+            match self.chars.peek() {
+                Some(&']') => return self.close_container(after_comma, "Trailing comma in array"),
+                Some(&'}') => return self.wrong_close(']'),
+                _ => {}
             }
 
-            let value = self.parse_value()?;
-            object.push((key, value));
+            element(self)?;
 
-            self.skip_whitespace();
-            match self.chars.next() {
-                Some(',') => continue,
-                Some('}') => return Ok(JsonValue::Object(object)),
-                _ => return Err("Expected ',' or '}' in object".to_string()),
+            if !self.parse_separator(']', "Expected ',' or ']' in array")? {
+                return Ok(());
             }
+            after_comma = true;
+        }
+    }
+
+    // Consumes the closing bracket under the cursor, which is an error right
+    // after a `,` unless trailing commas are allowed.
+    fn close_container(&mut self, after_comma: bool, message: &str) -> Result<(), ParseError> {
+        if after_comma && !self.options.allow_trailing_commas {
+            let error = self.error(message);
+            self.recover(error)?;
         }
+        self.consume_token()
     }
 
-    fn parse_array(&mut self) -> Result<JsonValue, String> {
-        self.chars.next(); // Consume '['
-        let mut array = Vec::new();
+    // Fails at a closing bracket that does not match the container's opener,
+    // `close` being the one expected. While recovering, the error is recorded
+    // and the wrong bracket still ends the container.
+    fn wrong_close(&mut self, close: char) -> Result<(), ParseError> {
+        let error = match self.chars.peek() {
+            Some(&found) => ParseError::MismatchedBracket {
+                expected: close,
+                found,
+                line: self.line,
+                column: self.column,
+            },
+            None => ParseError::UnexpectedEof,
+        };
+        if !self.recovering {
+            return Err(error);
+        }
+        self.errors.push(error);
+        self.consume_token()
+    }
 
+    // Consumes the `,` after a container entry (returning true) or the
+    // container's closing bracket (returning false).
+    fn parse_separator(&mut self, close: char, message: &str) -> Result<bool, ParseError> {
         loop {
-            self.skip_whitespace();
-            if let Some(&']') = self.chars.peek() {
-                self.chars.next();
-                return Ok(JsonValue::Array(array));
+            self.skip_whitespace()?;
+            match self.chars.peek() {
+                Some(&',') => {
+                    self.consume_token()?;
+                    return Ok(true);
+                }
+                Some(&c) if c == close => {
+                    self.consume_token()?;
+                    return Ok(false);
+                }
+                Some(&(']' | '}')) => {
+                    self.wrong_close(close)?;
+                    return Ok(false);
+                }
+                Some(_) => {
+                    let error = self.error(message);
+                    self.recover(error)?;
+                }
+                None => return Err(ParseError::UnexpectedEof),
             }
+        }
+    }
 
-            let value = self.parse_value()?;
-            array.push(value);
+    fn parse_value_or_recover(&mut self) -> Result<JsonValue, ParseError> {
+        self.parse_value().or_else(|error| {
+            self.recover(error)?;
+            Ok(JsonValue::Null)
+        })
+    }
 
-            self.skip_whitespace();
-            match self.chars.next() {
-                Some(',') => continue,
-                Some(']') => return Ok(JsonValue::Array(array)),
-                _ => return Err("Expected ',' or ']' in array".to_string()),
+    // In recovery mode, records `error` and skips ahead to the next `,`, `]` or
+    // `}` at the current nesting level so the enclosing container can carry on.
+    // Brackets and commas inside strings are not special-cased, since the error
+    // may have left us partway through a string.
+    fn recover(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if !self.recovering
+            || !matches!(
+                error,
+                ParseError::Syntax { .. } | ParseError::UnexpectedBom { .. }
+            )
+        {
+            return Err(error);
+        }
+        self.errors.push(error);
+        let mut depth = 0;
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                '[' | '{' => depth += 1,
+                ']' | '}' if depth == 0 => break,
+                ']' | '}' => depth -= 1,
+                ',' if depth == 0 => break,
+                _ => {}
             }
+            self.next_char();
         }
+        Ok(())
     }
 
-    fn parse_string(&mut self) -> Result<String, String> {
-        self.chars.next(); // Consume opening '"'
-        let mut string = String::new();
+    // Validates a value exactly as `parse_value` would, without building
+    // containers for it.
+    fn skip_value(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace()?;
+        match self.chars.peek() {
+            Some(&'{') => {
+                self.count_token()?;
+                self.parse_members(|parser, _| parser.skip_value())
+            }
+            Some(&'[') => {
+                self.count_token()?;
+                self.parse_elements(|parser| parser.skip_value())
+            }
+            _ => self.parse_value().map(|_| ()),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        let (line, column) = (self.line, self.column);
+        self.next_char(); // Consume opening '"'
+        let mut string = String::with_capacity(self.options.string_capacity_hint.unwrap_or(0));
 
-        while let Some(c) = self.chars.next() {
+        while let Some(c) = self.next_char() {
             match c {
                 '"' => return Ok(string),
-                '\\' => {
-                    match self.chars.next() {
-                        Some('"') => string.push('"'),
-                        Some('\\') => string.push('\\'),
-                        Some('/') => string.push('/'),
-                        Some('b') => string.push('\u{0008}'),
-                        Some('f') => string.push('\u{000C}'),
-                        Some('n') => string.push('\n'),
-                        Some('r') => string.push('\r'),
-                        Some('t') => string.push('\t'),
-                        Some('u') => {
-                            // Parse 4-digit hex
-                            let hex: String = self.chars.by_ref().take(4).collect();
-                            if hex.len() != 4 {
-                                return Err("Invalid unicode escape".to_string());
-                            }
-                            let code = u32::from_str_radix(&hex, 16)
-                                .map_err(|_| "Invalid unicode escape".to_string())?;
-                            string.push(
-                                char::from_u32(code).ok_or("Invalid unicode escape".to_string())?,
-                            );
-                        }
-                        _ => return Err("Invalid escape character".to_string()),
-                    }
+                '\\' => match self.next_char() {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('/') => string.push('/'),
+                    Some('b') => string.push('\u{0008}'),
+                    Some('f') => string.push('\u{000C}'),
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('u') => string.push(self.parse_unicode_escape()?),
+                    Some(_) => return Err(self.error("Invalid escape character")),
+                    None => break,
+                },
+                c if c < '\u{20}' && self.options.reject_control_chars => {
+                    return Err(self.error("Unescaped control character in string"));
                 }
                 _ => string.push(c),
             }
         }
-        Err("Unterminated string".to_string())
+        Err(ParseError::UnterminatedString { line, column })
     }
 
-    fn parse_number(&mut self) -> Result<JsonValue, String> {
+    // Reads the hex digits of a `\uXXXX` escape, whose `\u` has been consumed,
+    // combining a UTF-16 surrogate pair written as two escapes.
+    fn parse_unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.parse_hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high)
+                .ok_or_else(|| self.error("Unpaired surrogate in unicode escape"));
+        }
+        if self.next_char() != Some('\\') || self.next_char() != Some('u') {
+            return Err(self.error("Unpaired surrogate in unicode escape"));
+        }
+        let low = self.parse_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("Unpaired surrogate in unicode escape"));
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, ParseError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.next_char().and_then(|c| c.to_digit(16));
+            code = code * 16 + digit.ok_or_else(|| self.error("Invalid unicode escape"))?;
+        }
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, ParseError> {
+        let (line, column) = (self.line, self.column);
         let mut number = String::new();
 
         if let Some(&'-') = self.chars.peek() {
-            number.push(self.chars.next().unwrap());
+            number.push(self.next_char().unwrap());
         }
 
         while let Some(&c) = self.chars.peek() {
-            if c.is_digit(10) || c == '.' || c == 'e' || c == 'E' || c == '+' || c == '-' {
-                number.push(self.chars.next().unwrap());
+            let underscore = c == '_' && self.options.allow_numeric_underscores;
+            if c.is_ascii_digit()
+                || c == '.'
+                || c == 'e'
+                || c == 'E'
+                || c == '+'
+                || c == '-'
+                || underscore
+            {
+                if self
+                    .options
+                    .max_number_len
+                    .is_some_and(|limit| number.len() >= limit)
+                {
+                    return Err(ParseError::Syntax {
+                        message: "Number is too long".to_string(),
+                        line,
+                        column,
+                    });
+                }
+                number.push(self.next_char().unwrap());
             } else {
                 break;
             }
         }
 
+        let error_at_start = |message: &str| ParseError::Syntax {
+            message: message.to_string(),
+            line,
+            column,
+        };
+        let invalid = || error_at_start("Invalid number");
+        if number.contains('_') {
+            let bytes = number.as_bytes();
+            let between_digits = |i: usize| {
+                i > 0
+                    && bytes[i - 1].is_ascii_digit()
+                    && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)
+            };
+            if (0..bytes.len()).any(|i| bytes[i] == b'_' && !between_digits(i)) {
+                return Err(error_at_start(&format!(
+                    "Misplaced underscore in '{}'",
+                    number
+                )));
+            }
+            number.retain(|c| c != '_');
+        }
+        if let Err(reason) = check_number(&number, self.options.allow_leading_zeros) {
+            return Err(error_at_start(&format!("{} in '{}'", reason, number)));
+        }
+        if let Some(limit) = self.options.max_fraction_digits {
+            let (mantissa, exponent) = number.split_once(['e', 'E']).unwrap_or((&number, "0"));
+            let written = mantissa
+                .split_once('.')
+                .map_or(0, |(_, digits)| digits.len());
+            // Too long an exponent for i64 saturates, as no limit is that large.
+            let exponent = exponent
+                .parse::<i64>()
+                .unwrap_or(match exponent.starts_with('-') {
+                    true => i64::MIN,
+                    false => i64::MAX,
+                });
+            let digits = (written as i64).saturating_sub(exponent).max(0) as u64;
+            if digits > limit as u64 {
+                return Err(error_at_start("Number has too many fraction digits"));
+            }
+        }
+        if let Some(limit) = self.options.max_number_abs {
+            if number.parse::<f64>().map_or(true, |n| n.abs() > limit) {
+                return Err(error_at_start("Number exceeds the maximum magnitude"));
+            }
+        }
+
+        if self.options.allow_bigint_suffix && self.chars.peek() == Some(&'n') {
+            let (sign, digits) = match number.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", number.as_str()),
+            };
+            if digits.chars().all(|c| c.is_ascii_digit()) {
+                self.next_char();
+                let digits = match digits.trim_start_matches('0') {
+                    "" => "0",
+                    trimmed => trimmed,
+                };
+                return Ok(JsonValue::RawNumber(format!("{}{}", sign, digits)));
+            }
+        }
+
+        if self.options.preserve_number_text {
+            return Ok(JsonValue::RawNumber(number));
+        }
+
+        // Integers that fit are kept exact; `-0` stays a float to keep its
+        // sign unless negative zero is being normalized.
+        let normalize_zero = self.options.normalize_negative_zero;
+        if !number.contains(['.', 'e', 'E']) && (number != "-0" || normalize_zero) {
+            if let Ok(i) = number.parse::<i64>() {
+                return Ok(JsonValue::Integer(i));
+            }
+        }
+
         number
             .parse::<f64>()
-            .map(JsonValue::Number)
-            .map_err(|_| "Invalid number".to_string())
+            .map(|n| match n {
+                // `-0.0 == 0.0`, so this only clears the sign.
+                0.0 if normalize_zero => JsonValue::Number(0.0),
+                n => JsonValue::Number(n),
+            })
+            .map_err(|_| invalid())
     }
 
-    fn parse_boolean(&mut self) -> Result<JsonValue, String> {
+    fn parse_boolean(&mut self) -> Result<JsonValue, ParseError> {
         match self.chars.peek() {
-            Some(&'t') => {
-                if self.consume_if_match("true") {
-                    Ok(JsonValue::Boolean(true))
-                } else {
-                    Err("Expected 'true'".to_string())
-                }
-            }
-            Some(&'f') => {
-                if self.consume_if_match("false") {
-                    Ok(JsonValue::Boolean(false))
-                } else {
-                    Err("Expected 'false'".to_string())
-                }
+            Some(&'t') => self
+                .consume_literal("true")
+                .map(|_| JsonValue::Boolean(true)),
+            Some(&'f') => self
+                .consume_literal("false")
+                .map(|_| JsonValue::Boolean(false)),
+            _ => Err(self.error("Expected boolean")),
+        }
+    }
+
+    // With `allow_relaxed_booleans`, consumes a whole word spelling `value`
+    // as `yes`/`on` or `no`/`off` in any case.
+    fn consume_relaxed_boolean(&mut self, value: bool) -> bool {
+        if !self.options.allow_relaxed_booleans {
+            return false;
+        }
+        let word: String = self
+            .lookahead()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect();
+        let spellings = match value {
+            true => ["yes", "on"],
+            false => ["no", "off"],
+        };
+        if spellings.iter().any(|s| word.eq_ignore_ascii_case(s)) {
+            for _ in 0..word.len() {
+                self.next_char();
             }
-            _ => Err("Expected boolean".to_string()),
+            return true;
         }
+        false
     }
 
-    fn parse_null(&mut self) -> Result<JsonValue, String> {
-        if self.consume_if_match("null") {
-            Ok(JsonValue::Null)
+    fn parse_null(&mut self) -> Result<JsonValue, ParseError> {
+        self.consume_literal("null").map(|_| JsonValue::Null)
+    }
+
+    // Checks that the document starts with `open`, for entry points that only
+    // accept one kind of root container.
+    fn expect_root(&mut self, open: char, message: &str) -> Result<(), ParseError> {
+        self.skip_whitespace()?;
+        match self.chars.peek() {
+            Some(&c) if c == open => self.count_token(),
+            Some(_) => Err(self.error(message)),
+            None => Err(ParseError::EmptyInput),
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace()?;
+        if self.options.allow_trailing_punctuation
+            && matches!(self.chars.peek(), Some(&(';' | ',')))
+        {
+            self.next_char();
+            self.skip_whitespace()?;
+        }
+        if self.at_bom() {
+            Err(ParseError::UnexpectedBom {
+                offset: self.offset,
+            })
+        } else if self.chars.peek().is_some() {
+            Err(ParseError::TrailingData {
+                line: self.line,
+                column: self.column,
+                offset: self.offset,
+            })
         } else {
-            Err("Expected 'null'".to_string())
+            Ok(())
         }
     }
 
-    fn consume_if_match(&mut self, expected: &str) -> bool {
-        let mut chars = self.chars.clone();
-        for exp_char in expected.chars() {
-            if chars.next() != Some(exp_char) {
-                return false;
+    // Consumes `expected` one character at a time, failing at the first
+    // character that differs, so `tru]` is reported at the `]`.
+    fn consume_literal(&mut self, expected: &str) -> Result<(), ParseError> {
+        for c in expected.chars() {
+            if self.chars.peek() != Some(&c) {
+                return Err(self.error(format!("Expected '{}'", expected)));
             }
+            self.next_char();
         }
-        for _ in 0..expected.len() {
-            self.chars.next();
+        Ok(())
+    }
+}
+
+// Checks the RFC 8259 number grammar,
+// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`,
+// describing the first violation.
+fn check_number(number: &str, allow_leading_zeros: bool) -> Result<(), &'static str> {
+    let bytes = number.as_bytes();
+    let digits_from = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
         }
-        true
+        i
+    };
+
+    let start = usize::from(bytes.first() == Some(&b'-'));
+    let mut i = digits_from(start);
+    let int_len = i - start;
+    if int_len == 0 {
+        return Err("Missing integer digits");
     }
+    if int_len > 1 && bytes[start] == b'0' && !allow_leading_zeros {
+        return Err("Leading zeros");
+    }
+    if bytes.get(i) == Some(&b'.') {
+        let end = digits_from(i + 1);
+        if end == i + 1 {
+            return Err("Fraction missing digits");
+        }
+        i = end;
+    }
+    if let Some(b'e' | b'E') = bytes.get(i) {
+        i += 1;
+        if let Some(b'+' | b'-') = bytes.get(i) {
+            i += 1;
+        }
+        let end = digits_from(i);
+        if end == i {
+            return Err("Exponent missing digits");
+        }
+        i = end;
+    }
+    match bytes.get(i) {
+        None => Ok(()),
+        Some(b'.') if number[..i].contains(['e', 'E']) => Err("Fractional exponent"),
+        Some(b'.') => Err("Multiple decimal points"),
+        Some(b'e' | b'E') => Err("Multiple exponents"),
+        Some(_) => Err("Misplaced sign"),
+    }
+}
+
+pub fn parse_json(input: &str) -> Result<JsonValue, ParseError> {
+    parse_json_with_options(input, &ParserOptions::default())
 }
 
-pub fn parse_json(input: &str) -> Result<JsonValue, String> {
-    let mut parser = Parser::new(input);
+pub fn parse_json_with_options(
+    input: &str,
+    options: &ParserOptions,
+) -> Result<JsonValue, ParseError> {
+    parse_document(input, options).map(|(value, _)| value)
+}
+
+// Parses `input` as a whole document, also returning the parser for its
+// counters.
+fn parse_document<'a>(
+    input: &'a str,
+    options: &'a ParserOptions,
+) -> Result<(JsonValue, Parser<'a>), ParseError> {
+    if options.require_ascii {
+        if let Some((offset, c)) = input.char_indices().find(|(_, c)| !c.is_ascii()) {
+            let message = format!("Non-ASCII character '{}'; use a \\u escape", c);
+            return Err(ParseError::syntax_at(input, offset, message));
+        }
+    }
+    let mut parser = Parser::new(input, options);
+    parser.skip_whitespace()?;
+    if parser.chars.peek().is_none() {
+        return Err(ParseError::EmptyInput);
+    }
     let value = parser.parse_value()?;
-    parser.skip_whitespace();
-    if parser.chars.next().is_some() {
-        Err("Unexpected characters after JSON value".to_string())
-    } else {
-        Ok(value)
+    parser.finish()?;
+    Ok((value, parser))
+}
+
+/// Counters from one [`parse_json_timed`] run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseMetrics {
+    /// Wall-clock time spent parsing.
+    pub elapsed: Duration,
+    /// Characters consumed, including whitespace and comments.
+    pub chars_read: usize,
+    /// Times the remaining input was cloned to look ahead without consuming,
+    /// once per comment; always 0 unless comments are allowed.
+    pub lookaheads: usize,
+}
+
+/// Like [`parse_json`], also measuring how long parsing took and how much
+/// work the parser did, for finding hot paths.
+pub fn parse_json_timed(input: &str) -> Result<(JsonValue, ParseMetrics), ParseError> {
+    parse_json_timed_with_options(input, &ParserOptions::default())
+}
+
+/// Like [`parse_json_with_options`], also reporting [`ParseMetrics`].
+pub fn parse_json_timed_with_options(
+    input: &str,
+    options: &ParserOptions,
+) -> Result<(JsonValue, ParseMetrics), ParseError> {
+    let start = Instant::now();
+    let (value, parser) = parse_document(input, options)?;
+    let metrics = ParseMetrics {
+        elapsed: start.elapsed(),
+        chars_read: parser.chars_read,
+        lookaheads: parser.lookaheads,
+    };
+    Ok((value, metrics))
+}
+
+/// Reports which kind of value `input` starts with, after any whitespace,
+/// judging by its first character alone. Nothing past that is checked, so
+/// `tru` is still a `Boolean`.
+pub fn peek_type(input: &str) -> Result<JsonType, ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.skip_whitespace()?;
+    match parser.chars.peek() {
+        Some(&'{') => Ok(JsonType::Object),
+        Some(&'[') => Ok(JsonType::Array),
+        Some(&'"') => Ok(JsonType::String),
+        Some(&('-' | '0'..='9')) => Ok(JsonType::Number),
+        Some(&('t' | 'f')) => Ok(JsonType::Boolean),
+        Some(&'n') => Ok(JsonType::Null),
+        Some(&c) => Err(parser.error(format!("Unexpected character: {}", c))),
+        None => Err(ParseError::EmptyInput),
+    }
+}
+
+/// Counts the elements of a top-level array without building them. Nested
+/// values are still fully validated.
+pub fn array_len(input: &str) -> Result<usize, ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.expect_root('[', "Expected array")?;
+    let mut len = 0;
+    parser.parse_elements(|parser| {
+        len += 1;
+        parser.skip_value()
+    })?;
+    parser.finish()?;
+    Ok(len)
+}
+
+/// Parses `input` and records the byte range each value occupies, keyed by
+/// JSON Pointer (`""` is the root). Ranges cover the value only, excluding
+/// surrounding whitespace.
+pub fn parse_with_spans(
+    input: &str,
+) -> Result<(JsonValue, BTreeMap<String, Range<usize>>), ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.spans = Some(BTreeMap::new());
+    parser.skip_whitespace()?;
+    if parser.chars.peek().is_none() {
+        return Err(ParseError::EmptyInput);
+    }
+    let value = parser.parse_value()?;
+    parser.finish()?;
+    Ok((value, parser.spans.unwrap_or_default()))
+}
+
+/// Comments keyed by the JSON Pointer of the object member they precede, as
+/// returned by [`parse_json_with_comments`].
+pub type CommentMap = BTreeMap<String, Vec<String>>;
+
+/// Parses JSON that may contain `//` and `/* */` comments, and returns the
+/// comments that sit on their own lines directly before an object member,
+/// keyed by that member's JSON Pointer. Comments elsewhere, including those
+/// after a value on the same line, are skipped.
+pub fn parse_json_with_comments(input: &str) -> Result<(JsonValue, CommentMap), ParseError> {
+    let options = ParserOptions::default().allow_comments(true);
+    let mut parser = Parser::new(input, &options);
+    parser.comments = Some(BTreeMap::new());
+    parser.skip_whitespace()?;
+    if parser.chars.peek().is_none() {
+        return Err(ParseError::EmptyInput);
+    }
+    let value = parser.parse_value()?;
+    parser.finish()?;
+    Ok((value, parser.comments.unwrap_or_default()))
+}
+
+/// Parses a JSONP response such as `cb({"a": 1});`, returning the callback
+/// name and its single argument. The name is a JavaScript identifier path:
+/// one or more `.`-separated segments, each starting with an ASCII letter,
+/// `_` or `$` and continuing with those or digits, as in
+/// `jQuery.handlers.cb`. Whitespace is allowed around the parts and the
+/// trailing `;` is optional.
+pub fn parse_jsonp(input: &str) -> Result<(String, JsonValue), ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.skip_whitespace()?;
+    if parser.chars.peek().is_none() {
+        return Err(ParseError::EmptyInput);
+    }
+    let mut callback = String::new();
+    loop {
+        match parser.chars.peek() {
+            Some(&c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {}
+            _ => return Err(parser.error("Expected JSONP callback name")),
+        }
+        while let Some(&c) = parser.chars.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+                break;
+            }
+            callback.push(c);
+            parser.next_char();
+        }
+        if parser.chars.peek() != Some(&'.') {
+            break;
+        }
+        callback.push('.');
+        parser.next_char();
+    }
+    parser.skip_whitespace()?;
+    if parser.chars.peek() != Some(&'(') {
+        return Err(parser.error("Expected '(' after callback name"));
+    }
+    parser.next_char();
+    let value = parser.parse_value()?;
+    parser.skip_whitespace()?;
+    if parser.chars.peek() != Some(&')') {
+        return Err(parser.error("Expected ')' after JSONP argument"));
+    }
+    parser.next_char();
+    parser.skip_whitespace()?;
+    if parser.chars.peek() == Some(&';') {
+        parser.next_char();
+    }
+    parser.finish()?;
+    Ok((callback, value))
+}
+
+/// Parses each input independently with [`parse_json`], as when validating
+/// many files: every input gets its own result, in order, and a failure never
+/// stops the rest from being parsed.
+pub fn parse_batch(inputs: &[&str]) -> Vec<Result<JsonValue, ParseError>> {
+    inputs.iter().map(|input| parse_json(input)).collect()
+}
+
+/// Parses each layer and merges them left to right, so later layers override
+/// earlier ones, as with defaults, then environment, then local settings.
+/// Layers are combined with [`merge_patch`]: objects merge member by member,
+/// any other value replaces what was there, and a `null` member removes the
+/// key. The first layer that fails to parse is the error; no layers at all
+/// is `ParseError::EmptyInput`.
+pub fn parse_layered(layers: &[&str]) -> Result<JsonValue, ParseError> {
+    let (first, rest) = layers.split_first().ok_or(ParseError::EmptyInput)?;
+    let mut config = parse_json(first)?;
+    for layer in rest {
+        merge_patch(&mut config, &parse_json(layer)?);
+    }
+    Ok(config)
+}
+
+/// Parses with [`ParserOptions::strict`], for inputs that must be exactly RFC 8259.
+pub fn parse_json_strict(input: &str) -> Result<JsonValue, ParseError> {
+    parse_json_with_options(input, &ParserOptions::strict())
+}
+
+/// Parses an object document but keeps only the listed top-level keys. The
+/// other members are validated and discarded. A non-object root is an error.
+pub fn parse_json_projected(input: &str, keep: &[&str]) -> Result<JsonValue, ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.expect_root('{', "Expected object")?;
+    let mut object = Vec::new();
+    parser.parse_members(|parser, key| {
+        if keep.contains(&key.as_str()) {
+            object.push((key, parser.parse_value()?));
+            Ok(())
+        } else {
+            parser.skip_value()
+        }
+    })?;
+    parser.finish()?;
+    Ok(JsonValue::Object(object))
+}
+
+/// Parses an envelope object such as `{"data": ...}` and returns the value of
+/// its `key` member, skipping the others as [`parse_json_projected`] does. If
+/// the key repeats, the first is used. Fails with `ParseError::MissingField`
+/// if the key is absent.
+pub fn parse_json_unwrap(input: &str, key: &str) -> Result<JsonValue, ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.expect_root('{', "Expected object")?;
+    let mut value = None;
+    parser.parse_members(|parser, member| {
+        if value.is_none() && member == key {
+            value = Some(parser.parse_value()?);
+            Ok(())
+        } else {
+            parser.skip_value()
+        }
+    })?;
+    parser.finish()?;
+    value.ok_or_else(|| ParseError::MissingField {
+        key: key.to_string(),
+    })
+}
+
+/// Parses as much of `input` as possible, for editors that want every problem
+/// at once. Each syntax error is recorded, the broken value becomes `Null`, and
+/// parsing resumes at the next `,`, `]` or `}`. The value is `None` only when
+/// nothing usable could be built, such as on truncated input.
+pub fn parse_with_recovery(input: &str) -> (Option<JsonValue>, Vec<ParseError>) {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.recovering = true;
+    if let Err(error) = parser.skip_whitespace() {
+        return (None, vec![error]);
+    }
+    if parser.chars.peek().is_none() {
+        return (None, vec![ParseError::EmptyInput]);
+    }
+    let value = parser.parse_value().inspect(|_| {
+        if let Err(error) = parser.finish() {
+            parser.errors.push(error);
+        }
+    });
+    let mut errors = parser.errors;
+    match value {
+        Ok(value) => (Some(value), errors),
+        Err(error) => {
+            errors.push(error);
+            (None, errors)
+        }
+    }
+}
+
+/// Parses an RFC 7464 JSON text sequence: values each preceded by the record
+/// separator U+001E and usually followed by a newline.
+///
+/// As the RFC recommends, a record that fails to parse is skipped rather than
+/// ending the sequence, as is a top-level number not followed by whitespace
+/// (it may have been truncated). The only error is input that does not start
+/// with a record separator, which is not a sequence at all.
+pub fn parse_json_seq(input: &str) -> Result<Vec<JsonValue>, ParseError> {
+    let start = input.trim_start();
+    if !start.is_empty() && !start.starts_with('\u{1E}') {
+        let offset = input.len() - start.len();
+        return Err(ParseError::syntax_at(
+            input,
+            offset,
+            "Expected record separator",
+        ));
+    }
+    let values = start
+        .split('\u{1E}')
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| match parse_json(record) {
+            Ok(JsonValue::Integer(_) | JsonValue::Number(_))
+                if !record.ends_with(char::is_whitespace) =>
+            {
+                None
+            }
+            result => result.ok(),
+        })
+        .collect();
+    Ok(values)
+}
+
+/// Parses JSON from raw bytes, reporting the byte offset of any invalid UTF-8.
+pub fn parse_json_bytes(input: &[u8]) -> Result<JsonValue, ParseError> {
+    let input = std::str::from_utf8(input).map_err(|e| ParseError::InvalidUtf8 {
+        offset: e.valid_up_to(),
+    })?;
+    parse_json(input)
+}
+
+/// Decodes a quoted JSON string literal such as `"a\nb"` into the string it
+/// denotes, with the same escape handling as [`parse_json`], including
+/// surrogate pairs. Nothing may precede or follow the quotes.
+pub fn unescape_string(literal: &str) -> Result<String, ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(literal, &options);
+    match parser.chars.peek() {
+        Some(&'"') => {}
+        Some(_) => return Err(parser.error("Expected string literal")),
+        None => return Err(ParseError::EmptyInput),
+    }
+    let string = parser.parse_string()?;
+    if parser.chars.peek().is_some() {
+        return Err(ParseError::TrailingData {
+            line: parser.line,
+            column: parser.column,
+            offset: parser.offset,
+        });
+    }
+    Ok(string)
+}
+
+/// Decompresses gzip data and parses the JSON inside. A corrupt or truncated
+/// stream is a `ParseError::Decompress`, distinct from errors in the JSON.
+#[cfg(feature = "gzip")]
+pub fn parse_json_gzip(data: &[u8]) -> Result<JsonValue, ParseError> {
+    let mut bytes = Vec::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut bytes)
+        .map_err(|e| ParseError::Decompress {
+            message: e.to_string(),
+        })?;
+    parse_json_bytes(&bytes)
+}
+
+/// Like [`parse_json`], but a top-level array has its elements parsed in
+/// parallel on the rayon thread pool, which pays off for large arrays of
+/// records. The result is the same as from `parse_json`. So are errors: on
+/// any failure the input is reparsed sequentially, so positions refer to the
+/// whole input rather than to one element.
+#[cfg(feature = "rayon")]
+pub fn parse_json_parallel(input: &str) -> Result<JsonValue, ParseError> {
+    use rayon::prelude::*;
+
+    let Some(elements) = split_array_elements(input) else {
+        return parse_json(input);
+    };
+    elements
+        .into_par_iter()
+        .map(parse_json)
+        .collect::<Result<Vec<_>, _>>()
+        .map(JsonValue::Array)
+        .or_else(|_| parse_json(input))
+}
+
+// The text of each element of a top-level array, found by tracking strings
+// and nesting only. `None` if the input is not a single array; elements are
+// not validated, which is left to the parser.
+#[cfg(feature = "rayon")]
+fn split_array_elements(input: &str) -> Option<Vec<&str>> {
+    let trimmed = input.trim();
+    let inner = trimmed.strip_prefix('[')?;
+    let mut elements = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, b) in inner.bytes().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' if depth > 0 => depth -= 1,
+            b',' if depth == 0 => {
+                elements.push(&inner[start..i]);
+                start = i + 1;
+            }
+            b']' => {
+                if i + 1 != inner.len() {
+                    return None;
+                }
+                let last = &inner[start..i];
+                if !(elements.is_empty() && last.trim().is_empty()) {
+                    elements.push(last);
+                }
+                return Some(elements);
+            }
+            b'}' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses a document that must consist of a single string, returning its
+/// decoded content. Unlike [`unescape_string`], whitespace around the literal
+/// is allowed, as in [`parse_json`].
+pub fn parse_string_literal(input: &str) -> Result<String, ParseError> {
+    let options = ParserOptions::default();
+    let mut parser = Parser::new(input, &options);
+    parser.skip_whitespace()?;
+    match parser.chars.peek() {
+        Some(&'"') => {}
+        Some(_) => return Err(parser.error("Expected string literal")),
+        None => return Err(ParseError::EmptyInput),
+    }
+    let string = parser.parse_string()?;
+    parser.finish()?;
+    Ok(string)
+}
+
+/// Parses JSON from UTF-16 code units. A leading byte order mark is skipped.
+pub fn parse_json_utf16(input: &[u16]) -> Result<JsonValue, ParseError> {
+    parse_json(&decode_utf16(input)?)
+}
+
+fn decode_utf16(input: &[u16]) -> Result<String, ParseError> {
+    let input = input.strip_prefix(&[0xFEFF]).unwrap_or(input);
+    let mut decoded = String::with_capacity(input.len());
+    let mut offset = 0;
+    for c in char::decode_utf16(input.iter().copied()) {
+        let c = c.map_err(|_| ParseError::InvalidUtf16 { offset })?;
+        offset += c.len_utf16();
+        decoded.push(c);
+    }
+    Ok(decoded)
+}
+
+/// Parses UTF-16 JSON from raw bytes. The byte order comes from the byte order
+/// mark if there is one; otherwise a zero first byte means big-endian (JSON
+/// text starts with an ASCII character) and anything else little-endian.
+pub fn parse_json_utf16_bytes(input: &[u8]) -> Result<JsonValue, ParseError> {
+    parse_json(&decode_utf16_bytes(input)?)
+}
+
+fn decode_utf16_bytes(input: &[u8]) -> Result<String, ParseError> {
+    if !input.len().is_multiple_of(2) {
+        return Err(ParseError::InvalidUtf16 {
+            offset: input.len() / 2,
+        });
+    }
+    let big_endian = match input {
+        [0xFE, 0xFF, ..] => true,
+        [0xFF, 0xFE, ..] => false,
+        [first, ..] => *first == 0,
+        [] => false,
+    };
+    let units: Vec<u16> = input
+        .chunks_exact(2)
+        .map(|pair| match big_endian {
+            true => u16::from_be_bytes([pair[0], pair[1]]),
+            false => u16::from_le_bytes([pair[0], pair[1]]),
+        })
+        .collect();
+    decode_utf16(&units)
+}
+
+// Decodes JSON bytes in whichever encoding they use, following RFC 4627:
+// a byte order mark decides if present, and otherwise a zero in either of
+// the first two bytes means UTF-16, since JSON text starts with ASCII.
+pub(crate) fn decode_json_bytes(input: &[u8]) -> Result<Cow<'_, str>, ParseError> {
+    let (bom, utf8) = match input {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => (3, rest),
+        [0xFE, 0xFF, ..] | [0xFF, 0xFE, ..] | [0, _, ..] | [_, 0, ..] => {
+            return decode_utf16_bytes(input).map(Cow::Owned);
+        }
+        _ => (0, input),
+    };
+    std::str::from_utf8(utf8)
+        .map(Cow::Borrowed)
+        .map_err(|e| ParseError::InvalidUtf8 {
+            offset: bom + e.valid_up_to(),
+        })
+}
+
+/// Reads a whole document from `reader` and parses it, detecting UTF-8
+/// (with or without a byte order mark) or UTF-16 in either byte order from
+/// the leading bytes. Read failures are `ParseError::Io`.
+pub fn parse_json_reader<R: Read>(mut reader: R) -> Result<JsonValue, ParseError> {
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input).map_err(|e| ParseError::Io {
+        message: e.to_string(),
+    })?;
+    parse_json(&decode_json_bytes(&input)?)
+}
+
+/// Reads messages framed by a 4-byte big-endian length prefix, as RPC
+/// transports often send them, and parses each frame's bytes with
+/// [`parse_json_bytes`]. Iteration ends cleanly at end of input between
+/// frames; input ending inside a frame is `ParseError::UnexpectedEof`, and a
+/// read failure is `ParseError::Io`. Nothing is read after an error.
+pub fn parse_framed<R: Read>(mut reader: R) -> impl Iterator<Item = Result<JsonValue, ParseError>> {
+    let mut failed = false;
+    std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let result = read_frame(&mut reader).transpose()?;
+        let result = result.and_then(|frame| parse_json_bytes(&frame));
+        failed = result.is_err();
+        Some(result)
+    })
+}
+
+// The next frame's bytes, or `None` at end of input before its prefix.
+fn read_frame(reader: &mut impl Read) -> Result<Option<Vec<u8>>, ParseError> {
+    let mut prefix = [0; 4];
+    match read_full(reader, &mut prefix)? {
+        0 => return Ok(None),
+        4 => {}
+        _ => return Err(ParseError::UnexpectedEof),
+    }
+    // Read through `take` rather than into a buffer of the announced size,
+    // so a bogus prefix cannot allocate gigabytes up front.
+    let len = u32::from_be_bytes(prefix) as usize;
+    let mut frame = Vec::new();
+    reader
+        .take(len as u64)
+        .read_to_end(&mut frame)
+        .map_err(|e| ParseError::Io {
+            message: e.to_string(),
+        })?;
+    if frame.len() < len {
+        return Err(ParseError::UnexpectedEof);
+    }
+    Ok(Some(frame))
+}
+
+// Fills `buf` unless the input ends first, returning how much was read.
+pub(crate) fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, ParseError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                return Err(ParseError::Io {
+                    message: e.to_string(),
+                })
+            }
+        }
+    }
+    Ok(filled)
+}
+
+/// A lexical token of JSON text, as yielded by [`Tokenizer`]. Numbers are
+/// split the way [`JsonValue`] splits them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    BraceOpen,
+    BraceClose,
+    BracketOpen,
+    BracketClose,
+    Colon,
+    Comma,
+    String(String),
+    Integer(i64),
+    Number(f64),
+    RawNumber(String),
+    True,
+    False,
+    Null,
+}
+
+/// Splits JSON text into tokens with their byte ranges, without building a
+/// tree or checking that the tokens form a valid document, for tools such as
+/// syntax highlighters. Whitespace and any comments the options allow are
+/// skipped. Strings and numbers are read exactly as the parser reads them;
+/// iteration ends after the first error.
+pub struct Tokenizer<'a> {
+    parser: Parser<'a>,
+    failed: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        static DEFAULT: OnceLock<ParserOptions> = OnceLock::new();
+        Tokenizer::with_options(input, DEFAULT.get_or_init(ParserOptions::default))
+    }
+
+    pub fn with_options(input: &'a str, options: &'a ParserOptions) -> Self {
+        Tokenizer {
+            parser: Parser::new(input, options),
+            failed: false,
+        }
+    }
+
+    fn next_token(&mut self, c: char) -> Result<Token, ParseError> {
+        let parser = &mut self.parser;
+        parser.count_token()?;
+        let punctuation = match c {
+            '{' => Some(Token::BraceOpen),
+            '}' => Some(Token::BraceClose),
+            '[' => Some(Token::BracketOpen),
+            ']' => Some(Token::BracketClose),
+            ':' => Some(Token::Colon),
+            ',' => Some(Token::Comma),
+            _ => None,
+        };
+        if let Some(token) = punctuation {
+            parser.next_char();
+            return Ok(token);
+        }
+        match c {
+            '"' => parser.parse_string().map(Token::String),
+            '-' | '0'..='9' => Ok(match parser.parse_number()? {
+                JsonValue::Integer(i) => Token::Integer(i),
+                JsonValue::RawNumber(raw) => Token::RawNumber(raw),
+                // `parse_number` yields no other variants.
+                number => Token::Number(number.as_f64().unwrap_or(f64::NAN)),
+            }),
+            't' | 'f' => Ok(match parser.parse_boolean()? {
+                JsonValue::Boolean(true) => Token::True,
+                _ => Token::False,
+            }),
+            'n' => parser.parse_null().map(|_| Token::Null),
+            c => Err(parser.error(format!("Unexpected character: {}", c))),
+        }
+    }
+}
+
+impl Iterator for Tokenizer<'_> {
+    type Item = Result<(Token, Range<usize>), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        if let Err(error) = self.parser.skip_whitespace() {
+            self.failed = true;
+            return Some(Err(error));
+        }
+        let c = *self.parser.chars.peek()?;
+        let start = self.parser.offset;
+        match self.next_token(c) {
+            Ok(token) => Some(Ok((token, start..self.parser.offset))),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::options::DEFAULT_MAX_NUMBER_LEN;
+
+    fn syntax(message: &str, line: usize, column: usize) -> ParseError {
+        ParseError::Syntax {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn empty_or_blank_input_is_empty_input() {
+        assert_eq!(parse_json(""), Err(ParseError::EmptyInput));
+        assert_eq!(parse_json("  \n\t"), Err(ParseError::EmptyInput));
+        assert_eq!(parse_json("[").unwrap_err(), ParseError::UnexpectedEof);
+        assert_eq!(parse_json(" 0 "), Ok(JsonValue::Integer(0)));
+    }
+
+    #[test]
+    fn max_tokens_counts_values_and_punctuation() {
+        let input = r#"{"a": [1, 2]}"#;
+        let within = ParserOptions::default().max_tokens(Some(8));
+        assert!(parse_json_with_options(input, &within).is_ok());
+        let below = ParserOptions::default().max_tokens(Some(7));
+        assert_eq!(
+            parse_json_with_options(input, &below),
+            Err(ParseError::TooManyTokens { limit: 7 })
+        );
+        let single = ParserOptions::default().max_tokens(Some(0));
+        assert_eq!(
+            parse_json_with_options("null", &single),
+            Err(ParseError::TooManyTokens { limit: 0 })
+        );
+    }
+
+    #[test]
+    fn parse_json_bytes_reports_the_invalid_utf8_offset() {
+        assert_eq!(
+            parse_json_bytes("{\"é\": 1}".as_bytes()),
+            parse_json("{\"é\": 1}")
+        );
+        assert_eq!(
+            parse_json_bytes(b"[\"ok\", \"\xff\"]"),
+            Err(ParseError::InvalidUtf8 { offset: 8 })
+        );
+        // A truncated multi-byte sequence is reported where it starts.
+        assert_eq!(
+            parse_json_bytes(b"\"\xc3"),
+            Err(ParseError::InvalidUtf8 { offset: 1 })
+        );
+    }
+
+    #[test]
+    fn array_len_counts_top_level_elements() {
+        assert_eq!(array_len("[]"), Ok(0));
+        assert_eq!(array_len(r#" [1, {"a": [2, 3]}, "x", null] "#), Ok(4));
+        assert_eq!(
+            array_len(r#"{"a": 1}"#),
+            Err(syntax("Expected array", 1, 1))
+        );
+        assert_eq!(array_len(""), Err(ParseError::EmptyInput));
+        assert_eq!(array_len("[1, 2"), Err(ParseError::UnexpectedEof));
+        // Nested values are validated even though they are not built.
+        assert!(array_len(r#"[1, {"a": tru}]"#).is_err());
+        assert!(array_len("[1] 2").is_err());
+    }
+
+    #[test]
+    fn bigint_suffix_yields_raw_numbers_when_allowed() {
+        let options = ParserOptions::default().allow_bigint_suffix(true);
+        assert_eq!(
+            parse_json_with_options("[123n, -9007199254740993n, 0n, 4]", &options),
+            Ok(JsonValue::Array(vec![
+                JsonValue::RawNumber("123".into()),
+                JsonValue::RawNumber("-9007199254740993".into()),
+                JsonValue::RawNumber("0".into()),
+                JsonValue::Integer(4),
+            ]))
+        );
+        assert!(parse_json_with_options("1.5n", &options).is_err());
+        assert!(parse_json_with_options("1e3n", &options).is_err());
+        assert!(parse_json("123n").is_err());
+        assert!(parse_json("[123n]").is_err());
+    }
+
+    #[test]
+    fn number_grammar_accepts_rfc_8259_numbers() {
+        let cases = [
+            ("0", JsonValue::Integer(0)),
+            ("-0", JsonValue::Number(-0.0)),
+            ("10", JsonValue::Integer(10)),
+            ("0.5", JsonValue::Number(0.5)),
+            ("-1.25e+2", JsonValue::Number(-125.0)),
+            ("1E-2", JsonValue::Number(0.01)),
+            ("0e0", JsonValue::Number(0.0)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_json(input), Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn number_grammar_rejects_malformed_numbers() {
+        for input in [
+            "01", "-01", "00", "1.", ".5", "1e", "1e+", "-", "+1", "1.2.3", "--1",
+        ] {
+            assert!(parse_json(input).is_err(), "{} should be rejected", input);
+        }
+        assert_eq!(
+            parse_json("[1, 007]"),
+            Err(syntax("Leading zeros in '007'", 1, 5))
+        );
+    }
+
+    #[test]
+    fn allow_leading_zeros_relaxes_only_the_integer_part() {
+        let options = ParserOptions::default().allow_leading_zeros(true);
+        assert_eq!(
+            parse_json_with_options("007", &options),
+            Ok(JsonValue::Integer(7))
+        );
+        assert_eq!(
+            parse_json_with_options("-00.5", &options),
+            Ok(JsonValue::Number(-0.5))
+        );
+        assert!(parse_json_with_options("1.", &options).is_err());
+        assert!(parse_json_with_options("-", &options).is_err());
+    }
+
+    #[test]
+    fn skip_keys_drops_members_at_any_depth() {
+        let options = ParserOptions::default().skip_keys(["secret"]);
+        assert_eq!(
+            parse_json_with_options(
+                r#"{"id": 1, "secret": {"deep": [1, 2]}, "items": [{"secret": 2, "x": 3}]}"#,
+                &options
+            ),
+            parse_json(r#"{"id": 1, "items": [{"x": 3}]}"#)
+        );
+        // Skipped values must still be valid JSON.
+        assert!(parse_json_with_options(r#"{"secret": [1,, 2], "id": 1}"#, &options).is_err());
+        assert!(parse_json_with_options(r#"{"secret": tru}"#, &options).is_err());
+    }
+
+    #[test]
+    fn parse_json_projected_keeps_only_listed_keys() {
+        assert_eq!(
+            parse_json_projected(r#"{"a": 1, "b": [2], "c": {"d": 3}}"#, &["c", "a", "z"]),
+            parse_json(r#"{"a": 1, "c": {"d": 3}}"#)
+        );
+        assert_eq!(parse_json_projected(r#"{"a": 1}"#, &[]), parse_json("{}"));
+        assert_eq!(
+            parse_json_projected("[1]", &["a"]),
+            Err(syntax("Expected object", 1, 1))
+        );
+        assert!(parse_json_projected(r#"{"a": 1, "b": [2,]}"#, &["a"]).is_err());
+        assert!(parse_json_projected(r#"{"a": 1} x"#, &["a"]).is_err());
+    }
+
+    #[test]
+    fn allow_non_string_keys_stringifies_scalar_keys() {
+        let options = ParserOptions::default().allow_non_string_keys(true);
+        assert_eq!(
+            parse_json_with_options(
+                r#"{1: "a", -2.5: "b", true: "c", null: "d", "e": 5}"#,
+                &options
+            ),
+            parse_json(r#"{"1": "a", "-2.5": "b", "true": "c", "null": "d", "e": 5}"#)
+        );
+        assert!(parse_json_with_options(r#"{[1]: "a"}"#, &options).is_err());
+        assert!(parse_json_with_options("{key: 1}", &options).is_err());
+        assert_eq!(
+            parse_json(r#"{1: "a"}"#),
+            Err(syntax("Expected string key in object", 1, 2))
+        );
+    }
+
+    #[test]
+    fn parse_with_recovery_reports_every_error() {
+        let (value, errors) = parse_with_recovery(r#"{"a": x, "b": [1, @], "c": 3}"#);
+        assert_eq!(
+            value,
+            parse_json(r#"{"a": null, "b": [1, null], "c": 3}"#).ok()
+        );
+        assert_eq!(
+            errors,
+            [
+                syntax("Unexpected character: x", 1, 7),
+                syntax("Unexpected character: @", 1, 19),
+            ]
+        );
+
+        let (value, errors) = parse_with_recovery(r#"[1, tru, {"a" 2}, 3]"#);
+        assert_eq!(value, parse_json("[1, null, {}, 3]").ok());
+        assert_eq!(
+            errors,
+            [
+                syntax("Expected 'true'", 1, 8),
+                syntax("Expected ':' in object", 1, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_with_recovery_gives_up_on_truncated_input() {
+        assert_eq!(
+            parse_with_recovery("[1, 2]"),
+            (parse_json("[1, 2]").ok(), vec![])
+        );
+        assert_eq!(
+            parse_with_recovery("[1, 2"),
+            (None, vec![ParseError::UnexpectedEof])
+        );
+        assert_eq!(
+            parse_with_recovery(" "),
+            (None, vec![ParseError::EmptyInput])
+        );
+        assert_eq!(
+            parse_with_recovery("[1] 2"),
+            (
+                parse_json("[1]").ok(),
+                vec![ParseError::TrailingData {
+                    line: 1,
+                    column: 5,
+                    offset: 4
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_key_error_points_at_the_repeated_key() {
+        let input = "{\n  \"a\": 1,\n  \"b\": {\"a\": 2},\n  \"a\": 3\n}";
+        assert_eq!(
+            parse_json_strict(input),
+            Err(syntax("Duplicate key 'a'", 4, 3))
+        );
+        assert_eq!(
+            parse_json_strict(r#"{"a": {"x": 1, "x": 2}}"#),
+            Err(syntax("Duplicate key 'x'", 1, 16))
+        );
+        // By default every entry is kept.
+        assert_eq!(
+            parse_json(r#"{"a": 1, "a": 2}"#),
+            Ok(JsonValue::Object(vec![
+                ("a".into(), JsonValue::Integer(1)),
+                ("a".into(), JsonValue::Integer(2)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn strict_rejects_raw_control_characters_and_loose_whitespace() {
+        assert!(parse_json_strict("{\"a\": \"tab\\there\"}").is_ok());
+        assert!(parse_json_strict("{\"a\": \"tab\there\"}").is_err());
+        assert!(parse_json("{\"a\": \"tab\there\"}").is_ok());
+        assert!(parse_json_strict("[1,\u{00A0}2]").is_err());
+        assert!(parse_json("[1,\u{00A0}2]").is_ok());
+    }
+
+    #[test]
+    fn parse_with_spans_records_byte_ranges_by_pointer() {
+        let input = r#" {"a": [1, "é"], "b/c": {"d": null}} "#;
+        let (value, spans) = parse_with_spans(input).unwrap();
+        assert_eq!(Ok(value), parse_json(input));
+        let span = |pointer: &str| &input[spans[pointer].clone()];
+        assert_eq!(span(""), r#"{"a": [1, "é"], "b/c": {"d": null}}"#);
+        assert_eq!(span("/a"), r#"[1, "é"]"#);
+        assert_eq!(span("/a/0"), "1");
+        assert_eq!(span("/a/1"), r#""é""#);
+        assert_eq!(spans["/a/1"], 11..15);
+        assert_eq!(span("/b~1c/d"), "null");
+        assert_eq!(spans.len(), 6);
+        assert!(parse_with_spans("[1,]").is_err());
+    }
+
+    #[test]
+    fn capacity_hints_preallocate_containers() {
+        let options = ParserOptions::default()
+            .array_capacity_hint(Some(32))
+            .object_capacity_hint(Some(16));
+        let mut value = parse_json_with_options(r#"[{"a": 1}]"#, &options).unwrap();
+        assert_eq!(value, parse_json(r#"[{"a": 1}]"#).unwrap());
+        assert!(value.as_array_mut().unwrap().capacity() >= 32);
+        let object = value.as_array_mut().unwrap()[0].as_object_mut().unwrap();
+        assert!(object.capacity() >= 16);
+
+        let mut plain = parse_json("[1]").unwrap();
+        assert!(plain.as_array_mut().unwrap().capacity() < 32);
+    }
+
+    #[test]
+    fn empty_as_null_replaces_empty_values() {
+        let options = ParserOptions::default().empty_as_null(true);
+        assert_eq!(
+            parse_json_with_options(
+                r#"{"a": "", "b": [], "c": {}, "d": [[]], "e": " "}"#,
+                &options
+            ),
+            parse_json(r#"{"a": null, "b": null, "c": null, "d": [null], "e": " "}"#)
+        );
+        assert_eq!(parse_json_with_options("{}", &options), Ok(JsonValue::Null));
+        assert_eq!(
+            parse_json(r#"{"a": ""}"#),
+            Ok(JsonValue::Object(vec![(
+                "a".into(),
+                JsonValue::String(String::new())
+            )]))
+        );
+        assert!(parse_json_with_options("[,]", &options).is_err());
+    }
+
+    fn utf16_bytes(text: &str, bom: bool, big_endian: bool) -> Vec<u8> {
+        let bom = bom.then_some(0xFEFF);
+        bom.into_iter()
+            .chain(text.encode_utf16())
+            .flat_map(|unit| match big_endian {
+                true => unit.to_be_bytes(),
+                false => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_json_utf16_decodes_each_byte_order() {
+        let text = r#"{"emoji": "😀", "n": [1]}"#;
+        let expected = parse_json(text);
+        let units: Vec<u16> = text.encode_utf16().collect();
+        assert_eq!(parse_json_utf16(&units), expected);
+        let with_bom: Vec<u16> = [0xFEFF].into_iter().chain(units).collect();
+        assert_eq!(parse_json_utf16(&with_bom), expected);
+        for bom in [true, false] {
+            for big_endian in [true, false] {
+                let bytes = utf16_bytes(text, bom, big_endian);
+                assert_eq!(
+                    parse_json_utf16_bytes(&bytes),
+                    expected,
+                    "{} {}",
+                    bom,
+                    big_endian
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_json_utf16_rejects_malformed_units() {
+        // An unpaired high surrogate at code unit 2.
+        let units = [u16::from(b'"'), u16::from(b'a'), 0xD83D, u16::from(b'"')];
+        assert_eq!(
+            parse_json_utf16(&units),
+            Err(ParseError::InvalidUtf16 { offset: 2 })
+        );
+        let mut bytes = utf16_bytes("[1]", false, false);
+        bytes.push(0);
+        assert_eq!(
+            parse_json_utf16_bytes(&bytes),
+            Err(ParseError::InvalidUtf16 { offset: 3 })
+        );
+        assert_eq!(parse_json_utf16_bytes(&[]), Err(ParseError::EmptyInput));
+    }
+
+    #[test]
+    fn comments_are_skipped_when_allowed() {
+        let options = ParserOptions::default().allow_comments(true);
+        let input = "// leading\n[1, /* inline */ 2 /* trailing */]\n// done";
+        assert_eq!(
+            parse_json_with_options(input, &options),
+            parse_json("[1, 2]")
+        );
+        assert!(parse_json("[1, /* inline */ 2]").is_err());
+    }
+
+    #[test]
+    fn unterminated_block_comment_is_an_error_at_its_opening() {
+        let options = ParserOptions::default().allow_comments(true);
+        assert_eq!(
+            parse_json_with_options("[1,\n  /* never closed", &options),
+            Err(syntax("Unterminated block comment", 2, 3))
+        );
+        assert_eq!(
+            parse_json_with_options("[1] /* never closed *", &options),
+            Err(syntax("Unterminated block comment", 1, 5))
+        );
+        assert_eq!(
+            parse_json_with_options("[1] // last line", &options),
+            parse_json("[1]")
+        );
+    }
+
+    #[test]
+    fn parse_json_with_comments_keys_comments_by_member() {
+        let input = "{\n  // The user\n  /* primary */\n  \"user\": {\n    // Login name\n    \"name\": \"ann\", // inline\n    \"a/b\": 1\n  }\n}";
+        let (value, comments) = parse_json_with_comments(input).unwrap();
+        assert_eq!(
+            Ok(value),
+            parse_json(r#"{"user": {"name": "ann", "a/b": 1}}"#)
+        );
+        let expected: CommentMap = [
+            (
+                "/user".to_string(),
+                vec!["The user".to_string(), "primary".to_string()],
+            ),
+            ("/user/name".to_string(), vec!["Login name".to_string()]),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(comments, expected);
+        assert!(parse_json_with_comments("[1, /* open").is_err());
+    }
+
+    #[test]
+    fn trailing_data_reports_the_first_extra_character() {
+        assert_eq!(
+            parse_json("{\"a\": 1}\n  {\"b\": 2}"),
+            Err(ParseError::TrailingData {
+                line: 2,
+                column: 3,
+                offset: 11
+            })
+        );
+        assert_eq!(
+            parse_json("1 2"),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 3,
+                offset: 2
+            })
+        );
+        assert_eq!(parse_json("[1]  \n\t"), parse_json("[1]"));
+    }
+
+    #[test]
+    fn rename_key_applies_to_every_key_before_other_options() {
+        let options = ParserOptions::default()
+            .rename_key(|key| key.to_lowercase())
+            .duplicate_keys(DuplicateKeyPolicy::Error);
+        assert_eq!(
+            parse_json_with_options(r#"{"Name": "a", "Items": [{"ID": 1}]}"#, &options),
+            parse_json(r#"{"name": "a", "items": [{"id": 1}]}"#)
+        );
+        // Keys that only differ before renaming collide afterwards.
+        assert_eq!(
+            parse_json_with_options(r#"{"id": 1, "ID": 2}"#, &options),
+            Err(syntax("Duplicate key 'id'", 1, 11))
+        );
+        assert_eq!(
+            parse_json_with_options(r#"["Name"]"#, &options),
+            parse_json(r#"["Name"]"#)
+        );
+    }
+
+    #[test]
+    fn allow_hash_comments_skips_shebang_and_hash_lines() {
+        let options = ParserOptions::default().allow_hash_comments(true);
+        let input = "#!/usr/bin/env tool\n# settings\n{\"a\": 1, # trailing\n \"b\": 2}";
+        assert_eq!(
+            parse_json_with_options(input, &options),
+            parse_json(r#"{"a": 1, "b": 2}"#)
+        );
+        // `#` comments do not enable `//` ones, nor the other way round.
+        assert!(parse_json_with_options("// no\n1", &options).is_err());
+        let slashes = ParserOptions::default().allow_comments(true);
+        assert!(parse_json_with_options("# no\n1", &slashes).is_err());
+        assert!(parse_json("#!/bin/sh\n1").is_err());
+    }
+
+    #[test]
+    fn unterminated_string_reports_its_opening_quote() {
+        assert_eq!(
+            parse_json("{\"a\": [1,\n  \"never closed]}"),
+            Err(ParseError::UnterminatedString { line: 2, column: 3 })
+        );
+        assert_eq!(
+            parse_json("\"ends with escape\\"),
+            Err(ParseError::UnterminatedString { line: 1, column: 1 })
+        );
+        assert_eq!(
+            parse_json("{\"key"),
+            Err(ParseError::UnterminatedString { line: 1, column: 2 })
+        );
+        assert_eq!(parse_json("\"\""), Ok(JsonValue::String(String::new())));
+    }
+
+    fn message(error: ParseError) -> String {
+        match error {
+            ParseError::Syntax { message, .. } => message,
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unescape_string_decodes_escapes_and_surrogate_pairs() {
+        assert_eq!(
+            unescape_string(r#""a\nb\t\"\\\/""#),
+            Ok("a\nb\t\"\\/".to_string())
+        );
+        assert_eq!(unescape_string(r#""é😀""#), Ok("é😀".to_string()));
+        assert_eq!(unescape_string(r#""""#), Ok(String::new()));
+        assert_eq!(
+            message(unescape_string(r#" "a""#).unwrap_err()),
+            "Expected string literal"
+        );
+        assert_eq!(
+            unescape_string(r#""a" "#),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 4,
+                offset: 3
+            })
+        );
+    }
+
+    #[test]
+    fn unescape_string_rejects_lone_surrogates() {
+        for literal in [r#""\uD83D""#, r#""\uD83Dx""#, r#""\uD83DA""#, r#""\uDE00""#] {
+            assert_eq!(
+                message(unescape_string(literal).unwrap_err()),
+                "Unpaired surrogate in unicode escape",
+                "{}",
+                literal
+            );
+        }
+        assert_eq!(
+            message(unescape_string(r#""\u12G4""#).unwrap_err()),
+            "Invalid unicode escape"
+        );
+        assert_eq!(
+            message(unescape_string(r#""\x""#).unwrap_err()),
+            "Invalid escape character"
+        );
+    }
+
+    #[test]
+    fn peek_type_judges_by_the_first_character() {
+        let cases = [
+            (" {\"a\": 1}", JsonType::Object),
+            ("\n[", JsonType::Array),
+            ("\"s", JsonType::String),
+            ("-1", JsonType::Number),
+            ("7", JsonType::Number),
+            ("tru", JsonType::Boolean),
+            ("false", JsonType::Boolean),
+            ("null", JsonType::Null),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(peek_type(input), Ok(expected), "{}", input);
+            if let Ok(value) = parse_json(input) {
+                assert_eq!(value.json_type(), expected);
+            }
+        }
+        assert_eq!(peek_type(" "), Err(ParseError::EmptyInput));
+        assert_eq!(peek_type("x"), Err(syntax("Unexpected character: x", 1, 1)));
+        assert_eq!(JsonType::Number.to_string(), "number");
+    }
+
+    #[test]
+    fn parse_json_seq_reads_rfc_7464_records() {
+        let input = "\u{1E}{\"a\": 1}\n\u{1E}[2]\n\u{1E}3\n\u{1E}\"s\"";
+        assert_eq!(
+            parse_json_seq(input),
+            Ok(vec![
+                parse_json(r#"{"a": 1}"#).unwrap(),
+                parse_json("[2]").unwrap(),
+                JsonValue::Integer(3),
+                JsonValue::String("s".into()),
+            ])
+        );
+        assert_eq!(parse_json_seq(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_json_seq_skips_broken_and_truncated_records() {
+        // `12` without a trailing newline may have been cut short from `123`.
+        let input = "\u{1E}[1, \n\u{1E}true\n\u{1E}\u{1E}12";
+        assert_eq!(parse_json_seq(input), Ok(vec![JsonValue::Boolean(true)]));
+        assert_eq!(
+            parse_json_seq("\n [1]\n"),
+            Err(syntax("Expected record separator", 2, 2))
+        );
+    }
+
+    #[test]
+    fn number_limits_bound_magnitude_and_fraction_digits() {
+        let options = ParserOptions::default()
+            .max_number_abs(Some(1e6))
+            .max_fraction_digits(Some(2));
+        assert_eq!(
+            parse_json_with_options("[-1000000, 0.25, 1.5e2, 12]", &options),
+            parse_json("[-1000000, 0.25, 150.0, 12]")
+        );
+        assert_eq!(
+            parse_json_with_options("[1, 1000001]", &options),
+            Err(syntax("Number exceeds the maximum magnitude", 1, 5))
+        );
+        assert_eq!(
+            parse_json_with_options("-1e7", &options),
+            Err(syntax("Number exceeds the maximum magnitude", 1, 1))
+        );
+        assert_eq!(
+            parse_json_with_options("0.125", &options),
+            Err(syntax("Number has too many fraction digits", 1, 1))
+        );
+        // The exponent moves the point: `1.5e-10` is 0.00000000015.
+        assert_eq!(
+            parse_json_with_options("1.5e-10", &options),
+            Err(syntax("Number has too many fraction digits", 1, 1))
+        );
+        assert!(parse_json_with_options("1.25e-1", &options).is_err());
+        assert!(parse_json_with_options("1.5e-1", &options).is_ok());
+        assert!(parse_json_with_options("0.125E+1", &options).is_ok());
+        assert!(parse_json_with_options("1e-99999999999999999999", &options).is_err());
+        let exact = ParserOptions::default().max_fraction_digits(Some(11));
+        assert!(parse_json_with_options("1.5e-10", &exact).is_ok());
+        assert!(parse_json("1e300").is_ok());
+    }
+
+    #[cfg(feature = "gzip")]
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn parse_json_gzip_decompresses_then_parses() {
+        let input = r#"{"items": [1, 2, 3], "name": "z"}"#;
+        assert_eq!(parse_json_gzip(&gzip(input.as_bytes())), parse_json(input));
+        // Errors in the decompressed JSON are the ones parse_json reports.
+        assert_eq!(parse_json_gzip(&gzip(b"[1, 2")), parse_json("[1, 2"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn parse_json_gzip_reports_corrupt_streams() {
+        let compressed = gzip(br#"{"items": [1, 2, 3]}"#);
+        let truncated = &compressed[..compressed.len() / 2];
+        assert!(matches!(
+            parse_json_gzip(truncated),
+            Err(ParseError::Decompress { .. })
+        ));
+        assert!(matches!(
+            parse_json_gzip(b"not gzip at all"),
+            Err(ParseError::Decompress { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_string_literal_decodes_a_lone_string() {
+        assert_eq!(
+            parse_string_literal(" \"a\\tb\\u00e9\" \n"),
+            Ok("a\tb\u{e9}".to_string())
+        );
+        assert_eq!(parse_string_literal(r#""""#), Ok(String::new()));
+    }
+
+    #[test]
+    fn parse_string_literal_rejects_other_documents() {
+        assert_eq!(
+            parse_string_literal("  42"),
+            Err(syntax("Expected string literal", 1, 3))
+        );
+        assert_eq!(parse_string_literal("   "), Err(ParseError::EmptyInput));
+        assert_eq!(
+            parse_string_literal(r#""a" "b""#),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 5,
+                offset: 4
+            })
+        );
+        assert_eq!(
+            parse_string_literal(r#" "abc"#),
+            Err(ParseError::UnterminatedString { line: 1, column: 2 })
+        );
+    }
+
+    #[test]
+    fn trailing_punctuation_allows_one_semicolon_or_comma() {
+        let options = ParserOptions::default().allow_trailing_punctuation(true);
+        assert_eq!(
+            parse_json_with_options(r#"{"a": 1};"#, &options),
+            parse_json(r#"{"a": 1}"#)
+        );
+        assert_eq!(
+            parse_json_with_options("[1, 2] ,\n", &options),
+            parse_json("[1, 2]")
+        );
+        assert_eq!(
+            parse_json_with_options("1;;", &options),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 3,
+                offset: 2
+            })
+        );
+        assert_eq!(
+            parse_json_with_options("1; 2", &options),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 4,
+                offset: 3
+            })
+        );
+    }
+
+    #[test]
+    fn trailing_punctuation_is_rejected_by_default() {
+        assert_eq!(
+            parse_json("[1];"),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 4,
+                offset: 3
+            })
+        );
+        let options = ParserOptions::default().allow_trailing_punctuation(true);
+        assert!(parse_json_with_options(";", &options).is_err());
+    }
+
+    #[test]
+    fn malformed_numbers_name_the_problem_and_the_number() {
+        let cases = [
+            ("[-]", "Missing integer digits in '-'"),
+            ("[-.5]", "Missing integer digits in '-.5'"),
+            ("[012]", "Leading zeros in '012'"),
+            ("[-00.5]", "Leading zeros in '-00.5'"),
+            ("[1.]", "Fraction missing digits in '1.'"),
+            ("[1.e5]", "Fraction missing digits in '1.e5'"),
+            ("[1e]", "Exponent missing digits in '1e'"),
+            ("[1e+]", "Exponent missing digits in '1e+'"),
+            ("[1e5.5]", "Fractional exponent in '1e5.5'"),
+            ("[1.2.3]", "Multiple decimal points in '1.2.3'"),
+            ("[1e2e3]", "Multiple exponents in '1e2e3'"),
+            ("[1-2]", "Misplaced sign in '1-2'"),
+            ("[1.5+]", "Misplaced sign in '1.5+'"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_json(input), Err(syntax(expected, 1, 2)), "{input}");
+        }
+    }
+
+    #[test]
+    fn malformed_numbers_are_reported_at_their_start() {
+        assert_eq!(
+            parse_json("{\n  \"a\": 1.2.3}"),
+            Err(syntax("Multiple decimal points in '1.2.3'", 2, 8))
+        );
+        assert!(parse_json("[0, -0, 0.5, -1.5e-3, 2E+10]").is_ok());
+        let options = ParserOptions::default().allow_leading_zeros(true);
+        assert!(parse_json_with_options("[007, 00.5]", &options).is_ok());
+    }
+
+    #[test]
+    fn numbers_longer_than_the_default_limit_are_rejected() {
+        let longest = format!("0.{}", "0".repeat(DEFAULT_MAX_NUMBER_LEN - 2));
+        assert_eq!(parse_json(&longest), Ok(JsonValue::Number(0.0)));
+        let too_long = format!("[{}0]", longest);
+        assert_eq!(
+            parse_json(&too_long),
+            Err(syntax("Number is too long", 1, 2))
+        );
+    }
+
+    #[test]
+    fn max_number_len_is_configurable() {
+        let options = ParserOptions::default().max_number_len(Some(3));
+        assert_eq!(
+            parse_json_with_options("[123, -12]", &options),
+            parse_json("[123, -12]")
+        );
+        assert_eq!(
+            parse_json_with_options("[1, -123]", &options),
+            Err(syntax("Number is too long", 1, 5))
+        );
+        let unlimited = ParserOptions::default().max_number_len(None);
+        let digits = format!("0.{}1", "0".repeat(DEFAULT_MAX_NUMBER_LEN));
+        assert!(parse_json_with_options(&digits, &unlimited).is_ok());
+    }
+
+    #[test]
+    fn loose_typing_reads_quoted_booleans_and_numbers() {
+        let options = ParserOptions::default().loose_typing(true);
+        assert_eq!(
+            parse_json_with_options(
+                r#"{"a": "true", "b": "false", "c": "42", "d": "-1.5e2", "e": "True", "f": " 7", "g": "0x10", "h": "null"}"#,
+                &options
+            ),
+            parse_json(
+                r#"{"a": true, "b": false, "c": 42, "d": -1.5e2, "e": "True", "f": " 7", "g": "0x10", "h": "null"}"#
+            )
+        );
+        assert_eq!(
+            parse_json_with_options(r#"{"1": "2"}"#, &options),
+            parse_json(r#"{"1": 2}"#)
+        );
+        assert_eq!(
+            parse_json(r#"["true", "1"]"#),
+            Ok(JsonValue::Array(vec![
+                JsonValue::String("true".to_string()),
+                JsonValue::String("1".to_string())
+            ]))
+        );
+    }
+
+    #[test]
+    fn loose_typing_applies_after_empty_as_null() {
+        let options = ParserOptions::default()
+            .loose_typing(true)
+            .empty_as_null(true);
+        assert_eq!(
+            parse_json_with_options(r#"["", "3", []]"#, &options),
+            parse_json("[null, 3, null]")
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parse_json_parallel_matches_parse_json() {
+        let records: Vec<String> = (0..200)
+            .map(|i| {
+                format!(
+                    r#"{{"id": {}, "tags": ["a,b", "]"], "n": {{"x": [{}]}}}}"#,
+                    i, i
+                )
+            })
+            .collect();
+        let inputs = [
+            format!("[{}]", records.join(",\n")),
+            " [ ] ".to_string(),
+            r#"["\"]", [1, [2]], {"a": "}"}]"#.to_string(),
+            r#"{"not": "an array"}"#.to_string(),
+            "42".to_string(),
+        ];
+        for input in &inputs {
+            assert_eq!(parse_json_parallel(input), parse_json(input), "{input}");
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parse_json_parallel_reports_errors_against_the_whole_input() {
+        let inputs = [
+            "[1, 2, {\"a\": tru}]",
+            "[1,\n  2,\n  x]",
+            "[1, 2,]",
+            "[1, 2",
+            "[1] 2",
+            "[1, \"unterminated]",
+        ];
+        for input in inputs {
+            let error = parse_json_parallel(input);
+            assert!(error.is_err(), "{input}");
+            assert_eq!(error, parse_json(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_json_unwrap_returns_the_named_member() {
+        let input = r#"{"meta": {"page": 1}, "data": [{"id": 1}, {"id": 2}], "data": null}"#;
+        assert_eq!(
+            parse_json_unwrap(input, "data"),
+            parse_json(r#"[{"id": 1}, {"id": 2}]"#)
+        );
+        assert_eq!(
+            parse_json_unwrap(input, "meta"),
+            parse_json(r#"{"page": 1}"#)
+        );
+    }
+
+    #[test]
+    fn parse_json_unwrap_rejects_missing_keys_and_bad_envelopes() {
+        assert_eq!(
+            parse_json_unwrap(r#"{"meta": 1}"#, "data"),
+            Err(ParseError::MissingField {
+                key: "data".to_string()
+            })
+        );
+        assert_eq!(
+            parse_json_unwrap(r#"[{"data": 1}]"#, "data"),
+            Err(syntax("Expected object", 1, 1))
+        );
+        assert!(parse_json_unwrap(r#"{"data": 1, "meta": [1,}"#, "data").is_err());
+        assert!(parse_json_unwrap(r#"{"data": 1} x"#, "data").is_err());
+    }
+
+    #[test]
+    fn tokenizer_yields_tokens_with_byte_ranges() {
+        let input = "{\"é\": [1, -2.5, true, null]}";
+        let tokens: Vec<_> = Tokenizer::new(input).collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            tokens,
+            [
+                (Token::BraceOpen, 0..1),
+                (Token::String("é".to_string()), 1..5),
+                (Token::Colon, 5..6),
+                (Token::BracketOpen, 7..8),
+                (Token::Integer(1), 8..9),
+                (Token::Comma, 9..10),
+                (Token::Number(-2.5), 11..15),
+                (Token::Comma, 15..16),
+                (Token::True, 17..21),
+                (Token::Comma, 21..22),
+                (Token::Null, 23..27),
+                (Token::BracketClose, 27..28),
+                (Token::BraceClose, 28..29),
+            ]
+        );
+        for (_, range) in &tokens {
+            assert!(input.get(range.clone()).is_some());
+        }
+    }
+
+    #[test]
+    fn tokenizer_skips_comments_and_stops_after_an_error() {
+        let options = ParserOptions::default().allow_comments(true);
+        let tokens: Vec<_> = Tokenizer::with_options("/* c */ ] ,", &options).collect();
+        assert_eq!(
+            tokens,
+            [Ok((Token::BracketClose, 8..9)), Ok((Token::Comma, 10..11))]
+        );
+        let mut tokens = Tokenizer::new("[1 @ 2]");
+        assert_eq!(tokens.next(), Some(Ok((Token::BracketOpen, 0..1))));
+        assert_eq!(tokens.next(), Some(Ok((Token::Integer(1), 1..2))));
+        assert_eq!(
+            tokens.next(),
+            Some(Err(syntax("Unexpected character: @", 1, 4)))
+        );
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn negative_zero_keeps_its_sign_by_default() {
+        for input in ["-0", "-0.0", "-0e5"] {
+            match parse_json(input) {
+                Ok(JsonValue::Number(n)) => assert!(n == 0.0 && n.is_sign_negative(), "{input}"),
+                other => panic!("{input}: {other:?}"),
+            }
+        }
+        assert_eq!(parse_json("0"), Ok(JsonValue::Integer(0)));
+    }
+
+    #[test]
+    fn normalize_negative_zero_drops_the_sign() {
+        let options = ParserOptions::default().normalize_negative_zero(true);
+        assert_eq!(
+            parse_json_with_options("-0", &options),
+            Ok(JsonValue::Integer(0))
+        );
+        match parse_json_with_options("-0.0", &options) {
+            Ok(JsonValue::Number(n)) => assert!(n == 0.0 && n.is_sign_positive()),
+            other => panic!("{other:?}"),
+        }
+        assert_eq!(
+            parse_json_with_options("[-1, -0.5]", &options),
+            parse_json("[-1, -0.5]")
+        );
+        let raw = options.preserve_number_text(true);
+        assert_eq!(
+            parse_json_with_options("-0", &raw),
+            Ok(JsonValue::RawNumber("-0".to_string()))
+        );
+    }
+
+    #[test]
+    fn require_ascii_rejects_raw_non_ascii_characters() {
+        let options = ParserOptions::default().require_ascii(true);
+        assert_eq!(
+            parse_json_with_options("{\"name\": \"caf\u{e9}\"}", &options),
+            Err(syntax(
+                "Non-ASCII character '\u{e9}'; use a \\u escape",
+                1,
+                14
+            ))
+        );
+        assert_eq!(
+            parse_json_with_options("[1,\n \"\u{1F600}\"]", &options),
+            Err(syntax(
+                "Non-ASCII character '\u{1F600}'; use a \\u escape",
+                2,
+                3
+            ))
+        );
+        // The scan runs before parsing, so it wins over later syntax errors.
+        assert_eq!(
+            parse_json_with_options("[\u{e9}", &options),
+            Err(syntax(
+                "Non-ASCII character '\u{e9}'; use a \\u escape",
+                1,
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn require_ascii_accepts_escapes() {
+        let options = ParserOptions::default().require_ascii(true);
+        assert_eq!(
+            parse_json_with_options(r#"{"name": "caf\u00e9"}"#, &options),
+            parse_json("{\"name\": \"caf\u{e9}\"}")
+        );
+        assert!(parse_json("\"caf\u{e9}\"").is_ok());
+    }
+
+    #[test]
+    fn parse_jsonp_returns_callback_and_argument() {
+        assert_eq!(
+            parse_jsonp(r#"cb({"a": 1});"#),
+            Ok(("cb".to_string(), parse_json(r#"{"a": 1}"#).unwrap()))
+        );
+        assert_eq!(
+            parse_jsonp(" jQuery.handlers.$cb_1 ( [1, 2] ) \n"),
+            Ok((
+                "jQuery.handlers.$cb_1".to_string(),
+                parse_json("[1, 2]").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_jsonp_rejects_malformed_wrappers() {
+        assert_eq!(parse_jsonp("  "), Err(ParseError::EmptyInput));
+        assert_eq!(
+            parse_jsonp(r#"({"a": 1})"#),
+            Err(syntax("Expected JSONP callback name", 1, 1))
+        );
+        assert_eq!(
+            parse_jsonp(r#"cb {"a": 1}"#),
+            Err(syntax("Expected '(' after callback name", 1, 4))
+        );
+        assert_eq!(
+            parse_jsonp("cb(1, 2)"),
+            Err(syntax("Expected ')' after JSONP argument", 1, 5))
+        );
+        assert!(matches!(
+            parse_jsonp("cb(1);;"),
+            Err(ParseError::TrailingData { column: 7, .. })
+        ));
+        assert!(parse_jsonp("cb([1,)").is_err());
+    }
+
+    #[test]
+    fn parse_jsonp_rejects_callbacks_that_are_not_identifier_paths() {
+        let expected = |column| Err(syntax("Expected JSONP callback name", 1, column));
+        assert_eq!(parse_jsonp("1cb(1)"), expected(1));
+        assert_eq!(parse_jsonp(".cb(1)"), expected(1));
+        assert_eq!(parse_jsonp("a..b(1)"), expected(3));
+        assert_eq!(parse_jsonp("a.(1)"), expected(3));
+        assert_eq!(parse_jsonp("a.1b(1)"), expected(3));
+        assert_eq!(parse_jsonp("a."), expected(3));
+    }
+
+    #[test]
+    fn parse_batch_parses_every_input_independently() {
+        let inputs = ["[1]", "{bad", "", "\"s\"", "[1,]"];
+        let results = parse_batch(&inputs);
+        assert_eq!(results.len(), inputs.len());
+        for (input, result) in inputs.iter().zip(&results) {
+            assert_eq!(*result, parse_json(input), "{input}");
+        }
+        assert_eq!(results[0], parse_json("[1]"));
+        assert!(results[1].is_err());
+        assert_eq!(results[2], Err(ParseError::EmptyInput));
+        assert_eq!(results[3], Ok(JsonValue::from("s")));
+        assert!(parse_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn numeric_underscores_are_ignored_between_digits() {
+        let options = ParserOptions::default().allow_numeric_underscores(true);
+        assert_eq!(
+            parse_json_with_options("[1_000_000, -1_0.2_5, 1e1_0]", &options),
+            parse_json("[1000000, -10.25, 1e10]")
+        );
+        assert_eq!(
+            parse_json("[1_000]"),
+            Err(syntax("Expected ',' or ']' in array", 1, 3))
+        );
+    }
+
+    #[test]
+    fn numeric_underscores_must_sit_between_digits() {
+        let options = ParserOptions::default().allow_numeric_underscores(true);
+        for (input, number) in [
+            ("[-_1]", "-_1"),
+            ("[1_]", "1_"),
+            ("[1__0]", "1__0"),
+            ("[1_.5]", "1_.5"),
+            ("[1._5]", "1._5"),
+            ("[1e_5]", "1e_5"),
+        ] {
+            assert_eq!(
+                parse_json_with_options(input, &options),
+                Err(syntax(
+                    &format!("Misplaced underscore in '{}'", number),
+                    1,
+                    2
+                )),
+                "{input}"
+            );
+        }
+        assert_eq!(
+            parse_json_with_options("[_1]", &options),
+            Err(syntax("Unexpected character: _", 1, 2))
+        );
+    }
+
+    #[test]
+    fn preserve_number_text_keeps_numbers_as_written() {
+        let options = ParserOptions::default().preserve_number_text(true);
+        let value = parse_json_with_options("[1.0, 1e3, -0, 12345678901234567890, 2.50]", &options)
+            .unwrap();
+        let raw: Vec<_> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n.raw_number().unwrap())
+            .collect();
+        assert_eq!(raw, ["1.0", "1e3", "-0", "12345678901234567890", "2.50"]);
+        assert_eq!(
+            to_string_with_options(&value, &SerializerOptions::default()).unwrap(),
+            "[1.0,1e3,-0,12345678901234567890,2.50]"
+        );
+        assert_eq!(value.as_array().unwrap()[1].as_f64(), Some(1000.0));
+    }
+
+    #[test]
+    fn preserve_number_text_still_validates_numbers() {
+        let options = ParserOptions::default().preserve_number_text(true);
+        assert_eq!(
+            parse_json_with_options("[01]", &options),
+            Err(syntax("Leading zeros in '01'", 1, 2))
+        );
+        assert_eq!(parse_json("1.0").unwrap().raw_number(), None);
+    }
+
+    #[test]
+    fn relaxed_booleans_accept_yes_no_on_off_in_any_case() {
+        let options = ParserOptions::default().allow_relaxed_booleans(true);
+        assert_eq!(
+            parse_json_with_options(
+                r#"{"a": yes, "b": NO, "c": On, "d": off, "e": true}"#,
+                &options
+            ),
+            parse_json(r#"{"a": true, "b": false, "c": true, "d": false, "e": true}"#)
+        );
+        assert_eq!(
+            parse_json_with_options(r#"["yes", null]"#, &options),
+            parse_json(r#"["yes", null]"#)
+        );
+    }
+
+    #[test]
+    fn relaxed_booleans_reject_other_words() {
+        let options = ParserOptions::default().allow_relaxed_booleans(true);
+        assert_eq!(
+            parse_json_with_options("[yess]", &options),
+            Err(syntax("Unexpected character: y", 1, 2))
+        );
+        assert_eq!(
+            parse_json_with_options("[TRUE]", &options),
+            Err(syntax("Unexpected character: T", 1, 2))
+        );
+        // `noo` starts like `null`, so it fails as a misspelt literal.
+        assert_eq!(
+            parse_json_with_options("[noo]", &options),
+            Err(syntax("Expected 'null'", 1, 3))
+        );
+        assert_eq!(
+            parse_json("[yes]"),
+            Err(syntax("Unexpected character: y", 1, 2))
+        );
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk on fire"))
+        }
+    }
+
+    #[test]
+    fn parse_json_reader_detects_the_encoding() {
+        let text = "{\"k\": [\"\u{e9}\u{1F600}\", 1]}";
+        let expected = parse_json(text);
+        assert_eq!(parse_json_reader(text.as_bytes()), expected);
+        let with_bom = [&b"\xEF\xBB\xBF"[..], text.as_bytes()].concat();
+        assert_eq!(parse_json_reader(&with_bom[..]), expected);
+        for (bom, big_endian) in [(true, true), (true, false), (false, true), (false, false)] {
+            let bytes = utf16_bytes(text, bom, big_endian);
+            assert_eq!(
+                parse_json_reader(&bytes[..]),
+                expected,
+                "{bom} {big_endian}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_json_reader_reports_bad_bytes_and_read_failures() {
+        assert_eq!(
+            parse_json_reader(&b"[\"a\xFF\"]"[..]),
+            Err(ParseError::InvalidUtf8 { offset: 3 })
+        );
+        assert_eq!(
+            parse_json_reader(FailingReader),
+            Err(ParseError::Io {
+                message: "disk on fire".to_string()
+            })
+        );
+        assert_eq!(parse_json_reader(&b""[..]), Err(ParseError::EmptyInput));
+    }
+
+    #[test]
+    fn truncate_containers_at_keeps_the_first_elements_and_members() {
+        let options = ParserOptions::default().truncate_containers_at(Some(2));
+        assert_eq!(
+            parse_json_with_options(
+                r#"{"a": [1, 2, 3, 4], "b": {"x": 1, "y": 2, "z": 3}, "c": "dropped"}"#,
+                &options
+            ),
+            parse_json(r#"{"a": [1, 2], "b": {"x": 1, "y": 2}}"#)
+        );
+        let zero = ParserOptions::default().truncate_containers_at(Some(0));
+        assert_eq!(
+            parse_json_with_options("[[1], {\"a\": 2}]", &zero),
+            parse_json("[]")
+        );
+    }
+
+    #[test]
+    fn truncate_containers_at_still_checks_discarded_syntax() {
+        let options = ParserOptions::default().truncate_containers_at(Some(1));
+        for input in ["[1, 2, tru]", r#"{"a": 1, "b": [1,}"#, "[1, 2"] {
+            let error = parse_json_with_options(input, &options);
+            assert!(error.is_err(), "{input}");
+            assert_eq!(error, parse_json(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_json_timed_counts_chars_without_keyword_lookaheads() {
+        let input = "[true, false, null, true, null]";
+        let (value, metrics) = parse_json_timed(input).unwrap();
+        assert_eq!(Ok(value), parse_json(input));
+        assert_eq!(metrics.chars_read, input.len());
+        assert_eq!(metrics.lookaheads, 0);
+    }
+
+    #[test]
+    fn parse_json_timed_with_options_counts_one_lookahead_per_comment() {
+        let options = ParserOptions::default().allow_comments(true);
+        let input = "// head\n[1, /* a */ 2, /* b */ 3] // tail";
+        let (value, metrics) = parse_json_timed_with_options(input, &options).unwrap();
+        assert_eq!(Ok(value), parse_json("[1, 2, 3]"));
+        assert_eq!(metrics.chars_read, input.len());
+        assert_eq!(metrics.lookaheads, 4);
+        assert_eq!(
+            parse_json_timed_with_options("[1 /* open", &options),
+            Err(syntax("Unterminated block comment", 1, 4))
+        );
+    }
+
+    #[test]
+    fn parse_json_timed_counts_whitespace_and_fails_like_parse_json() {
+        let input = " {\"a\" : [1, 2]}\n";
+        let (_, metrics) = parse_json_timed(input).unwrap();
+        assert_eq!(metrics.chars_read, input.chars().count());
+        assert_eq!(
+            parse_json_timed("[tru]"),
+            Err(syntax("Expected 'true'", 1, 5))
+        );
+        assert_eq!(parse_json_timed(""), Err(ParseError::EmptyInput));
+    }
+
+    #[test]
+    fn literals_are_consumed_one_character_at_a_time() {
+        assert_eq!(
+            parse_json("[true, false, null]"),
+            Ok(JsonValue::Array(vec![
+                JsonValue::Boolean(true),
+                JsonValue::Boolean(false),
+                JsonValue::Null
+            ]))
+        );
+        assert_eq!(parse_json("tru"), Err(syntax("Expected 'true'", 1, 4)));
+        assert_eq!(parse_json("nul"), Err(syntax("Expected 'null'", 1, 4)));
+        assert_eq!(parse_json("fxlse"), Err(syntax("Expected 'false'", 1, 2)));
+        assert_eq!(parse_json("[nulL]"), Err(syntax("Expected 'null'", 1, 5)));
+    }
+
+    #[test]
+    fn literals_followed_by_letters_are_trailing_data() {
+        assert_eq!(
+            parse_json("falsey"),
+            Err(ParseError::TrailingData {
+                line: 1,
+                column: 6,
+                offset: 5
+            })
+        );
+        assert_eq!(
+            parse_json("[truee]"),
+            Err(syntax("Expected ',' or ']' in array", 1, 6))
+        );
+    }
+
+    #[test]
+    fn merge_duplicate_keys_collects_values_into_an_array() {
+        let options = ParserOptions::default().duplicate_keys(DuplicateKeyPolicy::Merge);
+        assert_eq!(
+            parse_json_with_options(
+                r#"{"set-cookie": "a", "host": "x", "set-cookie": "b", "set-cookie": ["c"]}"#,
+                &options
+            ),
+            parse_json(r#"{"set-cookie": ["a", "b", ["c"]], "host": "x"}"#)
+        );
+        // A key seen once keeps its value, even when that value is an array.
+        assert_eq!(
+            parse_json_with_options(r#"{"a": [1], "b": {"a": 1, "a": 2}}"#, &options),
+            parse_json(r#"{"a": [1], "b": {"a": [1, 2]}}"#)
+        );
+        // An already-array first value is wrapped, not extended.
+        assert_eq!(
+            parse_json_with_options(r#"{"a": [1], "a": 2}"#, &options),
+            parse_json(r#"{"a": [[1], 2]}"#)
+        );
+        // Errors in a repeated value are still reported.
+        assert!(parse_json_with_options(r#"{"a": 1, "a": }"#, &options).is_err());
+    }
+
+    #[test]
+    fn parse_layered_merges_later_layers_over_earlier_ones() {
+        let defaults = r#"{"port": 80, "log": {"level": "info", "file": "a.log"}, "debug": false}"#;
+        let env = r#"{"log": {"level": "warn"}, "debug": null}"#;
+        let local = r#"{"port": 8080, "extra": [1]}"#;
+        assert_eq!(
+            parse_layered(&[defaults, env, local]),
+            parse_json(
+                r#"{"port": 8080, "log": {"level": "warn", "file": "a.log"}, "extra": [1]}"#
+            )
+        );
+        // A non-object layer replaces the whole config.
+        assert_eq!(parse_layered(&[defaults, "[1]"]), parse_json("[1]"));
+        assert_eq!(parse_layered(&[defaults]), parse_json(defaults));
+    }
+
+    #[test]
+    fn parse_layered_reports_the_first_bad_layer_or_no_layers() {
+        assert_eq!(parse_layered(&[]), Err(ParseError::EmptyInput));
+        assert_eq!(
+            parse_layered(&[r#"{"a": 1}"#, r#"{"a": }"#, "nope"]),
+            parse_json(r#"{"a": }"#)
+        );
+        assert!(parse_layered(&[r#"{"a": 1}"#, r#"{"a": }"#]).is_err());
+    }
+
+    #[test]
+    fn bom_outside_a_string_is_reported_at_its_byte_offset() {
+        let bom = |offset| Err(ParseError::UnexpectedBom { offset });
+        assert_eq!(parse_json("\u{FEFF}[1]"), bom(0));
+        assert_eq!(parse_json("[1,\u{FEFF}2]"), bom(3));
+        assert_eq!(parse_json("[1 \u{FEFF}]"), bom(3));
+        assert_eq!(parse_json("[1]\u{FEFF}"), bom(3));
+        assert_eq!(parse_json("{\"\u{e9}\":\u{FEFF}1}"), bom(6));
+        // Inside a string it is an ordinary character.
+        assert_eq!(
+            parse_json("[\"\u{FEFF}\"]"),
+            Ok(JsonValue::Array(vec![JsonValue::String("\u{FEFF}".into())]))
+        );
+    }
+
+    #[test]
+    fn recovery_replaces_a_value_at_a_bom_with_null() {
+        assert_eq!(
+            parse_with_recovery("[1,\u{FEFF}2, 3]"),
+            (
+                parse_json("[1, null, 3]").ok(),
+                vec![ParseError::UnexpectedBom { offset: 3 }]
+            )
+        );
+    }
+
+    #[test]
+    fn string_capacity_hint_preallocates_strings_and_keys() {
+        let options = ParserOptions::default().string_capacity_hint(Some(64));
+        let long = "x".repeat(100);
+        let input = format!(r#"{{"key": ["v", "{}"]}}"#, long);
+        let value = parse_json_with_options(&input, &options).unwrap();
+        assert_eq!(value, parse_json(&input).unwrap());
+        let (key, strings) = &value.as_object().unwrap()[0];
+        assert!(key.capacity() >= 64);
+        let strings = strings.as_array().unwrap();
+        assert!(matches!(&strings[0], JsonValue::String(s) if s.capacity() >= 64));
+        assert!(matches!(&strings[1], JsonValue::String(s) if *s == long));
+
+        let plain = parse_json(r#"{"key": "v"}"#).unwrap();
+        let (key, short) = &plain.as_object().unwrap()[0];
+        assert!(key.capacity() < 64);
+        assert!(matches!(short, JsonValue::String(s) if s.capacity() < 64));
+    }
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut framed = (payload.len() as u32).to_be_bytes().to_vec();
+        framed.extend_from_slice(payload);
+        framed
+    }
+
+    #[test]
+    fn parse_framed_parses_each_length_prefixed_frame() {
+        let input = [frame(br#"{"id": 1}"#), frame(b"[1, 2]"), frame(b"null")].concat();
+        assert_eq!(
+            parse_framed(&input[..]).collect::<Vec<_>>(),
+            vec![
+                parse_json(r#"{"id": 1}"#),
+                parse_json("[1, 2]"),
+                Ok(JsonValue::Null)
+            ]
+        );
+        assert_eq!(parse_framed(&b""[..]).count(), 0);
+        // The prefix is big-endian: `00 00 01 03` is 259 bytes.
+        let long = format!("\"{}\"", "a".repeat(257));
+        let input = frame(long.as_bytes());
+        assert_eq!(&input[..4], &[0, 0, 1, 3]);
+        assert_eq!(
+            parse_framed(&input[..]).collect::<Vec<_>>(),
+            vec![parse_json(&long)]
+        );
+    }
+
+    #[test]
+    fn parse_framed_stops_at_a_truncated_or_invalid_frame() {
+        // The second frame announces 6 bytes but only 3 follow.
+        let mut input = [frame(b"1"), frame(b"[1, 2]")].concat();
+        input.truncate(input.len() - 3);
+        assert_eq!(
+            parse_framed(&input[..]).collect::<Vec<_>>(),
+            vec![Ok(JsonValue::Integer(1)), Err(ParseError::UnexpectedEof)]
+        );
+        // Input ending inside a prefix is truncated too.
+        assert_eq!(
+            parse_framed(&[0, 0][..]).collect::<Vec<_>>(),
+            vec![Err(ParseError::UnexpectedEof)]
+        );
+        let input = [frame(b"[1,"), frame(b"2")].concat();
+        assert_eq!(
+            parse_framed(&input[..]).collect::<Vec<_>>(),
+            vec![parse_json("[1,")]
+        );
+        assert_eq!(
+            parse_framed(FailingReader).collect::<Vec<_>>(),
+            vec![Err(ParseError::Io {
+                message: "disk on fire".to_string()
+            })]
+        );
+    }
+
+    #[test]
+    fn wrong_close_bracket_reports_both_brackets_and_its_position() {
+        let mismatched = |expected, found, line, column| {
+            Err(ParseError::MismatchedBracket {
+                expected,
+                found,
+                line,
+                column,
+            })
+        };
+        assert_eq!(parse_json("[1}"), mismatched(']', '}', 1, 3));
+        assert_eq!(parse_json(r#"{"a":1]"#), mismatched('}', ']', 1, 7));
+        assert_eq!(parse_json(r#"[{"a": [1, 2}]"#), mismatched(']', '}', 1, 13));
+        assert_eq!(parse_json("[1,\n }"), mismatched(']', '}', 2, 2));
+        assert_eq!(
+            parse_json("[1}").unwrap_err().to_string(),
+            "Expected ']' to close '[' but found '}' at line 1, column 3"
+        );
+        assert_eq!(
+            parse_json("[1]"),
+            Ok(JsonValue::Array(vec![JsonValue::Integer(1)]))
+        );
     }
 }
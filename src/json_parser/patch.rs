@@ -0,0 +1,684 @@
+use std::fmt;
+
+use super::pointer::{escape_token, parse_index, parse_pointer};
+use super::value::JsonValue;
+
+/// Applies an RFC 7386 merge patch to `target` in place.
+pub fn merge_patch(target: &mut JsonValue, patch: &JsonValue) {
+    let JsonValue::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !matches!(target, JsonValue::Object(_)) {
+        *target = JsonValue::Object(Vec::new());
+    }
+    let JsonValue::Object(object) = target else {
+        unreachable!()
+    };
+
+    for (key, value) in patch {
+        let existing = object.iter().position(|(k, _)| k == key);
+        match (existing, value) {
+            (Some(index), JsonValue::Null) => {
+                object.remove(index);
+            }
+            (None, JsonValue::Null) => {}
+            (Some(index), value) => merge_patch(&mut object[index].1, value),
+            (None, value) => {
+                let mut entry = JsonValue::Null;
+                merge_patch(&mut entry, value);
+                object.push((key.clone(), entry));
+            }
+        }
+    }
+}
+
+/// Builds an RFC 7386 merge patch that turns `from` into `to` when passed to
+/// [`merge_patch`].
+///
+/// Merge patches use `null` to mean "delete", so a `null` member inside an
+/// object of `to` cannot be reproduced; it is removed instead.
+pub fn create_merge_patch(from: &JsonValue, to: &JsonValue) -> JsonValue {
+    let (JsonValue::Object(from), JsonValue::Object(to)) = (from, to) else {
+        return to.clone();
+    };
+
+    let mut patch = Vec::new();
+    for (key, old) in from {
+        match to.iter().find(|(k, _)| k == key) {
+            None => patch.push((key.clone(), JsonValue::Null)),
+            Some((_, new)) if new != old => patch.push((key.clone(), create_merge_patch(old, new))),
+            Some(_) => {}
+        }
+    }
+    for (key, new) in to {
+        if !from.iter().any(|(k, _)| k == key) {
+            patch.push((key.clone(), new.clone()));
+        }
+    }
+    JsonValue::Object(patch)
+}
+
+/// A location that [`merge_checked`] found set to different values on each side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// JSON Pointer of the conflicting member.
+    pub path: String,
+    pub left: JsonValue,
+    pub right: JsonValue,
+}
+
+/// Deep-merges two documents, failing with every conflict instead of letting
+/// one side win. Objects merge member by member; a member on only one side is
+/// kept, and equal values on both sides are fine. Any other pair of values,
+/// including two different arrays, is a conflict.
+pub fn merge_checked(a: &JsonValue, b: &JsonValue) -> Result<JsonValue, Vec<Conflict>> {
+    let mut conflicts = Vec::new();
+    let merged = merge_checked_at(a, b, &mut String::new(), &mut conflicts);
+    match conflicts.is_empty() {
+        true => Ok(merged),
+        false => Err(conflicts),
+    }
+}
+
+fn merge_checked_at(
+    a: &JsonValue,
+    b: &JsonValue,
+    path: &mut String,
+    conflicts: &mut Vec<Conflict>,
+) -> JsonValue {
+    let (JsonValue::Object(left), JsonValue::Object(right)) = (a, b) else {
+        if a != b {
+            conflicts.push(Conflict {
+                path: path.clone(),
+                left: a.clone(),
+                right: b.clone(),
+            });
+        }
+        return a.clone();
+    };
+
+    let mut merged = Vec::with_capacity(left.len());
+    for (key, value) in left {
+        let value = match right.iter().find(|(k, _)| k == key) {
+            Some((_, other)) => {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_token(key));
+                let value = merge_checked_at(value, other, path, conflicts);
+                path.truncate(len);
+                value
+            }
+            None => value.clone(),
+        };
+        merged.push((key.clone(), value));
+    }
+    for (key, value) in right {
+        if !left.iter().any(|(k, _)| k == key) {
+            merged.push((key.clone(), value.clone()));
+        }
+    }
+    JsonValue::Object(merged)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    /// The patch document or one of its operations is malformed.
+    InvalidOperation(String),
+    /// An operation referred to a location that does not exist.
+    PathNotFound(String),
+    /// A `test` operation found a different value at its path.
+    TestFailed(String),
+    /// Patch `index` of a sequence given to [`apply_patches`] failed.
+    InPatch {
+        index: usize,
+        error: Box<PatchError>,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidOperation(message) => {
+                write!(f, "Invalid patch operation: {}", message)
+            }
+            PatchError::PathNotFound(path) => write!(f, "Path not found: {}", path),
+            PatchError::TestFailed(path) => write!(f, "Test failed at {}", path),
+            PatchError::InPatch { index, error } => write!(f, "Patch {}: {}", index, error),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+fn member<'a>(op: &'a JsonValue, name: &str) -> Result<&'a JsonValue, PatchError> {
+    op.get(name)
+        .ok_or_else(|| PatchError::InvalidOperation(format!("missing '{}'", name)))
+}
+
+fn string_member<'a>(op: &'a JsonValue, name: &str) -> Result<&'a str, PatchError> {
+    match member(op, name)? {
+        JsonValue::String(s) => Ok(s),
+        _ => Err(PatchError::InvalidOperation(format!(
+            "'{}' must be a string",
+            name
+        ))),
+    }
+}
+
+fn add(doc: &mut JsonValue, path: &str, value: JsonValue) -> Result<(), PatchError> {
+    let not_found = || PatchError::PathNotFound(path.to_string());
+    let mut tokens = parse_pointer(path).ok_or_else(not_found)?;
+    let Some(last) = tokens.pop() else {
+        *doc = value;
+        return Ok(());
+    };
+    match doc.tokens_mut(&tokens).ok_or_else(not_found)? {
+        JsonValue::Object(object) => {
+            match object.iter_mut().find(|(k, _)| *k == last) {
+                Some((_, existing)) => *existing = value,
+                None => object.push((last, value)),
+            }
+            Ok(())
+        }
+        JsonValue::Array(array) => {
+            let index = match last.as_str() {
+                "-" => array.len(),
+                token => parse_index(token).ok_or_else(not_found)?,
+            };
+            if index > array.len() {
+                return Err(not_found());
+            }
+            array.insert(index, value);
+            Ok(())
+        }
+        _ => Err(not_found()),
+    }
+}
+
+fn apply_operation(doc: &mut JsonValue, op: &JsonValue) -> Result<(), PatchError> {
+    let path = string_member(op, "path")?;
+    let not_found = || PatchError::PathNotFound(path.to_string());
+    match string_member(op, "op")? {
+        "add" => add(doc, path, member(op, "value")?.clone()),
+        "remove" => doc.pointer_remove(path).map(|_| ()).ok_or_else(not_found),
+        "replace" => {
+            *doc.pointer_mut(path).ok_or_else(not_found)? = member(op, "value")?.clone();
+            Ok(())
+        }
+        "move" => {
+            let from = string_member(op, "from")?;
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(PatchError::InvalidOperation(format!(
+                    "cannot move {} into its own child {}",
+                    from, path
+                )));
+            }
+            let value = doc
+                .pointer_remove(from)
+                .ok_or_else(|| PatchError::PathNotFound(from.to_string()))?;
+            add(doc, path, value)
+        }
+        "copy" => {
+            let from = string_member(op, "from")?;
+            let value = doc
+                .pointer(from)
+                .ok_or_else(|| PatchError::PathNotFound(from.to_string()))?
+                .clone();
+            add(doc, path, value)
+        }
+        "test" => match doc.pointer(path) {
+            Some(actual) if actual == member(op, "value")? => Ok(()),
+            Some(_) => Err(PatchError::TestFailed(path.to_string())),
+            None => Err(not_found()),
+        },
+        other => Err(PatchError::InvalidOperation(format!(
+            "unknown op '{}'",
+            other
+        ))),
+    }
+}
+
+/// Applies an RFC 6902 JSON Patch. The patch is atomic: if any operation
+/// fails, `doc` is left untouched.
+pub fn apply_patch(doc: &mut JsonValue, patch: &JsonValue) -> Result<(), PatchError> {
+    let JsonValue::Array(operations) = patch else {
+        return Err(PatchError::InvalidOperation(
+            "patch must be an array".to_string(),
+        ));
+    };
+    let mut patched = doc.clone();
+    for op in operations {
+        apply_operation(&mut patched, op)?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+/// Applies RFC 6902 patches in order, each to the result of the ones before,
+/// as when replaying a changelog. Stops at the first patch that fails,
+/// reporting its index in `PatchError::InPatch`; that patch is undone as in
+/// [`apply_patch`], but the ones before it stay applied.
+pub fn apply_patches(doc: &mut JsonValue, patches: &[JsonValue]) -> Result<(), PatchError> {
+    for (index, patch) in patches.iter().enumerate() {
+        apply_patch(doc, patch).map_err(|error| PatchError::InPatch {
+            index,
+            error: Box::new(error),
+        })?;
+    }
+    Ok(())
+}
+
+fn operation(op: &str, path: &str, value: Option<&JsonValue>) -> JsonValue {
+    let mut object = vec![
+        ("op".to_string(), JsonValue::from(op)),
+        ("path".to_string(), JsonValue::from(path)),
+    ];
+    if let Some(value) = value {
+        object.push(("value".to_string(), value.clone()));
+    }
+    JsonValue::Object(object)
+}
+
+fn diff(
+    from: &JsonValue,
+    to: &JsonValue,
+    path: &str,
+    restore_order: bool,
+    ops: &mut Vec<JsonValue>,
+) {
+    if from == to {
+        return;
+    }
+    match (from, to) {
+        (JsonValue::Object(old), JsonValue::Object(new)) => {
+            for (key, old_value) in old {
+                let child = format!("{}/{}", path, escape_token(key));
+                match new.iter().find(|(k, _)| k == key) {
+                    Some((_, new_value)) => diff(old_value, new_value, &child, restore_order, ops),
+                    None => ops.push(operation("remove", &child, None)),
+                }
+            }
+            for (key, new_value) in new {
+                if !old.iter().any(|(k, _)| k == key) {
+                    let child = format!("{}/{}", path, escape_token(key));
+                    ops.push(operation("add", &child, Some(new_value)));
+                }
+            }
+            if !restore_order {
+                return;
+            }
+            // The ops above leave the kept members in their old order followed
+            // by the added ones. Moving a member onto its own path re-appends
+            // it, so moving every member from the first misplaced one onwards
+            // restores the order of `new`.
+            let patched = old
+                .iter()
+                .map(|(k, _)| k)
+                .filter(|k| new.iter().any(|(key, _)| key == *k))
+                .chain(
+                    new.iter()
+                        .map(|(k, _)| k)
+                        .filter(|k| !old.iter().any(|(key, _)| key == *k)),
+                );
+            if let Some(first) = patched.zip(new).position(|(k, (key, _))| k != key) {
+                for (key, _) in &new[first..] {
+                    let child = JsonValue::from(format!("{}/{}", path, escape_token(key)));
+                    ops.push(JsonValue::Object(vec![
+                        ("op".to_string(), JsonValue::from("move")),
+                        ("from".to_string(), child.clone()),
+                        ("path".to_string(), child),
+                    ]));
+                }
+            }
+        }
+        (JsonValue::Array(old), JsonValue::Array(new)) => {
+            for (i, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+                diff(
+                    old_value,
+                    new_value,
+                    &format!("{}/{}", path, i),
+                    restore_order,
+                    ops,
+                );
+            }
+            // Remove from the end so earlier indices stay valid.
+            for i in (new.len()..old.len()).rev() {
+                ops.push(operation("remove", &format!("{}/{}", path, i), None));
+            }
+            for (i, new_value) in new.iter().enumerate().skip(old.len()) {
+                ops.push(operation(
+                    "add",
+                    &format!("{}/{}", path, i),
+                    Some(new_value),
+                ));
+            }
+        }
+        _ => ops.push(operation("replace", path, Some(to))),
+    }
+}
+
+/// Builds an RFC 6902 patch of `add`, `remove` and `replace` operations that
+/// turns `from` into `to` when passed to [`apply_patch`]. Member order is not
+/// part of the patch: kept members stay where they were in `from` and added
+/// ones are appended, so the result equals `to` up to object key order. Use
+/// [`create_patch_ordered`] when the order matters too.
+pub fn create_patch(from: &JsonValue, to: &JsonValue) -> JsonValue {
+    let mut ops = Vec::new();
+    diff(from, to, "", false, &mut ops);
+    JsonValue::Array(ops)
+}
+
+/// Like [`create_patch`], but objects whose members end up in a different
+/// order also get a `move` of each misplaced member onto its own path, which
+/// re-appends it, so the result equals `to` including key order.
+pub fn create_patch_ordered(from: &JsonValue, to: &JsonValue) -> JsonValue {
+    let mut ops = Vec::new();
+    diff(from, to, "", true, &mut ops);
+    JsonValue::Array(ops)
+}
+
+/// A rough size for an RFC 6902 patch: one per operation plus the number of
+/// nodes in each operation's `value`, so replacing a large subtree costs more
+/// than many small edits. Anything but an array of operations costs 0.
+pub fn patch_cost(patch: &JsonValue) -> usize {
+    patch
+        .as_array()
+        .unwrap_or_default()
+        .iter()
+        .map(|op| {
+            let mut nodes = 0;
+            if let Some(value) = op.get("value") {
+                value.walk(|_| nodes += 1);
+            }
+            1 + nodes
+        })
+        .sum()
+}
+
+/// Like [`create_patch`], but falls back to a single `replace` of the whole
+/// document when that has a lower [`patch_cost`], as when nearly everything
+/// changed.
+pub fn create_patch_compact(from: &JsonValue, to: &JsonValue) -> JsonValue {
+    let patch = create_patch(from, to);
+    let replace = JsonValue::Array(vec![operation("replace", "", Some(to))]);
+    if patch_cost(&replace) < patch_cost(&patch) {
+        replace
+    } else {
+        patch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+    use crate::json_parser::serializer::to_string_canonical;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    // RFC 7386, Appendix A: (original, patch, result).
+    const MERGE_PATCH_VECTORS: [(&str, &str, &str); 15] = [
+        (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+        (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+        (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+        (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+        (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+        (
+            r#"{"a":{"b":"c"}}"#,
+            r#"{"a":{"b":"d","c":null}}"#,
+            r#"{"a":{"b":"d"}}"#,
+        ),
+        (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+        (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+        (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+        (r#"{"a":"foo"}"#, "null", "null"),
+        (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+        (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+        (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+        (
+            r#"{}"#,
+            r#"{"a":{"bb":{"ccc":null}}}"#,
+            r#"{"a":{"bb":{}}}"#,
+        ),
+    ];
+
+    #[test]
+    fn merge_patch_matches_rfc_7386_examples() {
+        for (original, patch, result) in MERGE_PATCH_VECTORS {
+            let mut target = json(original);
+            merge_patch(&mut target, &json(patch));
+            assert_eq!(target, json(result), "{} + {}", original, patch);
+        }
+    }
+
+    #[test]
+    fn create_merge_patch_reproduces_the_target() {
+        for (original, _, result) in MERGE_PATCH_VECTORS {
+            let (from, to) = (json(original), json(result));
+            let mut target = from.clone();
+            merge_patch(&mut target, &create_merge_patch(&from, &to));
+            assert_eq!(target, to, "{} -> {}", original, result);
+        }
+        assert_eq!(
+            create_merge_patch(&json(r#"{"a": 1, "b": 2}"#), &json(r#"{"a": 1, "c": 3}"#)),
+            json(r#"{"b": null, "c": 3}"#)
+        );
+        // A null member of the target can only be expressed as a deletion.
+        let mut target = json(r#"{"a": 1}"#);
+        merge_patch(
+            &mut target,
+            &create_merge_patch(&json(r#"{"a": 1}"#), &json(r#"{"a": null}"#)),
+        );
+        assert_eq!(target, json("{}"));
+    }
+
+    fn assert_round_trip(from: &str, to: &str) -> JsonValue {
+        let (from, to) = (json(from), json(to));
+        let patch = create_patch(&from, &to);
+        let mut patched = from.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(
+            to_string_canonical(&patched),
+            to_string_canonical(&to),
+            "patch {:?}",
+            patch
+        );
+        patch
+    }
+
+    fn assert_ordered_round_trip(from: &str, to: &str) -> JsonValue {
+        let (from, to) = (json(from), json(to));
+        let patch = create_patch_ordered(&from, &to);
+        let mut patched = from.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched, to, "patch {:?}", patch);
+        patch
+    }
+
+    #[test]
+    fn create_patch_round_trips() {
+        assert_eq!(assert_round_trip("[1, 2]", "[1, 2]"), json("[]"));
+        assert_eq!(
+            assert_round_trip(
+                r#"{"a": 1, "b": [1, 2, 3]}"#,
+                r#"{"a": 2, "b": [1], "c~/": null}"#
+            ),
+            json(
+                r#"[{"op": "replace", "path": "/a", "value": 2},
+                    {"op": "remove", "path": "/b/2"},
+                    {"op": "remove", "path": "/b/1"},
+                    {"op": "add", "path": "/c~0~1", "value": null}]"#
+            )
+        );
+        assert_round_trip("[1]", "[1, [2], {}]");
+        assert_round_trip(r#"{"a": 1}"#, "[1]");
+        assert_round_trip(r#"{"a": {"x": 1}}"#, r#"{"a": {"x": 1, "y": [true]}}"#);
+    }
+
+    #[test]
+    fn create_patch_ignores_key_order() {
+        let from = r#"{"a": 1, "b": 2, "c": 3}"#;
+        assert_eq!(
+            assert_round_trip(from, r#"{"a": 1, "c": 3, "b": 2}"#),
+            json("[]")
+        );
+        assert_eq!(
+            assert_round_trip(from, r#"{"c": 3, "z": 0, "a": 1}"#),
+            json(
+                r#"[{"op": "remove", "path": "/b"},
+                    {"op": "add", "path": "/z", "value": 0}]"#
+            )
+        );
+        let reordered = json(r#"{"c": 3, "b": 2, "a": 1}"#);
+        assert_eq!(create_patch_compact(&json(from), &reordered), json("[]"));
+    }
+
+    #[test]
+    fn create_patch_ordered_restores_key_order() {
+        assert_eq!(
+            assert_ordered_round_trip(r#"{"a": 1, "b": 2, "c": 3}"#, r#"{"a": 1, "c": 3, "b": 2}"#),
+            json(
+                r#"[{"op": "move", "from": "/c", "path": "/c"},
+                    {"op": "move", "from": "/b", "path": "/b"}]"#
+            )
+        );
+        assert_ordered_round_trip(r#"{"a": 1, "b": 2}"#, r#"{"z": 0, "a": 1}"#);
+        assert_ordered_round_trip(
+            r#"{"o": {"x": 1, "y": 2}, "p": 1}"#,
+            r#"{"p": 2, "o": {"y": 2, "x": 1, "z": 3}}"#,
+        );
+        assert_eq!(
+            assert_ordered_round_trip(r#"{"a": 1, "b": 2}"#, r#"{"a": 1, "c": 3}"#),
+            json(
+                r#"[{"op": "remove", "path": "/b"},
+                    {"op": "add", "path": "/c", "value": 3}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn merge_checked_combines_disjoint_members() {
+        assert_eq!(
+            merge_checked(
+                &json(r#"{"a": 1, "o": {"x": [1], "y": 2}}"#),
+                &json(r#"{"o": {"x": [1], "z": 3}, "b": 4}"#)
+            ),
+            Ok(json(r#"{"a": 1, "o": {"x": [1], "y": 2, "z": 3}, "b": 4}"#))
+        );
+    }
+
+    #[test]
+    fn merge_checked_reports_every_conflict_path() {
+        let conflicts = merge_checked(
+            &json(r#"{"a": 1, "o": {"k/ey": [1], "same": true}, "t": {}}"#),
+            &json(r#"{"a": 2, "o": {"k/ey": [2], "same": true}, "t": null}"#),
+        )
+        .unwrap_err();
+        assert_eq!(
+            conflicts,
+            [
+                Conflict {
+                    path: "/a".into(),
+                    left: json("1"),
+                    right: json("2"),
+                },
+                Conflict {
+                    path: "/o/k~1ey".into(),
+                    left: json("[1]"),
+                    right: json("[2]"),
+                },
+                Conflict {
+                    path: "/t".into(),
+                    left: json("{}"),
+                    right: json("null"),
+                },
+            ]
+        );
+        assert_eq!(
+            merge_checked(&json("1"), &json("2")).unwrap_err()[0].path,
+            ""
+        );
+    }
+
+    #[test]
+    fn apply_patches_replays_patches_in_order() {
+        let mut doc = json(r#"{"n": 1}"#);
+        let patches = [
+            json(r#"[{"op": "add", "path": "/items", "value": []}]"#),
+            json(
+                r#"[{"op": "add", "path": "/items/-", "value": "a"}, {"op": "replace", "path": "/n", "value": 2}]"#,
+            ),
+            json(
+                r#"[{"op": "test", "path": "/n", "value": 2}, {"op": "move", "from": "/n", "path": "/items/0"}]"#,
+            ),
+        ];
+        assert_eq!(apply_patches(&mut doc, &patches), Ok(()));
+        assert_eq!(doc, json(r#"{"items": [2, "a"]}"#));
+        assert_eq!(apply_patches(&mut doc, &[]), Ok(()));
+    }
+
+    #[test]
+    fn apply_patches_undoes_only_the_failing_patch() {
+        let mut doc = json(r#"{"n": 1}"#);
+        let patches = [
+            json(r#"[{"op": "replace", "path": "/n", "value": 2}]"#),
+            json(
+                r#"[{"op": "add", "path": "/m", "value": 3}, {"op": "remove", "path": "/missing"}]"#,
+            ),
+            json(r#"[{"op": "replace", "path": "/n", "value": 4}]"#),
+        ];
+        let error = apply_patches(&mut doc, &patches).unwrap_err();
+        assert!(
+            matches!(&error, PatchError::InPatch { index: 1, .. }),
+            "{error:?}"
+        );
+        assert!(error.to_string().starts_with("Patch 1: "));
+        assert_eq!(doc, json(r#"{"n": 2}"#));
+
+        let failing_test = [json(r#"[{"op": "test", "path": "/n", "value": 5}]"#)];
+        assert_eq!(
+            apply_patches(&mut doc, &failing_test),
+            Err(PatchError::InPatch {
+                index: 0,
+                error: Box::new(PatchError::TestFailed("/n".to_string()))
+            })
+        );
+        assert_eq!(doc, json(r#"{"n": 2}"#));
+    }
+
+    #[test]
+    fn patch_cost_counts_operations_and_value_nodes() {
+        let patch = json(
+            r#"[{"op": "remove", "path": "/a"}, {"op": "add", "path": "/b", "value": 5},
+                {"op": "replace", "path": "/c", "value": {"x": [1, 2]}}]"#,
+        );
+        assert_eq!(patch_cost(&patch), 1 + 2 + 5);
+        assert_eq!(patch_cost(&json("[]")), 0);
+        assert_eq!(patch_cost(&json(r#"{"op": "remove"}"#)), 0);
+    }
+
+    #[test]
+    fn create_patch_compact_replaces_the_root_when_cheaper() {
+        let from = json(r#"{"a": 1, "b": 2, "c": 3}"#);
+        let to = json(r#"{"x": 1, "y": 2, "z": 3}"#);
+        let patch = create_patch_compact(&from, &to);
+        assert_eq!(
+            patch,
+            json(r#"[{"op": "replace", "path": "", "value": {"x": 1, "y": 2, "z": 3}}]"#)
+        );
+        let mut doc = from.clone();
+        apply_patch(&mut doc, &patch).unwrap();
+        assert_eq!(doc, to);
+
+        let small = json(r#"{"a": 1, "b": 3, "c": 3}"#);
+        assert_eq!(
+            create_patch_compact(&from, &small),
+            create_patch(&from, &small)
+        );
+        assert_eq!(create_patch_compact(&from, &from), json("[]"));
+    }
+}
@@ -0,0 +1,426 @@
+use super::serializer::to_string;
+use super::value::JsonValue;
+
+// Splits an RFC 6901 pointer into unescaped reference tokens. The empty
+// pointer refers to the whole document; any other pointer must start with '/'.
+pub(crate) fn parse_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let rest = pointer.strip_prefix('/')?;
+    Some(
+        rest.split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+pub(crate) fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+pub(crate) fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || token.starts_with('+') || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+impl JsonValue {
+    /// Looks up a value by JSON Pointer, e.g. `/address/city` or `/grades/0`.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        let mut current = self;
+        for token in parse_pointer(pointer)? {
+            current = match current {
+                JsonValue::Object(object) => {
+                    object.iter().find(|(k, _)| *k == token).map(|(_, v)| v)?
+                }
+                JsonValue::Array(array) => array.get(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonValue> {
+        self.tokens_mut(&parse_pointer(pointer)?)
+    }
+
+    pub(crate) fn tokens_mut(&mut self, tokens: &[String]) -> Option<&mut JsonValue> {
+        let mut current = self;
+        for token in tokens {
+            current = match current {
+                JsonValue::Object(object) => object
+                    .iter_mut()
+                    .find(|(k, _)| k == token)
+                    .map(|(_, v)| v)?,
+                JsonValue::Array(array) => array.get_mut(parse_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// The compact JSON text of the subtree at `pointer`, e.g. for logging one
+    /// nested field. `None` if nothing is there, or if the subtree cannot be
+    /// written with the default [`SerializerOptions`](super::serializer::SerializerOptions),
+    /// because it nests too deeply or holds a non-finite float.
+    pub fn to_string_at(&self, pointer: &str) -> Option<String> {
+        to_string(self.pointer(pointer)?).ok()
+    }
+
+    /// Removes and returns the value at `pointer`. Later array elements shift
+    /// down to fill the gap. The root itself cannot be removed.
+    pub fn pointer_remove(&mut self, pointer: &str) -> Option<JsonValue> {
+        let mut tokens = parse_pointer(pointer)?;
+        let last = tokens.pop()?;
+        match self.tokens_mut(&tokens)? {
+            JsonValue::Object(object) => {
+                let index = object.iter().position(|(k, _)| *k == last)?;
+                Some(object.remove(index).1)
+            }
+            JsonValue::Array(array) => {
+                let index = parse_index(&last)?;
+                (index < array.len()).then(|| array.remove(index))
+            }
+            _ => None,
+        }
+    }
+
+    /// Lazily yields every scalar leaf with its JSON Pointer, in document
+    /// order. Empty arrays and objects have no leaves and are skipped; a
+    /// scalar root is yielded once with the empty pointer.
+    pub fn leaves(&self) -> impl Iterator<Item = (String, &JsonValue)> {
+        let mut stack = vec![(String::new(), self)];
+        std::iter::from_fn(move || loop {
+            let (pointer, value) = stack.pop()?;
+            match value {
+                JsonValue::Array(array) => stack.extend(
+                    array
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, child)| (format!("{}/{}", pointer, i), child)),
+                ),
+                JsonValue::Object(object) => stack.extend(
+                    object
+                        .iter()
+                        .rev()
+                        .map(|(key, child)| (format!("{}/{}", pointer, escape_token(key)), child)),
+                ),
+                leaf => return Some((pointer, leaf)),
+            }
+        })
+    }
+
+    /// Whether the tree can be written as standard JSON: it holds no NaN or
+    /// infinite `Number`, which a value built in code can.
+    pub fn is_valid_json(&self) -> bool {
+        self.validate_serializable().is_ok()
+    }
+
+    /// Like [`is_valid_json`](JsonValue::is_valid_json), but on failure returns
+    /// the pointer of the first non-finite number in document order.
+    pub fn validate_serializable(&self) -> Result<(), String> {
+        match self
+            .leaves()
+            .find(|(_, leaf)| matches!(leaf, JsonValue::Number(n) if !n.is_finite()))
+        {
+            Some((pointer, _)) => Err(pointer),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Builds a copy of `value` holding only the nodes at `pointers` plus the
+/// containers leading to them, like a GraphQL field selection. Members keep
+/// their source order; kept array elements close up, so selecting
+/// `/grades/2` alone yields `{"grades": [77]}`. Pointers that do not resolve
+/// are ignored.
+pub fn project(value: &JsonValue, pointers: &[&str]) -> JsonValue {
+    let selections: Vec<Vec<String>> = pointers
+        .iter()
+        .filter(|pointer| value.pointer(pointer).is_some())
+        .filter_map(|pointer| parse_pointer(pointer))
+        .collect();
+    let selections: Vec<&[String]> = selections.iter().map(Vec::as_slice).collect();
+    project_tokens(value, &selections)
+}
+
+fn project_tokens(value: &JsonValue, selections: &[&[String]]) -> JsonValue {
+    if selections.iter().any(|tokens| tokens.is_empty()) {
+        return value.clone();
+    }
+    let below = |matches: &dyn Fn(&str) -> bool| -> Vec<&[String]> {
+        selections
+            .iter()
+            .filter(|tokens| matches(&tokens[0]))
+            .map(|tokens| &tokens[1..])
+            .collect()
+    };
+    match value {
+        JsonValue::Object(object) => JsonValue::Object(
+            object
+                .iter()
+                .filter_map(|(key, child)| {
+                    let rest = below(&|token| token == key);
+                    (!rest.is_empty()).then(|| (key.clone(), project_tokens(child, &rest)))
+                })
+                .collect(),
+        ),
+        JsonValue::Array(array) => JsonValue::Array(
+            array
+                .iter()
+                .enumerate()
+                .filter_map(|(i, child)| {
+                    let rest = below(&|token| parse_index(token) == Some(i));
+                    (!rest.is_empty()).then(|| project_tokens(child, &rest))
+                })
+                .collect(),
+        ),
+        _ => JsonValue::Null,
+    }
+}
+
+/// Checks that every pointer in `required` resolves, as a quick presence
+/// check lighter than a schema. On failure, returns the missing pointers in
+/// the order given.
+pub fn require_pointers(value: &JsonValue, required: &[&str]) -> Result<(), Vec<String>> {
+    let missing: Vec<String> = required
+        .iter()
+        .filter(|pointer| value.pointer(pointer).is_none())
+        .map(|pointer| pointer.to_string())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing)
+    }
+}
+
+/// Checks every object key at any depth for characters in `forbidden`, such
+/// as `.` and `$` for MongoDB or `.` for dotted-path access. On failure,
+/// returns the pointers of all offending members in document order.
+pub fn validate_keys(value: &JsonValue, forbidden: &[char]) -> Result<(), Vec<String>> {
+    let mut invalid = Vec::new();
+    validate_keys_at(value, forbidden, &mut String::new(), &mut invalid);
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+fn validate_keys_at(
+    value: &JsonValue,
+    forbidden: &[char],
+    pointer: &mut String,
+    invalid: &mut Vec<String>,
+) {
+    let mut visit = |token: &str, child: &JsonValue, bad_key: bool| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(token);
+        if bad_key {
+            invalid.push(pointer.clone());
+        }
+        validate_keys_at(child, forbidden, pointer, invalid);
+        pointer.truncate(len);
+    };
+    match value {
+        JsonValue::Array(array) => {
+            for (i, child) in array.iter().enumerate() {
+                visit(&i.to_string(), child, false);
+            }
+        }
+        JsonValue::Object(object) => {
+            for (key, child) in object {
+                visit(&escape_token(key), child, key.contains(forbidden));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn pointer_unescapes_tokens_and_rejects_bad_indexes() {
+        let doc = json(r#"{"a/b": {"m~n": [10, 20]}, "": 1}"#);
+        assert_eq!(doc.pointer(""), Some(&doc));
+        assert_eq!(doc.pointer("/"), Some(&JsonValue::Integer(1)));
+        assert_eq!(doc.pointer("/a~1b/m~0n/1"), Some(&JsonValue::Integer(20)));
+        assert_eq!(doc.pointer("/a/b"), None);
+        assert_eq!(doc.pointer("a~1b"), None);
+        for index in ["01", "+1", "-1", "2", "", "x"] {
+            assert_eq!(
+                doc.pointer(&format!("/a~1b/m~0n/{}", index)),
+                None,
+                "{}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn pointer_remove_takes_the_value_out() {
+        let mut doc = json(r#"{"a": [1, 2, 3], "b~": true}"#);
+        assert_eq!(doc.pointer_remove("/a/0"), Some(JsonValue::Integer(1)));
+        assert_eq!(doc.pointer_remove("/b~0"), Some(JsonValue::Boolean(true)));
+        assert_eq!(doc, json(r#"{"a": [2, 3]}"#));
+        assert_eq!(doc.pointer_remove("/a/2"), None);
+        assert_eq!(doc.pointer_remove("/a/01"), None);
+        assert_eq!(doc.pointer_remove("/missing"), None);
+        assert_eq!(doc.pointer_remove(""), None);
+        assert_eq!(doc, json(r#"{"a": [2, 3]}"#));
+    }
+
+    #[test]
+    fn project_keeps_selected_paths_in_source_order() {
+        let doc = json(
+            r#"{"name": "Ann", "age": 30, "grades": [90, 85, 77],
+                "address": {"city": "Oslo", "zip": "0150"}}"#,
+        );
+        assert_eq!(
+            project(&doc, &["/address/city", "/name", "/grades/2"]),
+            json(r#"{"name": "Ann", "grades": [77], "address": {"city": "Oslo"}}"#)
+        );
+        assert_eq!(
+            project(&doc, &["/address", "/address/zip"]),
+            json(r#"{"address": {"city": "Oslo", "zip": "0150"}}"#)
+        );
+        assert_eq!(project(&doc, &[""]), doc);
+    }
+
+    #[test]
+    fn project_ignores_unresolved_pointers() {
+        let doc = json(r#"{"a": [1, 2], "b": 3}"#);
+        assert_eq!(
+            project(&doc, &["/a/5", "/missing", "/b/x", "/b"]),
+            json(r#"{"b": 3}"#)
+        );
+        assert_eq!(project(&doc, &[]), json("{}"));
+    }
+
+    #[test]
+    fn validate_keys_reports_every_offending_member() {
+        let doc = json(r#"{"ok": 1, "a.b": {"$set": 2, "fine": [{"x.y": 3}]}, "c/d": {"e": 4}}"#);
+        assert_eq!(
+            validate_keys(&doc, &['.', '$']),
+            Err(vec![
+                "/a.b".to_string(),
+                "/a.b/$set".to_string(),
+                "/a.b/fine/0/x.y".to_string(),
+            ])
+        );
+        assert_eq!(validate_keys(&doc, &['/']), Err(vec!["/c~1d".to_string()]));
+    }
+
+    #[test]
+    fn validate_keys_accepts_clean_documents() {
+        assert_eq!(
+            validate_keys(&json(r#"{"a": {"b": ["c.d"]}}"#), &['.']),
+            Ok(())
+        );
+        assert_eq!(validate_keys(&json(r#""a.b""#), &['.']), Ok(()));
+        assert_eq!(validate_keys(&json(r#"{"a.b": 1}"#), &[]), Ok(()));
+    }
+
+    #[test]
+    fn leaves_yields_scalars_with_pointers_in_document_order() {
+        let doc = json(r#"{"a": [1, {"b~": null}], "c/d": "x", "e": [], "f": {}, "g": true}"#);
+        let leaves: Vec<(String, JsonValue)> = doc
+            .leaves()
+            .map(|(pointer, leaf)| (pointer, leaf.clone()))
+            .collect();
+        assert_eq!(
+            leaves,
+            [
+                ("/a/0".to_string(), json("1")),
+                ("/a/1/b~0".to_string(), json("null")),
+                ("/c~1d".to_string(), json(r#""x""#)),
+                ("/g".to_string(), json("true")),
+            ]
+        );
+        for (pointer, leaf) in doc.leaves() {
+            assert_eq!(doc.pointer(&pointer), Some(leaf));
+        }
+    }
+
+    #[test]
+    fn leaves_of_scalar_and_empty_roots() {
+        let scalar = json("5");
+        assert_eq!(
+            scalar.leaves().collect::<Vec<_>>(),
+            [(String::new(), &scalar)]
+        );
+        assert_eq!(json("[[], {}]").leaves().count(), 0);
+    }
+
+    #[test]
+    fn to_string_at_writes_the_subtree_compactly() {
+        let doc = json(r#"{"user": {"name": "Ann", "tags": ["a", "b"]}, "n": 1}"#);
+        assert_eq!(
+            doc.to_string_at("/user"),
+            Some(r#"{"name":"Ann","tags":["a","b"]}"#.to_string())
+        );
+        assert_eq!(doc.to_string_at("/user/tags/1"), Some(r#""b""#.to_string()));
+        assert_eq!(
+            doc.to_string_at(""),
+            Some(r#"{"user":{"name":"Ann","tags":["a","b"]},"n":1}"#.to_string())
+        );
+    }
+
+    #[test]
+    fn to_string_at_is_none_for_missing_or_unwritable_subtrees() {
+        let mut doc = json(r#"{"a": [1]}"#);
+        assert_eq!(doc.to_string_at("/b"), None);
+        assert_eq!(doc.to_string_at("/a/1"), None);
+        *doc.pointer_mut("/a/0").unwrap() = JsonValue::Number(f64::NAN);
+        assert_eq!(doc.to_string_at("/a"), None);
+    }
+
+    #[test]
+    fn is_valid_json_accepts_finite_trees() {
+        assert!(json(r#"{"a": [1, 2.5, -0.0, "NaN"], "b": null}"#).is_valid_json());
+        assert_eq!(json("[]").validate_serializable(), Ok(()));
+    }
+
+    #[test]
+    fn validate_serializable_points_at_the_first_non_finite_number() {
+        let mut doc = json(r#"{"a": [1, 2], "b": {"c": 3}}"#);
+        *doc.pointer_mut("/b/c").unwrap() = JsonValue::Number(f64::INFINITY);
+        *doc.pointer_mut("/a/1").unwrap() = JsonValue::Number(f64::NAN);
+        assert!(!doc.is_valid_json());
+        assert_eq!(doc.validate_serializable(), Err("/a/1".to_string()));
+        assert_eq!(
+            JsonValue::Number(f64::NEG_INFINITY).validate_serializable(),
+            Err(String::new())
+        );
+    }
+
+    #[test]
+    fn require_pointers_lists_missing_pointers_in_the_order_given() {
+        let doc = json(r#"{"user": {"id": 1, "a/b": null}, "tags": ["x"]}"#);
+        assert_eq!(
+            require_pointers(&doc, &["/user/id", "/tags/0", "/user/a~1b", ""]),
+            Ok(())
+        );
+        assert_eq!(
+            require_pointers(&doc, &["/user/name", "/user/id", "/tags/1", "user"]),
+            Err(vec![
+                "/user/name".to_string(),
+                "/tags/1".to_string(),
+                "user".to_string()
+            ])
+        );
+        assert_eq!(require_pointers(&doc, &[]), Ok(()));
+    }
+}
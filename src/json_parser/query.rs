@@ -0,0 +1,32 @@
+use std::collections::BTreeSet;
+
+use super::value::JsonValue;
+
+impl JsonValue {
+    /// Every distinct object key used anywhere in the document.
+    pub fn all_keys(&self) -> BTreeSet<String> {
+        let mut keys = BTreeSet::new();
+        self.walk(|value| {
+            if let JsonValue::Object(object) = value {
+                keys.extend(object.iter().map(|(k, _)| k.clone()));
+            }
+        });
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    #[test]
+    fn all_keys_collects_distinct_keys_at_any_depth() {
+        let doc = parse_json(r#"{"b": [{"a": 1, "b": {"c": null}}], "a": {}}"#).unwrap();
+        assert_eq!(
+            doc.all_keys(),
+            BTreeSet::from(["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert!(parse_json(r#"["a", ["b"]]"#).unwrap().all_keys().is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+use super::error::ParseError;
+use super::parser::parse_json;
+use super::value::JsonValue;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punct(char),
+    // Already rendered as a double-quoted JSON string literal.
+    Str(String),
+    Number(String),
+    Word(String),
+    Other(char),
+}
+
+impl Token {
+    fn starts_value(&self) -> bool {
+        matches!(
+            self,
+            Token::Str(_) | Token::Number(_) | Token::Word(_) | Token::Punct('{' | '[')
+        )
+    }
+
+    fn ends_value(&self) -> bool {
+        matches!(
+            self,
+            Token::Str(_) | Token::Number(_) | Token::Word(_) | Token::Punct('}' | ']')
+        )
+    }
+}
+
+fn lex(input: &str) -> Vec<Token> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '{' | '}' | '[' | ']' | ':' | ',' => {
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+            '"' | '\'' => {
+                let (literal, end) = lex_string(&chars, i);
+                tokens.push(Token::Str(literal));
+                i = end;
+            }
+            '-' | '0'..='9' => {
+                let start = i;
+                while i < chars.len() && matches!(chars[i], '0'..='9' | '.' | 'e' | 'E' | '+' | '-')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Number(chars[start..i].iter().collect()));
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Word(chars[start..i].iter().collect()));
+            }
+            c => {
+                tokens.push(Token::Other(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+// Reads a single- or double-quoted string starting at `start` and renders it
+// as a double-quoted literal. Escapes other than `\'` are copied verbatim.
+fn lex_string(chars: &[char], start: usize) -> (String, usize) {
+    let quote = chars[start];
+    let mut literal = String::from('"');
+    let mut i = start + 1;
+    while i < chars.len() && chars[i] != quote {
+        match chars[i] {
+            '\\' if chars.get(i + 1) == Some(&'\'') => {
+                literal.push('\'');
+                i += 1;
+            }
+            '\\' => {
+                literal.push('\\');
+                if let Some(&next) = chars.get(i + 1) {
+                    literal.push(next);
+                    i += 1;
+                }
+            }
+            '"' => literal.push_str("\\\""),
+            c => literal.push(c),
+        }
+        i += 1;
+    }
+    literal.push('"');
+    (literal, i + 1)
+}
+
+/// Best-effort parse of almost-JSON, such as hand-edited configs or model output.
+///
+/// This is a heuristic token-level rewrite followed by a normal parse. It:
+/// - drops trailing commas before `}` or `]` (and repeated commas),
+/// - inserts a missing comma between two adjacent values or members,
+/// - turns single-quoted strings into double-quoted ones,
+/// - quotes bare identifiers used as keys, and bare words used as values
+///   other than `true`, `false` and `null`.
+///
+/// Anything it cannot make sense of is left in place, so truly broken input
+/// still fails with a `ParseError` (positions refer to the repaired text).
+pub fn repair(input: &str) -> Result<JsonValue, ParseError> {
+    let tokens = lex(input);
+    let mut out = String::with_capacity(input.len());
+    let mut prev: Option<&Token> = None;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1);
+        if *token == Token::Punct(',')
+            && (matches!(next, None | Some(Token::Punct('}' | ']' | ',')))
+                || matches!(prev, None | Some(Token::Punct('{' | '['))))
+        {
+            continue;
+        }
+        if token.starts_value() && prev.is_some_and(Token::ends_value) {
+            out.push(',');
+        }
+        match token {
+            Token::Punct(c) | Token::Other(c) => out.push(*c),
+            Token::Str(literal) | Token::Number(literal) => out.push_str(literal),
+            Token::Word(word) => {
+                let is_key = next == Some(&Token::Punct(':'));
+                match word.as_str() {
+                    "true" | "false" | "null" if !is_key => out.push_str(word),
+                    _ => {
+                        out.push('"');
+                        out.push_str(word);
+                        out.push('"');
+                    }
+                }
+            }
+        }
+        prev = Some(token);
+    }
+    parse_json(&out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn repair_fixes_each_class_of_mistake() {
+        let cases = [
+            ("[1, 2,]", "[1, 2]"),
+            (r#"{"a": 1,,}"#, r#"{"a": 1}"#),
+            ("[, 1]", "[1]"),
+            (r#"[1 2 {"a": 3} "b"]"#, r#"[1, 2, {"a": 3}, "b"]"#),
+            (r#"{"a": 1 "b": 2}"#, r#"{"a": 1, "b": 2}"#),
+            ("{'a': 'it\\'s \"x\"'}", r#"{"a": "it's \"x\""}"#),
+            (
+                "{name: Ann, ok: true, none: null}",
+                r#"{"name": "Ann", "ok": true, "none": null}"#,
+            ),
+            ("{true: 1}", r#"{"true": 1}"#),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(repair(input), Ok(json(expected)), "{}", input);
+        }
+        assert_eq!(repair(r#"{"a": [1, 2]}"#), Ok(json(r#"{"a": [1, 2]}"#)));
+    }
+
+    #[test]
+    fn repair_still_rejects_broken_structure() {
+        assert!(repair("[1, 2").is_err());
+        assert!(repair(r#"{"a" 1}"#).is_err());
+        assert!(repair("[1 = 2]").is_err());
+        assert!(repair("").is_err());
+    }
+}
@@ -0,0 +1,1681 @@
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+
+use super::error::{line_col, ParseError};
+use super::options::ParserOptions;
+use super::parser::{
+    decode_json_bytes, parse_json_with_options, read_full, CommentMap, Token, Tokenizer,
+};
+use super::pointer::escape_token;
+use super::value::JsonValue;
+
+/// The nesting limit of [`SerializerOptions::default`].
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// The newline sequence used in pretty output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// What to write for a `Number` that is NaN or infinite, which JSON cannot
+/// represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Write `null`, silently losing the value.
+    Null,
+    /// Fail with `SerializeError::NonFiniteNumber`.
+    #[default]
+    Error,
+    /// Write the JavaScript literals `NaN`, `Infinity` and `-Infinity`. The
+    /// output is no longer valid JSON, though JSON5 readers accept it.
+    JsLiteral,
+}
+
+/// Controls how a [`JsonValue`] is written out. The default is compact output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerializerOptions {
+    /// Spaces per nesting level; `None` writes everything on one line.
+    pub indent: Option<usize>,
+    /// In pretty output, write an array or object on a single line, as
+    /// `[1, 2]` or `{"a": 1}`, when that line stays within this many
+    /// characters, and break it over several lines only when it does not.
+    /// `None` always breaks non-empty containers.
+    pub max_width: Option<usize>,
+    /// In pretty output, pad keys so the colons of each object line up.
+    pub align_values: bool,
+    /// Write object members ordered by key instead of in insertion order.
+    pub sort_keys: bool,
+    /// Keys written first in every object, in this order, such as `id` and
+    /// `type` to match an external schema. The remaining keys follow in
+    /// insertion order, or by key with `sort_keys`.
+    pub key_order: Vec<String>,
+    /// Write every non-ASCII character as a `\uXXXX` escape (a surrogate pair
+    /// outside the BMP). By default only `"`, `\` and U+0000 to U+001F are
+    /// escaped and everything else is written as raw UTF-8.
+    pub escape_non_ascii: bool,
+    /// Escape U+2028 and U+2029 as `\u2028` and `\u2029`. Both are valid in
+    /// JSON strings but end a line in JavaScript source, which breaks JSONP
+    /// and output embedded in scripts.
+    pub escape_line_separators: bool,
+    /// Write `/` as `\/`, so that `</script>` cannot appear in output
+    /// embedded in HTML.
+    pub escape_slash: bool,
+    /// End the output with a newline, as many editors expect of files.
+    pub trailing_newline: bool,
+    /// Write `Number` values with exactly this many fractional digits, so
+    /// `3.14159` becomes `3.14` at precision 2. `None` uses the shortest text
+    /// that reads back as the same `f64`. Either way, magnitudes below `1e-6`
+    /// or from `1e21` up are written in exponent form, as JavaScript does:
+    /// `1e300`, or `1.00e300` at precision 2. `Integer` and `RawNumber` values
+    /// are always written as they are.
+    pub float_precision: Option<usize>,
+    /// Write whole-valued `Number`s such as `30.0` as `30`, as JavaScript
+    /// does. When unset they keep a decimal point, `30.0`, so that readers
+    /// can tell them from integers. Ignored with `float_precision`.
+    pub integers_without_decimal: bool,
+    pub non_finite: NonFinitePolicy,
+    /// Used for every line break, including the trailing newline.
+    pub line_ending: LineEnding,
+    /// Maximum nesting of arrays and objects, past which serializing fails
+    /// instead of risking a stack overflow on a hostile, deeply nested value.
+    /// `None` is unlimited.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for SerializerOptions {
+    fn default() -> Self {
+        SerializerOptions {
+            indent: None,
+            max_width: None,
+            align_values: false,
+            sort_keys: false,
+            key_order: Vec::new(),
+            escape_non_ascii: false,
+            escape_line_separators: false,
+            escape_slash: false,
+            trailing_newline: false,
+            float_precision: None,
+            integers_without_decimal: true,
+            non_finite: NonFinitePolicy::Error,
+            line_ending: LineEnding::Lf,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+        }
+    }
+}
+
+impl SerializerOptions {
+    pub fn pretty() -> Self {
+        SerializerOptions::default().indent(Some(2))
+    }
+
+    pub fn indent(mut self, indent: Option<usize>) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    pub fn align_values(mut self, align_values: bool) -> Self {
+        self.align_values = align_values;
+        self
+    }
+
+    pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    pub fn key_order<I, S>(mut self, key_order: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.key_order = key_order.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn escape_non_ascii(mut self, escape_non_ascii: bool) -> Self {
+        self.escape_non_ascii = escape_non_ascii;
+        self
+    }
+
+    pub fn escape_line_separators(mut self, escape_line_separators: bool) -> Self {
+        self.escape_line_separators = escape_line_separators;
+        self
+    }
+
+    pub fn escape_slash(mut self, escape_slash: bool) -> Self {
+        self.escape_slash = escape_slash;
+        self
+    }
+
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    pub fn float_precision(mut self, float_precision: Option<usize>) -> Self {
+        self.float_precision = float_precision;
+        self
+    }
+
+    pub fn integers_without_decimal(mut self, integers_without_decimal: bool) -> Self {
+        self.integers_without_decimal = integers_without_decimal;
+        self
+    }
+
+    pub fn non_finite(mut self, non_finite: NonFinitePolicy) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerializeError {
+    /// The value nests arrays and objects deeper than `SerializerOptions::max_depth`.
+    DepthLimitExceeded { limit: usize },
+    /// A `Number` is NaN or infinite and `SerializerOptions::non_finite` is
+    /// `NonFinitePolicy::Error`.
+    NonFiniteNumber { value: f64 },
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerializeError::DepthLimitExceeded { limit } => {
+                write!(f, "Value nests deeper than the limit of {}", limit)
+            }
+            SerializeError::NonFiniteNumber { value } => {
+                write!(f, "Number {} has no JSON representation", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+struct Serializer<'a> {
+    options: &'a SerializerOptions,
+    out: String,
+    // Only used by `to_string_with_comments`: the comments to re-emit, and
+    // the escaped pointer of the value being written.
+    comments: Option<&'a CommentMap>,
+    path: String,
+    // Set while trying to fit a container on one line for `max_width`.
+    inline: bool,
+}
+
+impl<'a> Serializer<'a> {
+    fn new(options: &'a SerializerOptions) -> Self {
+        Serializer {
+            options,
+            out: String::new(),
+            comments: None,
+            path: String::new(),
+            inline: false,
+        }
+    }
+
+    // Writes a child value with `token` appended to the path, when comments
+    // are being written.
+    fn write_child(
+        &mut self,
+        token: impl FnOnce() -> String,
+        value: &JsonValue,
+        depth: usize,
+    ) -> Result<(), SerializeError> {
+        if self.comments.is_none() {
+            return self.write_value(value, depth);
+        }
+        let len = self.path.len();
+        self.path.push('/');
+        self.path.push_str(&token());
+        let result = self.write_value(value, depth);
+        self.path.truncate(len);
+        result
+    }
+
+    // Writes the comments filed under the member `key`, each on its own line.
+    fn write_comments(&mut self, key: &str, depth: usize) {
+        let Some(comments) = self.comments else {
+            return;
+        };
+        let pointer = format!("{}/{}", self.path, escape_token(key));
+        for comment in comments.get(&pointer).into_iter().flatten() {
+            let comment = match comment.contains('\n') {
+                true => format!("/* {} */", comment),
+                false => format!("// {}", comment),
+            };
+            self.out.push_str(&comment);
+            self.write_newline(depth);
+        }
+    }
+
+    fn write_value(&mut self, value: &JsonValue, depth: usize) -> Result<(), SerializeError> {
+        match value {
+            JsonValue::Null => self.out.push_str("null"),
+            JsonValue::Boolean(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            JsonValue::Integer(i) => self.out.push_str(&i.to_string()),
+            JsonValue::Number(n) => return self.write_number(*n),
+            JsonValue::RawNumber(raw) => self.out.push_str(raw),
+            JsonValue::String(s) => self.write_string(s),
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                return self.write_container(value, depth)
+            }
+        }
+        Ok(())
+    }
+
+    // Writes an array or object on one line if `max_width` allows it, and
+    // otherwise as usual.
+    fn write_container(&mut self, value: &JsonValue, depth: usize) -> Result<(), SerializeError> {
+        if let Some(max_width) = self.options.max_width {
+            if self.options.indent.is_some() && !self.inline && !self.has_comments_within() {
+                let start = self.out.len();
+                let column = self
+                    .out
+                    .rsplit('\n')
+                    .next()
+                    .map_or(0, |line| line.chars().count());
+                self.inline = true;
+                let result = self.write_container_lines(value, depth);
+                self.inline = false;
+                result?;
+                if column + self.out[start..].chars().count() <= max_width {
+                    return Ok(());
+                }
+                self.out.truncate(start);
+            }
+        }
+        self.write_container_lines(value, depth)
+    }
+
+    fn write_container_lines(
+        &mut self,
+        value: &JsonValue,
+        depth: usize,
+    ) -> Result<(), SerializeError> {
+        match value {
+            JsonValue::Array(array) => self.write_array(array, depth),
+            JsonValue::Object(object) => self.write_object(object, depth),
+            scalar => self.write_value(scalar, depth),
+        }
+    }
+
+    // Whether comments are to be written inside the value at `path`, which
+    // then cannot go on one line.
+    fn has_comments_within(&self) -> bool {
+        let prefix = format!("{}/", self.path);
+        self.comments
+            .is_some_and(|comments| comments.keys().any(|pointer| pointer.starts_with(&prefix)))
+    }
+
+    // `depth` counts the containers around the one about to be written.
+    fn check_depth(&self, depth: usize) -> Result<(), SerializeError> {
+        match self.options.max_depth {
+            Some(limit) if depth >= limit => Err(SerializeError::DepthLimitExceeded { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    fn write_number(&mut self, n: f64) -> Result<(), SerializeError> {
+        if !n.is_finite() {
+            match self.options.non_finite {
+                NonFinitePolicy::Null => self.out.push_str("null"),
+                NonFinitePolicy::Error => return Err(SerializeError::NonFiniteNumber { value: n }),
+                NonFinitePolicy::JsLiteral if n.is_nan() => self.out.push_str("NaN"),
+                NonFinitePolicy::JsLiteral if n > 0.0 => self.out.push_str("Infinity"),
+                NonFinitePolicy::JsLiteral => self.out.push_str("-Infinity"),
+            }
+        } else if n != 0.0 && (n.abs() < 1e-6 || n.abs() >= 1e21) {
+            // Written out in full these would run to hundreds of digits, or
+            // round to zero at a fixed precision.
+            match self.options.float_precision {
+                Some(precision) => self.out.push_str(&format!("{:.*e}", precision, n)),
+                None => self.out.push_str(&format!("{:e}", n)),
+            }
+        } else if let Some(precision) = self.options.float_precision {
+            self.out.push_str(&format!("{:.*}", precision, n));
+        } else if n.fract() == 0.0 && !self.options.integers_without_decimal {
+            self.out.push_str(&format!("{:.1}", n));
+        } else {
+            self.out.push_str(&n.to_string());
+        }
+        Ok(())
+    }
+
+    fn write_string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\u{0008}' => self.out.push_str("\\b"),
+                '\u{000C}' => self.out.push_str("\\f"),
+                c if (c as u32) < 0x20 => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+                '/' if self.options.escape_slash => self.out.push_str("\\/"),
+                '\u{2028}' | '\u{2029}' if self.options.escape_line_separators => {
+                    self.out.push_str(&format!("\\u{:04x}", c as u32))
+                }
+                c if self.options.escape_non_ascii && !c.is_ascii() => {
+                    let mut units = [0; 2];
+                    for unit in c.encode_utf16(&mut units) {
+                        self.out.push_str(&format!("\\u{:04x}", unit));
+                    }
+                }
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+
+    fn write_array(&mut self, array: &[JsonValue], depth: usize) -> Result<(), SerializeError> {
+        self.check_depth(depth)?;
+        if array.is_empty() {
+            self.out.push_str("[]");
+            return Ok(());
+        }
+        self.out.push('[');
+        for (i, value) in array.iter().enumerate() {
+            self.write_separator(i);
+            self.write_newline(depth + 1);
+            self.write_child(|| i.to_string(), value, depth + 1)?;
+        }
+        self.write_newline(depth);
+        self.out.push(']');
+        Ok(())
+    }
+
+    fn write_object(
+        &mut self,
+        object: &[(String, JsonValue)],
+        depth: usize,
+    ) -> Result<(), SerializeError> {
+        self.check_depth(depth)?;
+        if object.is_empty() {
+            self.out.push_str("{}");
+            return Ok(());
+        }
+        let width = if self.options.align_values && self.options.indent.is_some() && !self.inline {
+            object
+                .iter()
+                .map(|(key, _)| quoted_len(key, self.options))
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        let mut entries: Vec<_> = object.iter().collect();
+        if self.options.sort_keys {
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+        if !self.options.key_order.is_empty() {
+            let key_order = &self.options.key_order;
+            entries.sort_by_key(|(key, _)| {
+                key_order
+                    .iter()
+                    .position(|k| k == key)
+                    .unwrap_or(key_order.len())
+            });
+        }
+
+        self.out.push('{');
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            self.write_separator(i);
+            self.write_newline(depth + 1);
+            self.write_comments(key, depth + 1);
+            let start = self.out.len();
+            self.write_string(key);
+            let written = self.out[start..].chars().count();
+            self.out
+                .extend(std::iter::repeat_n(' ', width.saturating_sub(written)));
+            self.out.push(':');
+            if self.options.indent.is_some() {
+                self.out.push(' ');
+            }
+            self.write_child(|| escape_token(key), value, depth + 1)?;
+        }
+        self.write_newline(depth);
+        self.out.push('}');
+        Ok(())
+    }
+
+    // The comma before every element but the first; on a single line it is
+    // followed by a space.
+    fn write_separator(&mut self, index: usize) {
+        if index > 0 {
+            self.out.push(',');
+            if self.inline {
+                self.out.push(' ');
+            }
+        }
+    }
+
+    fn write_newline(&mut self, depth: usize) {
+        if self.inline {
+            return;
+        }
+        if let Some(indent) = self.options.indent {
+            self.out.push_str(self.options.line_ending.as_str());
+            self.out.extend(std::iter::repeat_n(' ', indent * depth));
+        }
+    }
+}
+
+// Width of a key once quoted and escaped, used to line up aligned values.
+fn quoted_len(key: &str, options: &SerializerOptions) -> usize {
+    let mut serializer = Serializer::new(options);
+    serializer.write_string(key);
+    serializer.out.chars().count()
+}
+
+// The text the serializer writes for the float `n` under `options`, for other
+// writers of numbers to match it.
+pub(crate) fn format_number(n: f64, options: &SerializerOptions) -> Result<String, SerializeError> {
+    let mut serializer = Serializer::new(options);
+    serializer.write_number(n)?;
+    Ok(serializer.out)
+}
+
+/// Quotes and escapes `s` as a JSON string literal, exactly as the serializer
+/// writes strings: `a "b"` becomes `"a \"b\""`.
+pub fn escape_string(s: &str) -> String {
+    let options = SerializerOptions::default();
+    let mut serializer = Serializer::new(&options);
+    serializer.write_string(s);
+    serializer.out
+}
+
+pub fn to_string(value: &JsonValue) -> Result<String, SerializeError> {
+    to_string_with_options(value, &SerializerOptions::default())
+}
+
+/// Compact output as bytes, e.g. for writing to a socket. The buffer is the
+/// serializer's own, handed over without copying or re-validating it.
+pub fn to_vec(value: &JsonValue) -> Result<Vec<u8>, SerializeError> {
+    to_string(value).map(String::into_bytes)
+}
+
+pub fn to_string_pretty(value: &JsonValue) -> Result<String, SerializeError> {
+    to_string_with_options(value, &SerializerOptions::pretty())
+}
+
+/// Compact output with sorted keys, so equal documents serialize identically
+/// regardless of member order.
+pub fn to_string_canonical(value: &JsonValue) -> Result<String, SerializeError> {
+    to_string_with_options(value, &SerializerOptions::default().sort_keys(true))
+}
+
+pub fn to_string_with_options(
+    value: &JsonValue,
+    options: &SerializerOptions,
+) -> Result<String, SerializeError> {
+    let mut serializer = Serializer::new(options);
+    serializer.write_value(value, 0)?;
+    if options.trailing_newline {
+        serializer.out.push_str(options.line_ending.as_str());
+    }
+    Ok(serializer.out)
+}
+
+/// Pretty-prints `value` with the comments captured by
+/// [`parse_json_with_comments`](super::parser::parse_json_with_comments)
+/// written back, each on its own line before the member it was filed under,
+/// so parsing the result again yields the same comments. Comments spanning
+/// several lines are written as `/* */` blocks, others as `//` lines.
+pub fn to_string_with_comments(
+    value: &JsonValue,
+    comments: &CommentMap,
+) -> Result<String, SerializeError> {
+    let options = SerializerOptions::pretty();
+    let mut serializer = Serializer::new(&options);
+    serializer.comments = Some(comments);
+    serializer.write_value(value, 0)?;
+    Ok(serializer.out)
+}
+
+impl JsonValue {
+    /// A one-line description for log messages, like JSON but with bare
+    /// object keys: `{name: "John", age: 30}`. Output longer than `max_len`
+    /// characters is cut short and ends in `...`, within `max_len`.
+    pub fn summary(&self, max_len: usize) -> String {
+        let mut out = String::new();
+        // Every character takes at most four bytes, so past this many bytes
+        // the output is certainly too long and the rest can be skipped.
+        write_summary(self, &mut out, max_len.saturating_mul(4));
+        if out.chars().count() <= max_len {
+            return out;
+        }
+        let mut summary: String = out.chars().take(max_len.saturating_sub(3)).collect();
+        summary.extend("...".chars().take(max_len));
+        summary
+    }
+}
+
+fn write_summary(value: &JsonValue, out: &mut String, max_bytes: usize) {
+    match value {
+        JsonValue::Array(array) => {
+            out.push('[');
+            for (i, element) in array.iter().enumerate() {
+                if out.len() > max_bytes {
+                    return;
+                }
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_summary(element, out, max_bytes);
+            }
+            out.push(']');
+        }
+        JsonValue::Object(object) => {
+            out.push('{');
+            for (i, (key, member)) in object.iter().enumerate() {
+                if out.len() > max_bytes {
+                    return;
+                }
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(key);
+                out.push_str(": ");
+                write_summary(member, out, max_bytes);
+            }
+            out.push('}');
+        }
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Boolean(b) => out.push_str(&b.to_string()),
+        JsonValue::Integer(i) => out.push_str(&i.to_string()),
+        JsonValue::Number(n) => out.push_str(&n.to_string()),
+        JsonValue::RawNumber(raw) => out.push_str(raw),
+        JsonValue::String(s) => out.push_str(&escape_string(s)),
+    }
+}
+
+/// Reads a whole JSON document from `reader` and writes it to `writer` pretty
+/// printed with `indent` spaces per level, like a `json-pretty` tool. Numbers
+/// are copied exactly as written and member order is kept, so only whitespace
+/// changes. The input encoding is detected as by
+/// [`parse_json_reader`](super::parser::parse_json_reader), and the document
+/// is held in memory while it is rewritten.
+pub fn reformat<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    indent: usize,
+) -> Result<(), ParseError> {
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input).map_err(io_error)?;
+    let input = decode_json_bytes(&input)?;
+    let value =
+        parse_json_with_options(&input, &ParserOptions::default().preserve_number_text(true))?;
+    // With numbers kept as text and no depth limit, writing cannot fail.
+    let options = SerializerOptions::pretty()
+        .indent(Some(indent))
+        .max_depth(None)
+        .trailing_newline(true);
+    let output = to_string_with_options(&value, &options).unwrap_or_default();
+    writer.write_all(output.as_bytes()).map_err(io_error)?;
+    writer.flush().map_err(io_error)
+}
+
+/// Like [`reformat`], but reads the input a token at a time and writes each
+/// token as soon as it is checked, so no tree is built and the input is never
+/// held whole. The output is the same as that of `reformat`.
+///
+/// Memory use is bounded by the longest single token and one entry per open
+/// container. UTF-8 input, with or without a byte order mark, is decoded as it
+/// is read; UTF-16 input is detected as by `reformat` but decoded in full
+/// first. Errors are reported as they are met, so a document that turns out
+/// to be invalid, including one with invalid UTF-8 partway through, leaves
+/// `writer` holding the output up to the offending token.
+pub fn prettify_stream<R: BufRead, W: Write>(
+    mut reader: R,
+    writer: W,
+    indent: usize,
+) -> Result<(), ParseError> {
+    let mut head = [0; 3];
+    let len = read_full(&mut reader, &mut head)?;
+    let head = &head[..len];
+    // The same detection as `decode_json_bytes`.
+    match head {
+        [0xEF, 0xBB, 0xBF] => prettify_utf8(reader, writer, indent, 3),
+        [0xFE, 0xFF, ..] | [0xFF, 0xFE, ..] | [0, _, ..] | [_, 0, ..] => {
+            let mut bytes = head.to_vec();
+            reader.read_to_end(&mut bytes).map_err(io_error)?;
+            let input = decode_json_bytes(&bytes)?;
+            prettify_utf8(input.as_bytes(), writer, indent, 0)
+        }
+        _ => prettify_utf8(head.chain(reader), writer, indent, 0),
+    }
+}
+
+fn io_error(e: io::Error) -> ParseError {
+    ParseError::Io {
+        message: e.to_string(),
+    }
+}
+
+// Streams UTF-8 `reader`, which `bom` bytes of byte order mark preceded.
+fn prettify_utf8<R: BufRead, W: Write>(
+    reader: R,
+    writer: W,
+    indent: usize,
+    bom: usize,
+) -> Result<(), ParseError> {
+    let options = ParserOptions::default().preserve_number_text(true);
+    let mut chars = CharReader {
+        reader,
+        peeked: None,
+        at: Position {
+            line: 1,
+            column: 1,
+            offset: 0,
+        },
+        bom,
+    };
+    let mut writer = io::BufWriter::new(writer);
+    let mut out = StreamPrettifier {
+        indent,
+        stack: Vec::new(),
+        expect: Expect::Value,
+        just_opened: false,
+        text: String::new(),
+    };
+    let mut lexeme = String::new();
+    let mut empty = true;
+    while let Some(start) = chars.next_lexeme(&mut lexeme)? {
+        // The lexeme holds one token, or a run the tokenizer splits or
+        // rejects exactly as it would within the whole text.
+        for token in Tokenizer::with_options(&lexeme, &options) {
+            let (token, span) = token.map_err(|e| start.relocate(e))?;
+            let found = lexeme[span.start..].chars().next().unwrap_or_default();
+            out.token(token, start.advance(&lexeme[..span.start]), found)?;
+            writer.write_all(out.text.as_bytes()).map_err(io_error)?;
+            out.text.clear();
+            empty = false;
+        }
+    }
+    match out.expect {
+        Expect::End => {}
+        _ if empty => return Err(ParseError::EmptyInput),
+        _ => return Err(ParseError::UnexpectedEof),
+    }
+    writer.write_all(b"\n").map_err(io_error)?;
+    writer.flush().map_err(io_error)
+}
+
+// A place in the decoded input: line and column as `ParseError` counts them,
+// and the byte offset.
+#[derive(Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+impl Position {
+    // The position `text` further on.
+    fn advance(self, text: &str) -> Position {
+        let (line, column) = self.shift(line_col(text, text.len()));
+        Position {
+            line,
+            column,
+            offset: self.offset + text.len(),
+        }
+    }
+
+    // Turns a line and column counted from here into absolute ones.
+    fn shift(self, (line, column): (usize, usize)) -> (usize, usize) {
+        match line {
+            1 => (self.line, self.column + column - 1),
+            _ => (self.line + line - 1, column),
+        }
+    }
+
+    // Moves the position of an error in a lexeme starting here to the
+    // position in the whole input.
+    fn relocate(self, error: ParseError) -> ParseError {
+        match error {
+            ParseError::Syntax {
+                message,
+                line,
+                column,
+            } => {
+                let (line, column) = self.shift((line, column));
+                ParseError::Syntax {
+                    message,
+                    line,
+                    column,
+                }
+            }
+            ParseError::UnterminatedString { line, column } => {
+                let (line, column) = self.shift((line, column));
+                ParseError::UnterminatedString { line, column }
+            }
+            ParseError::UnexpectedBom { offset } => ParseError::UnexpectedBom {
+                offset: self.offset + offset,
+            },
+            error => error,
+        }
+    }
+}
+
+// Decodes UTF-8 from `reader` one character at a time.
+struct CharReader<R> {
+    reader: R,
+    peeked: Option<char>,
+    // The position of the next character.
+    at: Position,
+    bom: usize,
+}
+
+impl<R: BufRead> CharReader<R> {
+    fn peek(&mut self) -> Result<Option<char>, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = self.decode()?;
+        }
+        Ok(self.peeked)
+    }
+
+    fn next_char(&mut self) -> Result<Option<char>, ParseError> {
+        let c = self.peek()?;
+        if let Some(c) = self.peeked.take() {
+            self.at.offset += c.len_utf8();
+            if c == '\n' {
+                self.at.line += 1;
+                self.at.column = 1;
+            } else {
+                self.at.column += 1;
+            }
+        }
+        Ok(c)
+    }
+
+    fn decode(&mut self) -> Result<Option<char>, ParseError> {
+        let mut bytes = [0; 4];
+        let width = match self.reader.fill_buf().map_err(io_error)?.first() {
+            None => return Ok(None),
+            Some(0xC0..=0xDF) => 2,
+            Some(0xE0..=0xEF) => 3,
+            Some(0xF0..=0xF7) => 4,
+            Some(_) => 1,
+        };
+        let mut len = 0;
+        while len < width {
+            match self.reader.fill_buf().map_err(io_error)?.first() {
+                Some(&byte) => bytes[len] = byte,
+                None => break,
+            }
+            self.reader.consume(1);
+            len += 1;
+        }
+        std::str::from_utf8(&bytes[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .map(Some)
+            .ok_or(ParseError::InvalidUtf8 {
+                offset: self.bom + self.at.offset,
+            })
+    }
+
+    // Skips whitespace and reads the next lexeme into `lexeme`: a punctuation
+    // character, a string up to its closing quote, or a run of anything else
+    // up to whitespace, punctuation or a quote. Returns where it starts, or
+    // `None` at the end of the input.
+    fn next_lexeme(&mut self, lexeme: &mut String) -> Result<Option<Position>, ParseError> {
+        while self.peek()?.is_some_and(char::is_whitespace) {
+            self.next_char()?;
+        }
+        let start = self.at;
+        let Some(first) = self.next_char()? else {
+            return Ok(None);
+        };
+        lexeme.clear();
+        lexeme.push(first);
+        let delimiter = |c: char| c.is_whitespace() || "{}[]:,\"".contains(c);
+        match first {
+            '"' => {
+                let mut escaped = false;
+                while let Some(c) = self.next_char()? {
+                    lexeme.push(c);
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            c if delimiter(c) => {}
+            _ => {
+                while let Some(c) = self.peek()?.filter(|&c| !delimiter(c)) {
+                    lexeme.push(c);
+                    self.next_char()?;
+                }
+            }
+        }
+        Ok(Some(start))
+    }
+}
+
+// What `prettify_stream` accepts next.
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    ValueOrClose,
+    Key,
+    KeyOrClose,
+    Colon,
+    CommaOrClose,
+    End,
+}
+
+// Checks the token sequence of `prettify_stream` against the JSON grammar
+// and renders each token into `text`.
+struct StreamPrettifier {
+    indent: usize,
+    // The close token of each open container, innermost last.
+    stack: Vec<Token>,
+    expect: Expect,
+    // Set after an open bracket, whose newline waits until it is known
+    // whether the container is empty.
+    just_opened: bool,
+    text: String,
+}
+
+impl StreamPrettifier {
+    // Renders `token`, found at `at` and starting with `found`.
+    fn token(&mut self, token: Token, at: Position, found: char) -> Result<(), ParseError> {
+        let close = matches!(token, Token::BracketClose | Token::BraceClose);
+        if self.just_opened && !close {
+            self.newline();
+        }
+        match (self.expect, token) {
+            (Expect::Key | Expect::KeyOrClose, Token::String(key)) => {
+                self.text.push_str(&escape_string(&key));
+                self.expect = Expect::Colon;
+            }
+            (Expect::Colon, Token::Colon) => {
+                self.text.push_str(": ");
+                self.expect = Expect::Value;
+            }
+            (Expect::Value | Expect::ValueOrClose, Token::BracketOpen) => {
+                self.open('[', Token::BracketClose, Expect::ValueOrClose)
+            }
+            (Expect::Value | Expect::ValueOrClose, Token::BraceOpen) => {
+                self.open('{', Token::BraceClose, Expect::KeyOrClose)
+            }
+            (Expect::Value | Expect::ValueOrClose, token) if !close => {
+                let value = match token {
+                    Token::String(s) => JsonValue::String(s),
+                    Token::Integer(i) => JsonValue::Integer(i),
+                    Token::Number(n) => JsonValue::Number(n),
+                    Token::RawNumber(raw) => JsonValue::RawNumber(raw),
+                    Token::True => JsonValue::Boolean(true),
+                    Token::False => JsonValue::Boolean(false),
+                    Token::Null => JsonValue::Null,
+                    _ => return Err(unexpected(at, found)),
+                };
+                let options = SerializerOptions::default().non_finite(NonFinitePolicy::Null);
+                self.text
+                    .push_str(&to_string_with_options(&value, &options).unwrap_or_default());
+                self.after_value();
+            }
+            (Expect::CommaOrClose, Token::Comma) => {
+                self.text.push(',');
+                self.newline();
+                self.expect = match self.stack.last() {
+                    Some(Token::BraceClose) => Expect::Key,
+                    _ => Expect::Value,
+                };
+            }
+            (Expect::CommaOrClose | Expect::ValueOrClose | Expect::KeyOrClose, token)
+                if self.stack.last() == Some(&token) =>
+            {
+                self.stack.pop();
+                if !self.just_opened {
+                    self.newline();
+                }
+                self.text.push(match token {
+                    Token::BracketClose => ']',
+                    _ => '}',
+                });
+                self.after_value();
+            }
+            (Expect::CommaOrClose | Expect::ValueOrClose | Expect::KeyOrClose, _) if close => {
+                return Err(ParseError::MismatchedBracket {
+                    expected: match self.stack.last() {
+                        Some(Token::BracketClose) => ']',
+                        _ => '}',
+                    },
+                    found,
+                    line: at.line,
+                    column: at.column,
+                });
+            }
+            (Expect::End, _) => {
+                return Err(ParseError::TrailingData {
+                    line: at.line,
+                    column: at.column,
+                    offset: at.offset,
+                })
+            }
+            _ => return Err(unexpected(at, found)),
+        }
+        self.just_opened = matches!(self.expect, Expect::ValueOrClose | Expect::KeyOrClose);
+        Ok(())
+    }
+
+    fn open(&mut self, bracket: char, close: Token, expect: Expect) {
+        self.text.push(bracket);
+        self.stack.push(close);
+        self.expect = expect;
+    }
+
+    fn after_value(&mut self) {
+        self.expect = match self.stack.is_empty() {
+            true => Expect::End,
+            false => Expect::CommaOrClose,
+        };
+    }
+
+    fn newline(&mut self) {
+        self.text.push('\n');
+        self.text
+            .extend(std::iter::repeat_n(' ', self.indent * self.stack.len()));
+    }
+}
+
+fn unexpected(at: Position, found: char) -> ParseError {
+    ParseError::Syntax {
+        message: format!("Unexpected character: {}", found),
+        line: at.line,
+        column: at.column,
+    }
+}
+
+/// Writes a JSON array to `W` one element at a time, so a large array never
+/// has to be held in memory. Call [`begin`](ArrayWriter::begin), then
+/// [`push`](ArrayWriter::push) for each element, then [`end`](ArrayWriter::end).
+///
+/// A value that cannot be serialized is reported as an `io::Error` of kind
+/// `InvalidData` wrapping the [`SerializeError`].
+pub struct ArrayWriter<W: Write> {
+    out: W,
+    options: SerializerOptions,
+    len: usize,
+    started: bool,
+}
+
+impl<W: Write> ArrayWriter<W> {
+    pub fn new(out: W) -> Self {
+        ArrayWriter::with_options(out, SerializerOptions::default())
+    }
+
+    /// Elements are written as if they were inside an array serialized with
+    /// `options`, so pretty options put one element per line.
+    pub fn with_options(out: W, options: SerializerOptions) -> Self {
+        ArrayWriter {
+            out,
+            options,
+            len: 0,
+            started: false,
+        }
+    }
+
+    /// Writes the opening `[`. `push` and `end` call this if it has not been.
+    pub fn begin(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.started = true;
+            self.out.write_all(b"[")?;
+        }
+        Ok(())
+    }
+
+    pub fn push(&mut self, value: &JsonValue) -> io::Result<()> {
+        self.begin()?;
+        let mut serializer = Serializer::new(&self.options);
+        if self.len > 0 {
+            serializer.out.push(',');
+        }
+        serializer.write_newline(1);
+        serializer
+            .write_value(value, 1)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.out.write_all(serializer.out.as_bytes())?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// The number of elements pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Writes the closing `]`, flushes, and returns the underlying writer.
+    pub fn end(mut self) -> io::Result<W> {
+        self.begin()?;
+        let mut serializer = Serializer::new(&self.options);
+        if self.len > 0 {
+            serializer.write_newline(0);
+        }
+        serializer.out.push(']');
+        if self.options.trailing_newline {
+            serializer.out.push_str(self.options.line_ending.as_str());
+        }
+        self.out.write_all(serializer.out.as_bytes())?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json_with_comments;
+    use crate::json_parser::parser::{parse_json, parse_json_bytes};
+
+    fn render(input: &str, options: &SerializerOptions) -> String {
+        to_string_with_options(&parse_json(input).unwrap(), options).unwrap()
+    }
+
+    #[test]
+    fn align_values_pads_keys_to_the_longest() {
+        let input = r#"{"a": 1, "long_key": [true], "mid": {"x": null}}"#;
+        let options = SerializerOptions::pretty().align_values(true);
+        assert_eq!(
+            render(input, &options),
+            "{\n  \"a\"       : 1,\n  \"long_key\": [\n    true\n  ],\n  \"mid\"     : {\n    \"x\": null\n  }\n}"
+        );
+        assert_eq!(
+            render(input, &SerializerOptions::pretty()),
+            "{\n  \"a\": 1,\n  \"long_key\": [\n    true\n  ],\n  \"mid\": {\n    \"x\": null\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn align_values_is_ignored_in_compact_output() {
+        let options = SerializerOptions::default().align_values(true);
+        assert_eq!(
+            render(r#"{"a": 1, "bbb": 2}"#, &options),
+            r#"{"a":1,"bbb":2}"#
+        );
+    }
+
+    #[test]
+    fn canonical_output_sorts_keys_at_every_depth() {
+        let a = parse_json(r#"{"b": [{"y": 1, "x": 2}], "a": null}"#).unwrap();
+        let b = parse_json(r#"{"a": null, "b": [{"x": 2, "y": 1}]}"#).unwrap();
+        assert_eq!(
+            to_string_canonical(&a).unwrap(),
+            r#"{"a":null,"b":[{"x":2,"y":1}]}"#
+        );
+        assert_eq!(to_string_canonical(&a), to_string_canonical(&b));
+        let reordered = parse_json("[2, 1]").unwrap();
+        assert_eq!(to_string_canonical(&reordered).unwrap(), "[2,1]");
+    }
+
+    #[test]
+    fn escape_non_ascii_writes_surrogate_pairs_outside_the_bmp() {
+        let value = JsonValue::String("é😀\u{7f}\"\n\u{1}".into());
+        assert_eq!(to_string(&value).unwrap(), "\"é😀\u{7f}\\\"\\n\\u0001\"");
+        let options = SerializerOptions::default().escape_non_ascii(true);
+        let escaped = to_string_with_options(&value, &options).unwrap();
+        assert_eq!(escaped, "\"\\u00e9\\ud83d\\ude00\u{7f}\\\"\\n\\u0001\"");
+        assert!(escaped.is_ascii());
+        assert_eq!(parse_json(&escaped), Ok(value));
+    }
+
+    #[test]
+    fn trailing_newline_ends_compact_and_pretty_output() {
+        let value = parse_json("[1]").unwrap();
+        let newline = |options: SerializerOptions| {
+            to_string_with_options(&value, &options.trailing_newline(true)).unwrap()
+        };
+        assert_eq!(newline(SerializerOptions::default()), "[1]\n");
+        assert_eq!(newline(SerializerOptions::pretty()), "[\n  1\n]\n");
+        assert_eq!(to_string(&value).unwrap(), "[1]");
+    }
+
+    #[test]
+    fn float_precision_fixes_fractional_digits_of_numbers_only() {
+        let value = parse_json("[3.14159, 2.5, 7]").unwrap();
+        let options = SerializerOptions::default().float_precision(Some(2));
+        assert_eq!(
+            to_string_with_options(&value, &options).unwrap(),
+            "[3.14,2.50,7]"
+        );
+        let rounded = SerializerOptions::default().float_precision(Some(0));
+        assert_eq!(
+            to_string_with_options(&JsonValue::Number(2.7), &rounded).unwrap(),
+            "3"
+        );
+        assert_eq!(to_string(&value).unwrap(), "[3.14159,2.5,7]");
+    }
+
+    #[test]
+    fn float_precision_keeps_exponent_form_for_extreme_floats() {
+        let options = SerializerOptions::default().float_precision(Some(2));
+        let write = |n: f64| to_string_with_options(&JsonValue::Number(n), &options).unwrap();
+        assert_eq!(write(1e300), "1.00e300");
+        assert_eq!(write(1e-300), "1.00e-300");
+        assert_eq!(write(-1.23456e-7), "-1.23e-7");
+        assert_eq!(write(1e20), "100000000000000000000.00");
+        for n in [1e300, 1e-300] {
+            assert_eq!(parse_json(&write(n)).unwrap().as_f64(), Some(n));
+        }
+    }
+
+    #[test]
+    fn extreme_floats_use_exponent_form_and_round_trip() {
+        let cases = [
+            (1e300, "1e300"),
+            (5e-324, "5e-324"),
+            (-1.5e22, "-1.5e22"),
+            (1e21, "1e21"),
+            (1.25e-7, "1.25e-7"),
+            (f64::MAX, "1.7976931348623157e308"),
+            (1e20, "100000000000000000000"),
+            (1e-6, "0.000001"),
+            (0.0, "0"),
+            (2.5, "2.5"),
+        ];
+        for (n, text) in cases {
+            let written = to_string(&JsonValue::Number(n)).unwrap();
+            assert_eq!(written, text);
+            assert_eq!(parse_json(&written).unwrap().as_f64(), Some(n), "{}", text);
+        }
+        let options = SerializerOptions::default().integers_without_decimal(false);
+        assert_eq!(
+            to_string_with_options(&JsonValue::Number(1e300), &options).unwrap(),
+            "1e300"
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings_apply_to_every_break() {
+        let value = parse_json(r#"{"a": [1]}"#).unwrap();
+        let options = SerializerOptions::pretty()
+            .line_ending(LineEnding::CrLf)
+            .trailing_newline(true);
+        let text = to_string_with_options(&value, &options).unwrap();
+        assert_eq!(text, "{\r\n  \"a\": [\r\n    1\r\n  ]\r\n}\r\n");
+        assert_eq!(text.matches('\n').count(), text.matches("\r\n").count());
+        assert_eq!(parse_json(&text), Ok(value.clone()));
+        let compact = SerializerOptions::default().line_ending(LineEnding::CrLf);
+        assert_eq!(
+            to_string_with_options(&value, &compact).unwrap(),
+            r#"{"a":[1]}"#
+        );
+    }
+
+    fn nested_arrays(depth: usize) -> JsonValue {
+        (0..depth).fold(JsonValue::Null, |inner, _| JsonValue::Array(vec![inner]))
+    }
+
+    #[test]
+    fn max_depth_bounds_container_nesting() {
+        let options = SerializerOptions::default().max_depth(Some(3));
+        assert_eq!(
+            to_string_with_options(&nested_arrays(3), &options).unwrap(),
+            "[[[null]]]"
+        );
+        assert_eq!(
+            to_string_with_options(&nested_arrays(4), &options),
+            Err(SerializeError::DepthLimitExceeded { limit: 3 })
+        );
+        assert_eq!(
+            to_string(&nested_arrays(DEFAULT_MAX_DEPTH + 1)),
+            Err(SerializeError::DepthLimitExceeded {
+                limit: DEFAULT_MAX_DEPTH
+            })
+        );
+        let unlimited = SerializerOptions::default().max_depth(None);
+        assert!(to_string_with_options(&nested_arrays(1000), &unlimited).is_ok());
+    }
+
+    #[test]
+    fn array_writer_matches_serializing_the_whole_array() {
+        let values = parse_json(r#"[1, {"a": [true]}, "s"]"#).unwrap();
+        for options in [SerializerOptions::default(), SerializerOptions::pretty()] {
+            let mut writer = ArrayWriter::with_options(Vec::new(), options.clone());
+            assert!(writer.is_empty());
+            for value in values.as_array().unwrap() {
+                writer.push(value).unwrap();
+            }
+            assert_eq!(writer.len(), 3);
+            let written = String::from_utf8(writer.end().unwrap()).unwrap();
+            assert_eq!(written, to_string_with_options(&values, &options).unwrap());
+        }
+        let empty = ArrayWriter::new(Vec::new()).end().unwrap();
+        assert_eq!(empty, b"[]");
+    }
+
+    #[test]
+    fn array_writer_rejects_unserializable_elements() {
+        let mut writer = ArrayWriter::new(Vec::new());
+        writer.push(&JsonValue::Integer(1)).unwrap();
+        let error = writer.push(&JsonValue::Number(f64::NAN)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(writer.len(), 1);
+        assert_eq!(writer.end().unwrap(), b"[1]");
+    }
+
+    #[test]
+    fn escape_string_writes_a_json_literal() {
+        assert_eq!(escape_string(r#"a "b""#), r#""a \"b\"""#);
+        assert_eq!(
+            escape_string("\\ \n\r\t\u{8}\u{c}\u{0}\u{1f} / é"),
+            r#""\\ \n\r\t\b\f\u0000\u001f / é""#
+        );
+        assert_eq!(escape_string(""), r#""""#);
+        for s in ["plain", "\"\\", "\u{2028}😀", "\u{7f}"] {
+            assert_eq!(
+                parse_json(&escape_string(s)),
+                Ok(JsonValue::String(s.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn escape_line_separators_and_slash_are_opt_in() {
+        let value = JsonValue::String("</script>\u{2028}\u{2029}".into());
+        assert_eq!(to_string(&value).unwrap(), "\"</script>\u{2028}\u{2029}\"");
+        let options = SerializerOptions::default()
+            .escape_line_separators(true)
+            .escape_slash(true);
+        let escaped = to_string_with_options(&value, &options).unwrap();
+        assert_eq!(escaped, r#""<\/script>\u2028\u2029""#);
+        assert_eq!(parse_json(&escaped), Ok(value));
+        let only_slash = SerializerOptions::default().escape_slash(true);
+        assert_eq!(
+            to_string_with_options(&JsonValue::String("a/\u{2028}".into()), &only_slash).unwrap(),
+            "\"a\\/\u{2028}\""
+        );
+    }
+
+    #[test]
+    fn key_order_writes_listed_keys_first_in_every_object() {
+        let input = r#"{"name": "a", "type": "t", "id": 1, "child": {"x": 1, "id": 2}}"#;
+        let options = SerializerOptions::default().key_order(["id", "type"]);
+        assert_eq!(
+            render(input, &options),
+            r#"{"id":1,"type":"t","name":"a","child":{"id":2,"x":1}}"#
+        );
+    }
+
+    #[test]
+    fn key_order_combines_with_sort_keys() {
+        let input = r#"{"b": 1, "type": 2, "a": 3, "missing_id": 4}"#;
+        let options = SerializerOptions::default()
+            .key_order(["id", "type"])
+            .sort_keys(true);
+        assert_eq!(
+            render(input, &options),
+            r#"{"type":2,"a":3,"b":1,"missing_id":4}"#
+        );
+        assert_eq!(
+            render(
+                input,
+                &SerializerOptions::default().key_order(Vec::<String>::new())
+            ),
+            r#"{"b":1,"type":2,"a":3,"missing_id":4}"#
+        );
+    }
+
+    fn non_finite_array() -> JsonValue {
+        JsonValue::Array(vec![
+            JsonValue::Number(f64::NAN),
+            JsonValue::Number(f64::INFINITY),
+            JsonValue::Number(f64::NEG_INFINITY),
+            JsonValue::Number(1.5),
+        ])
+    }
+
+    #[test]
+    fn non_finite_numbers_are_an_error_by_default() {
+        assert_eq!(
+            SerializerOptions::default().non_finite,
+            NonFinitePolicy::Error
+        );
+        assert!(matches!(
+            to_string(&non_finite_array()),
+            Err(SerializeError::NonFiniteNumber { value }) if value.is_nan()
+        ));
+        assert!(matches!(
+            to_string(&JsonValue::Number(f64::NEG_INFINITY)),
+            Err(SerializeError::NonFiniteNumber {
+                value: f64::NEG_INFINITY
+            })
+        ));
+    }
+
+    #[test]
+    fn non_finite_policy_can_write_null_or_js_literals() {
+        let null = SerializerOptions::default().non_finite(NonFinitePolicy::Null);
+        assert_eq!(
+            to_string_with_options(&non_finite_array(), &null).unwrap(),
+            "[null,null,null,1.5]"
+        );
+        let js = SerializerOptions::default().non_finite(NonFinitePolicy::JsLiteral);
+        assert_eq!(
+            to_string_with_options(&non_finite_array(), &js).unwrap(),
+            "[NaN,Infinity,-Infinity,1.5]"
+        );
+    }
+
+    #[test]
+    fn to_string_with_comments_writes_comments_back() {
+        let input = "{\n  // the port\n  \"port\": 80,\n  \"db\": {\n    /* first\n       second */\n    \"host\": \"x\" // trailing\n  }\n}";
+        let (value, comments) = parse_json_with_comments(input).unwrap();
+        let output = to_string_with_comments(&value, &comments).unwrap();
+        // Only the trailing comment, which is not filed under a member, is lost.
+        assert_eq!(output, input.replace(" // trailing", ""));
+        assert_eq!(
+            parse_json_with_comments(&output).unwrap(),
+            (value, comments)
+        );
+    }
+
+    #[test]
+    fn to_string_with_comments_files_comments_under_nested_pointers() {
+        let input = "[{\"a/b\": [{\n  // note\n  \"c\": 1}]}]";
+        let (value, comments) = parse_json_with_comments(input).unwrap();
+        assert_eq!(comments.keys().collect::<Vec<_>>(), ["/0/a~1b/0/c"]);
+        let output = to_string_with_comments(&value, &comments).unwrap();
+        assert!(
+            output.contains("        // note\n        \"c\": 1"),
+            "{output}"
+        );
+        assert_eq!(parse_json_with_comments(&output).unwrap().1, comments);
+        assert_eq!(
+            to_string_with_comments(&value, &CommentMap::new()),
+            to_string_pretty(&value)
+        );
+    }
+
+    #[test]
+    fn summary_writes_bare_keys_on_one_line() {
+        let value = parse_json(r#"{"name": "John", "age": 30, "tags": ["a", null, 1.5], "x": {}}"#)
+            .unwrap();
+        assert_eq!(
+            value.summary(100),
+            r#"{name: "John", age: 30, tags: ["a", null, 1.5], x: {}}"#
+        );
+        assert_eq!(JsonValue::from("q\"").summary(10), r#""q\"""#);
+    }
+
+    #[test]
+    fn summary_cuts_long_output_within_max_len() {
+        let value = parse_json(r#"{"name": "John", "age": 30}"#).unwrap();
+        assert_eq!(value.summary(12), "{name: \"J...");
+        assert_eq!(value.summary(2), "..");
+        assert_eq!(value.summary(0), "");
+        let wide = JsonValue::from("\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}");
+        assert_eq!(wide.summary(6), "\"\u{e9}\u{e9}...");
+        let long = JsonValue::Array((0..10_000).map(JsonValue::Integer).collect());
+        assert_eq!(long.summary(10), "[0, 1, ...");
+    }
+
+    #[test]
+    fn to_vec_matches_to_string_bytes() {
+        let value = parse_json(r#"{"name": "caf\u00e9", "n": [1, 2.5, null]}"#).unwrap();
+        let bytes = to_vec(&value).unwrap();
+        assert_eq!(bytes, to_string(&value).unwrap().into_bytes());
+        assert_eq!(
+            bytes,
+            "{\"name\":\"caf\u{e9}\",\"n\":[1,2.5,null]}".as_bytes()
+        );
+        assert_eq!(parse_json_bytes(&bytes), Ok(value));
+    }
+
+    #[test]
+    fn to_vec_reports_serialize_errors() {
+        assert!(matches!(
+            to_vec(&JsonValue::Number(f64::NAN)),
+            Err(SerializeError::NonFiniteNumber { .. })
+        ));
+        assert!(matches!(
+            to_vec(&nested_arrays(DEFAULT_MAX_DEPTH + 1)),
+            Err(SerializeError::DepthLimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn reformat_pretty_prints_and_keeps_numbers_exact() {
+        let input = br#"{"b":[1.0,1e3,12345678901234567890],"a":{"x":null}}"#;
+        let mut output = Vec::new();
+        reformat(&input[..], &mut output, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\n  \"b\": [\n    1.0,\n    1e3,\n    12345678901234567890\n  ],\n  \"a\": {\n    \"x\": null\n  }\n}\n"
+        );
+        let mut output = Vec::new();
+        reformat(&b"\xEF\xBB\xBF[1]"[..], &mut output, 4).unwrap();
+        assert_eq!(output, b"[\n    1\n]\n");
+    }
+
+    #[test]
+    fn reformat_reports_parse_errors_and_writes_nothing() {
+        let mut output = Vec::new();
+        assert!(matches!(
+            reformat(&b"[1, 2"[..], &mut output, 2),
+            Err(ParseError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            reformat(&b"[\"\xFF\"]"[..], &mut output, 2),
+            Err(ParseError::InvalidUtf8 { .. })
+        ));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn max_width_puts_containers_that_fit_on_one_line() {
+        let input = r#"{"short": [1, 2], "obj": {"a": 1, "b": "x"}, "long": [1000000, 2000000, 3000000, 4000000]}"#;
+        assert_eq!(
+            render(input, &SerializerOptions::pretty().max_width(Some(20))),
+            "{\n  \"short\": [1, 2],\n  \"obj\": {\n    \"a\": 1,\n    \"b\": \"x\"\n  },\n  \"long\": [\n    1000000,\n    2000000,\n    3000000,\n    4000000\n  ]\n}"
+        );
+        assert_eq!(
+            render(input, &SerializerOptions::pretty().max_width(Some(80))),
+            "{\n  \"short\": [1, 2],\n  \"obj\": {\"a\": 1, \"b\": \"x\"},\n  \"long\": [1000000, 2000000, 3000000, 4000000]\n}"
+        );
+    }
+
+    #[test]
+    fn max_width_counts_the_indent_and_key_before_the_container() {
+        // `  "short": [1, 2]` is 17 characters; the trailing comma is not counted.
+        let input = r#"{"short": [1, 2], "b": 1}"#;
+        assert_eq!(
+            render(input, &SerializerOptions::pretty().max_width(Some(17))),
+            "{\n  \"short\": [1, 2],\n  \"b\": 1\n}"
+        );
+        assert_eq!(
+            render(input, &SerializerOptions::pretty().max_width(Some(16))),
+            render(input, &SerializerOptions::pretty())
+        );
+        // Compact output is already on one line.
+        assert_eq!(
+            render(input, &SerializerOptions::default().max_width(Some(5))),
+            r#"{"short":[1,2],"b":1}"#
+        );
+    }
+
+    #[test]
+    fn prettify_stream_matches_reformat() {
+        let inputs: [&[u8]; 8] = [
+            br#"{"b":[1.0,1e3,12345678901234567890],"a":{"x":null}}"#,
+            br#"[[], {}, [[]], {"k": {}}, "s\u00e9\n"]"#,
+            b"\xEF\xBB\xBF [true, false, -0, 1E-2] ",
+            br#"{"a/b": {"c": [1, {"d": [2, 3]}]}, "e": "\""}"#,
+            b"42",
+            b"\"text\"",
+            b"null",
+            b"\xFF\xFE[\x001\x00,\x00\"\x00\xE9\x00\"\x00]\x00",
+        ];
+        for input in inputs {
+            for indent in [0, 2, 4] {
+                let (mut tree, mut stream) = (Vec::new(), Vec::new());
+                reformat(input, &mut tree, indent).unwrap();
+                prettify_stream(input, &mut stream, indent).unwrap();
+                assert_eq!(
+                    String::from_utf8(stream).unwrap(),
+                    String::from_utf8(tree).unwrap(),
+                    "input {:?}, indent {}",
+                    String::from_utf8_lossy(input),
+                    indent
+                );
+            }
+        }
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk on fire"))
+        }
+    }
+
+    #[test]
+    fn prettify_stream_leaves_partial_output_on_errors() {
+        let mut output = Vec::new();
+        assert_eq!(
+            prettify_stream(&b"[1, 2, x]"[..], &mut output, 2),
+            Err(ParseError::Syntax {
+                message: "Unexpected character: x".into(),
+                line: 1,
+                column: 8
+            })
+        );
+        assert_eq!(output, b"[\n  1,\n  2,\n  ");
+
+        let mut output = Vec::new();
+        assert_eq!(
+            prettify_stream(&b"[1, 2"[..], &mut output, 2),
+            Err(ParseError::UnexpectedEof)
+        );
+        assert_eq!(output, b"[\n  1,\n  2");
+
+        // Output is written before the rest of the input has been read.
+        let failing = (&b"[1, 2, "[..]).chain(FailingReader);
+        let mut output = Vec::new();
+        assert!(matches!(
+            prettify_stream(io::BufReader::new(failing), &mut output, 2),
+            Err(ParseError::Io { .. })
+        ));
+        assert_eq!(output, b"[\n  1,\n  2,\n  ");
+
+        // Invalid UTF-8 is found as it is read.
+        let mut output = Vec::new();
+        assert_eq!(
+            prettify_stream(&b"[\"\xFF\"]"[..], &mut output, 2),
+            Err(ParseError::InvalidUtf8 { offset: 2 })
+        );
+        assert_eq!(output, b"[");
+    }
+
+    #[test]
+    fn prettify_stream_matches_reformat_on_a_large_array() {
+        let mut input = String::from("[");
+        for i in 0..20_000 {
+            if i > 0 {
+                input.push_str(", ");
+            }
+            match i % 4 {
+                0 => input.push_str(&format!("{}", i)),
+                1 => input.push_str(&format!("{}.5e-3", i)),
+                2 => input.push_str(&format!(r#""caf\u00e9 \\ {}""#, i)),
+                _ => input.push_str(&format!(r#"{{"id": {}, "tags": [true, null]}}"#, i)),
+            }
+        }
+        input.push(']');
+        let (mut tree, mut stream) = (Vec::new(), Vec::new());
+        reformat(input.as_bytes(), &mut tree, 2).unwrap();
+        // A one-byte buffer splits every multi-byte character across reads.
+        let reader = io::BufReader::with_capacity(1, input.as_bytes());
+        prettify_stream(reader, &mut stream, 2).unwrap();
+        assert!(stream == tree);
+    }
+
+    #[test]
+    fn prettify_stream_reports_lexical_errors_where_reformat_does() {
+        // Each lexeme is tokenized alone, so positions must be carried over.
+        for input in [
+            "[tru]",
+            "[1, 2}",
+            "[\n  1.]",
+            "{\"a\": [1,\n \"b\\q\"]}",
+            "[\n\"abc",
+            "[1] 2",
+            "  ",
+            "[1, x]",
+            "[1,\n \u{feff}2]",
+            "[\"\\u12\"]",
+        ] {
+            let (mut tree, mut stream) = (Vec::new(), Vec::new());
+            let expected = reformat(input.as_bytes(), &mut tree, 2);
+            assert!(expected.is_err(), "{:?}", input);
+            assert_eq!(
+                prettify_stream(input.as_bytes(), &mut stream, 2),
+                expected,
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn integers_without_decimal_controls_whole_valued_floats() {
+        let value = parse_json("[1.0, -30.0, 0.0, 2.5, 7]").unwrap();
+        assert!(SerializerOptions::default().integers_without_decimal);
+        assert_eq!(to_string(&value).unwrap(), "[1,-30,0,2.5,7]");
+        let options = SerializerOptions::default().integers_without_decimal(false);
+        assert_eq!(
+            to_string_with_options(&value, &options).unwrap(),
+            "[1.0,-30.0,0.0,2.5,7]"
+        );
+        // The decimal point survives a round trip, so the float stays a float.
+        let written = to_string_with_options(&JsonValue::Number(1.0), &options).unwrap();
+        assert_eq!(written, "1.0");
+        assert_eq!(parse_json(&written), Ok(JsonValue::Number(1.0)));
+        // `float_precision` takes over when set.
+        let fixed = options.float_precision(Some(2));
+        assert_eq!(
+            to_string_with_options(&JsonValue::Number(1.0), &fixed).unwrap(),
+            "1.00"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::error::ParseError;
+use super::parser::parse_json;
+use super::pointer::{parse_index, parse_pointer};
+use super::value::{integer_eq_f64, JsonValue};
+
+/// A [`JsonValue`] counterpart whose strings and containers live behind `Rc`,
+/// so cloning any subtree is cheap and shares the underlying allocations.
+/// Equality follows [`JsonValue`]'s, with `Integer(5) == Number(5.0)`.
+#[derive(Debug, Clone)]
+pub enum SharedValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    RawNumber(Rc<str>),
+    String(Rc<str>),
+    Array(Rc<Vec<SharedValue>>),
+    Object(Rc<Vec<(Rc<str>, SharedValue)>>),
+}
+
+impl PartialEq for SharedValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SharedValue::Null, SharedValue::Null) => true,
+            (SharedValue::Boolean(a), SharedValue::Boolean(b)) => a == b,
+            (SharedValue::Integer(a), SharedValue::Integer(b)) => a == b,
+            (SharedValue::Number(a), SharedValue::Number(b)) => a == b,
+            (SharedValue::Integer(i), SharedValue::Number(n))
+            | (SharedValue::Number(n), SharedValue::Integer(i)) => integer_eq_f64(*i, *n),
+            (SharedValue::RawNumber(a), SharedValue::RawNumber(b)) => a == b,
+            (SharedValue::String(a), SharedValue::String(b)) => a == b,
+            (SharedValue::Array(a), SharedValue::Array(b)) => a == b,
+            (SharedValue::Object(a), SharedValue::Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl SharedValue {
+    /// Returns a copy of `self` with the value at `pointer` replaced by `value`.
+    /// A missing final object key is appended. Only the containers along the
+    /// pointer are copied; every other subtree is shared with `self`. Returns
+    /// `None` if the pointer does not lead to an existing parent.
+    pub fn update_at(&self, pointer: &str, value: SharedValue) -> Option<SharedValue> {
+        self.update_tokens(&parse_pointer(pointer)?, value)
+    }
+
+    fn update_tokens(&self, tokens: &[String], value: SharedValue) -> Option<SharedValue> {
+        let Some((token, rest)) = tokens.split_first() else {
+            return Some(value);
+        };
+        match self {
+            SharedValue::Object(object) => {
+                let mut object = Vec::clone(object);
+                match object.iter_mut().find(|(k, _)| **k == **token) {
+                    Some((_, child)) => *child = child.update_tokens(rest, value)?,
+                    None if rest.is_empty() => object.push((Rc::from(token.as_str()), value)),
+                    None => return None,
+                }
+                Some(SharedValue::Object(Rc::new(object)))
+            }
+            SharedValue::Array(array) => {
+                let mut array = Vec::clone(array);
+                let child = array.get_mut(parse_index(token)?)?;
+                *child = child.update_tokens(rest, value)?;
+                Some(SharedValue::Array(Rc::new(array)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<&JsonValue> for SharedValue {
+    fn from(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::Null => SharedValue::Null,
+            JsonValue::Boolean(b) => SharedValue::Boolean(*b),
+            JsonValue::Integer(i) => SharedValue::Integer(*i),
+            JsonValue::Number(n) => SharedValue::Number(*n),
+            JsonValue::RawNumber(raw) => SharedValue::RawNumber(Rc::from(raw.as_str())),
+            JsonValue::String(s) => SharedValue::String(Rc::from(s.as_str())),
+            JsonValue::Array(array) => {
+                SharedValue::Array(Rc::new(array.iter().map(SharedValue::from).collect()))
+            }
+            JsonValue::Object(object) => SharedValue::Object(Rc::new(
+                object
+                    .iter()
+                    .map(|(k, v)| (Rc::from(k.as_str()), SharedValue::from(v)))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+impl From<&SharedValue> for JsonValue {
+    fn from(value: &SharedValue) -> Self {
+        match value {
+            SharedValue::Null => JsonValue::Null,
+            SharedValue::Boolean(b) => JsonValue::Boolean(*b),
+            SharedValue::Integer(i) => JsonValue::Integer(*i),
+            SharedValue::Number(n) => JsonValue::Number(*n),
+            SharedValue::RawNumber(raw) => JsonValue::RawNumber(raw.to_string()),
+            SharedValue::String(s) => JsonValue::String(s.to_string()),
+            SharedValue::Array(array) => {
+                JsonValue::Array(array.iter().map(JsonValue::from).collect())
+            }
+            SharedValue::Object(object) => JsonValue::Object(
+                object
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), JsonValue::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+pub fn parse_json_shared(input: &str) -> Result<SharedValue, ParseError> {
+    parse_json(input).map(|value| SharedValue::from(&value))
+}
+
+/// Like [`parse_json_shared`], but string values that occur at least
+/// `min_repeats` times share a single `Rc<str>`, so enum-like fields such as
+/// a `status` repeated across records are stored once. Keys and rarer values
+/// get their own allocation as usual; the resulting value is the same.
+pub fn parse_json_shared_interned(
+    input: &str,
+    min_repeats: usize,
+) -> Result<SharedValue, ParseError> {
+    let value = parse_json(input)?;
+    let mut counts = HashMap::new();
+    count_strings(&value, &mut counts);
+    let interned: HashMap<&str, Rc<str>> = counts
+        .into_iter()
+        .filter(|&(_, count)| count >= min_repeats)
+        .map(|(s, _)| (s, Rc::from(s)))
+        .collect();
+    Ok(to_interned(&value, &interned))
+}
+
+fn count_strings<'a>(value: &'a JsonValue, counts: &mut HashMap<&'a str, usize>) {
+    match value {
+        JsonValue::String(s) => *counts.entry(s.as_str()).or_default() += 1,
+        JsonValue::Array(array) => array.iter().for_each(|v| count_strings(v, counts)),
+        JsonValue::Object(object) => object.iter().for_each(|(_, v)| count_strings(v, counts)),
+        _ => {}
+    }
+}
+
+fn to_interned(value: &JsonValue, interned: &HashMap<&str, Rc<str>>) -> SharedValue {
+    match value {
+        JsonValue::String(s) => match interned.get(s.as_str()) {
+            Some(shared) => SharedValue::String(Rc::clone(shared)),
+            None => SharedValue::String(Rc::from(s.as_str())),
+        },
+        JsonValue::Array(array) => SharedValue::Array(Rc::new(
+            array.iter().map(|v| to_interned(v, interned)).collect(),
+        )),
+        JsonValue::Object(object) => SharedValue::Object(Rc::new(
+            object
+                .iter()
+                .map(|(k, v)| (Rc::from(k.as_str()), to_interned(v, interned)))
+                .collect(),
+        )),
+        scalar => SharedValue::from(scalar),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_value_round_trips_and_clones_share_storage() {
+        let input = r#"{"name": "Ann", "tags": ["a", "b"], "n": 1.5, "i": 2, "x": null}"#;
+        let shared = parse_json_shared(input).unwrap();
+        assert_eq!(JsonValue::from(&shared), parse_json(input).unwrap());
+
+        let copy = shared.clone();
+        match (&shared, &copy) {
+            (SharedValue::Object(a), SharedValue::Object(b)) => assert!(Rc::ptr_eq(a, b)),
+            _ => panic!("expected objects"),
+        }
+        assert!(parse_json_shared("[1,").is_err());
+    }
+
+    fn object_members(value: &SharedValue) -> &[(Rc<str>, SharedValue)] {
+        match value {
+            SharedValue::Object(object) => object,
+            _ => panic!("expected an object"),
+        }
+    }
+
+    #[test]
+    fn update_at_copies_only_the_path_and_shares_siblings() {
+        let doc =
+            parse_json_shared(r#"{"a": {"x": [1, 2]}, "b": {"big": [3]}, "c": "s"}"#).unwrap();
+        let updated = doc.update_at("/a/x/1", SharedValue::Integer(20)).unwrap();
+        assert_eq!(
+            JsonValue::from(&updated),
+            parse_json(r#"{"a": {"x": [1, 20]}, "b": {"big": [3]}, "c": "s"}"#).unwrap()
+        );
+        let (before, after) = (object_members(&doc), object_members(&updated));
+        match (&before[1].1, &after[1].1) {
+            (SharedValue::Object(b), SharedValue::Object(a)) => assert!(Rc::ptr_eq(b, a)),
+            _ => panic!("expected objects"),
+        }
+        match (&before[2].1, &after[2].1) {
+            (SharedValue::String(b), SharedValue::String(a)) => assert!(Rc::ptr_eq(b, a)),
+            _ => panic!("expected strings"),
+        }
+        assert_ne!(before[0].1, after[0].1);
+        // The original is untouched.
+        assert_eq!(
+            JsonValue::from(&doc),
+            parse_json(r#"{"a": {"x": [1, 2]}, "b": {"big": [3]}, "c": "s"}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn update_at_appends_keys_but_not_missing_parents() {
+        let doc = parse_json_shared(r#"{"a": [1]}"#).unwrap();
+        let added = doc.update_at("/b", SharedValue::Null).unwrap();
+        assert_eq!(
+            JsonValue::from(&added),
+            parse_json(r#"{"a": [1], "b": null}"#).unwrap()
+        );
+        assert_eq!(doc.update_at("/x/y", SharedValue::Null), None);
+        assert_eq!(doc.update_at("/a/1", SharedValue::Null), None);
+        assert_eq!(doc.update_at("/a/0/z", SharedValue::Null), None);
+        assert_eq!(doc.update_at("a", SharedValue::Null), None);
+        assert_eq!(
+            doc.update_at("", SharedValue::Null),
+            Some(SharedValue::Null)
+        );
+    }
+
+    fn status_strings(value: &SharedValue) -> Vec<Rc<str>> {
+        match value {
+            SharedValue::Array(records) => records
+                .iter()
+                .map(|record| match &object_members(record)[0].1 {
+                    SharedValue::String(s) => Rc::clone(s),
+                    _ => panic!("expected a string"),
+                })
+                .collect(),
+            _ => panic!("expected an array"),
+        }
+    }
+
+    #[test]
+    fn interned_strings_share_one_rc() {
+        let input = r#"[{"status": "active"}, {"status": "closed"}, {"status": "active"}, {"status": "active"}]"#;
+        let shared = parse_json_shared_interned(input, 2).unwrap();
+        assert_eq!(JsonValue::from(&shared), parse_json(input).unwrap());
+        let statuses = status_strings(&shared);
+        assert!(Rc::ptr_eq(&statuses[0], &statuses[2]));
+        assert!(Rc::ptr_eq(&statuses[0], &statuses[3]));
+        assert!(!Rc::ptr_eq(&statuses[0], &statuses[1]));
+        // Held by the three records and by the three clones in `statuses`.
+        assert_eq!(Rc::strong_count(&statuses[0]), 6);
+    }
+
+    #[test]
+    fn strings_below_min_repeats_are_not_interned() {
+        let input = r#"[{"status": "active"}, {"status": "active"}]"#;
+        let statuses = status_strings(&parse_json_shared_interned(input, 3).unwrap());
+        assert!(!Rc::ptr_eq(&statuses[0], &statuses[1]));
+        let plain = status_strings(&parse_json_shared(input).unwrap());
+        assert!(!Rc::ptr_eq(&plain[0], &plain[1]));
+        assert!(parse_json_shared_interned("[", 1).is_err());
+    }
+
+    #[test]
+    fn shared_numbers_compare_like_json_values() {
+        let integer = parse_json_shared("[5]").unwrap();
+        assert_eq!(integer, parse_json_shared("[5.0]").unwrap());
+        assert_ne!(integer, parse_json_shared("[5.5]").unwrap());
+        assert_ne!(SharedValue::Integer(5), SharedValue::RawNumber("5".into()));
+    }
+}
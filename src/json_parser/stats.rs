@@ -0,0 +1,191 @@
+use super::error::ParseError;
+use super::parser::parse_json;
+use super::value::JsonValue;
+
+/// Counts describing the shape of a parsed document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseStats {
+    pub objects: usize,
+    pub arrays: usize,
+    /// String values; object keys are not counted.
+    pub strings: usize,
+    pub numbers: usize,
+    pub booleans: usize,
+    pub nulls: usize,
+    /// Deepest nesting of arrays and objects; a scalar document has depth 0.
+    pub max_depth: usize,
+    /// Length of the input in bytes.
+    pub bytes: usize,
+}
+
+impl ParseStats {
+    fn record(&mut self, value: &JsonValue, depth: usize) {
+        match value {
+            JsonValue::Null => self.nulls += 1,
+            JsonValue::Boolean(_) => self.booleans += 1,
+            JsonValue::Integer(_) | JsonValue::Number(_) | JsonValue::RawNumber(_) => {
+                self.numbers += 1
+            }
+            JsonValue::String(_) => self.strings += 1,
+            JsonValue::Array(array) => {
+                self.arrays += 1;
+                self.max_depth = self.max_depth.max(depth + 1);
+                array.iter().for_each(|v| self.record(v, depth + 1));
+            }
+            JsonValue::Object(object) => {
+                self.objects += 1;
+                self.max_depth = self.max_depth.max(depth + 1);
+                object.iter().for_each(|(_, v)| self.record(v, depth + 1));
+            }
+        }
+    }
+}
+
+/// Totals over the string values of a document, as returned by
+/// [`JsonValue::text_stats`]. Object keys are not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextStats {
+    pub strings: usize,
+    /// Combined UTF-8 length of the strings.
+    pub bytes: usize,
+    /// Combined length of the strings in `char`s.
+    pub chars: usize,
+}
+
+/// Parses `input` and also reports [`ParseStats`] for it, e.g. to profile the
+/// documents a service receives.
+pub fn parse_json_stats(input: &str) -> Result<(JsonValue, ParseStats), ParseError> {
+    let value = parse_json(input)?;
+    let mut stats = ParseStats {
+        bytes: input.len(),
+        ..ParseStats::default()
+    };
+    stats.record(&value, 0);
+    Ok((value, stats))
+}
+
+impl JsonValue {
+    /// Sums the sizes of every string value in the tree, e.g. to estimate the
+    /// cost of sending its text to an NLP pipeline.
+    pub fn text_stats(&self) -> TextStats {
+        let mut stats = TextStats::default();
+        self.walk(|value| {
+            if let JsonValue::String(s) = value {
+                stats.strings += 1;
+                stats.bytes += s.len();
+                stats.chars += s.chars().count();
+            }
+        });
+        stats
+    }
+
+    /// An estimate of the heap bytes owned by the tree: the capacity of every
+    /// string, key and container buffer, counted recursively. The value's own
+    /// inline size and allocator overhead are not included, so treat the
+    /// result as a lower bound for comparing documents, not an exact figure.
+    pub fn memory_size(&self) -> usize {
+        match self {
+            JsonValue::String(s) | JsonValue::RawNumber(s) => s.capacity(),
+            JsonValue::Array(array) => {
+                array.capacity() * size_of::<JsonValue>()
+                    + array.iter().map(JsonValue::memory_size).sum::<usize>()
+            }
+            JsonValue::Object(object) => {
+                object.capacity() * size_of::<(String, JsonValue)>()
+                    + object
+                        .iter()
+                        .map(|(key, value)| key.capacity() + value.memory_size())
+                        .sum::<usize>()
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_json_stats_counts_values_depth_and_bytes() {
+        let input = r#"{"a": [1, 2.5, "x"], "b": {"c": [true, null, []]}, "k": "v"}"#;
+        let (value, stats) = parse_json_stats(input).unwrap();
+        assert_eq!(value, parse_json(input).unwrap());
+        assert_eq!(
+            stats,
+            ParseStats {
+                objects: 2,
+                arrays: 3,
+                strings: 2,
+                numbers: 2,
+                booleans: 1,
+                nulls: 1,
+                max_depth: 4,
+                bytes: input.len(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_json_stats_handles_scalars_and_errors() {
+        let (_, stats) = parse_json_stats(" 42 ").unwrap();
+        assert_eq!(
+            stats,
+            ParseStats {
+                numbers: 1,
+                bytes: 4,
+                ..ParseStats::default()
+            }
+        );
+        assert!(parse_json_stats("[1,").is_err());
+    }
+
+    #[test]
+    fn memory_size_counts_heap_buffers_recursively() {
+        assert_eq!(JsonValue::Null.memory_size(), 0);
+        assert_eq!(JsonValue::Integer(7).memory_size(), 0);
+        let s = String::with_capacity(32);
+        assert_eq!(JsonValue::String(s).memory_size(), 32);
+
+        let mut items = Vec::with_capacity(4);
+        items.push(JsonValue::String(String::with_capacity(10)));
+        let array = JsonValue::Array(items);
+        assert_eq!(array.memory_size(), 4 * size_of::<JsonValue>() + 10);
+
+        let array_size = array.memory_size();
+        let mut members = Vec::with_capacity(2);
+        members.push((String::with_capacity(5), array));
+        let object = JsonValue::Object(members);
+        assert_eq!(
+            object.memory_size(),
+            2 * size_of::<(String, JsonValue)>() + 5 + array_size
+        );
+    }
+
+    #[test]
+    fn memory_size_grows_with_the_document() {
+        let small = parse_json(r#"{"a": "x"}"#).unwrap();
+        let large =
+            parse_json(r#"{"a": "x", "b": ["a long string value", {"c": [1, 2, 3]}]}"#).unwrap();
+        assert!(small.memory_size() > 0);
+        assert!(large.memory_size() > small.memory_size());
+    }
+
+    #[test]
+    fn text_stats_sums_string_values_but_not_keys() {
+        let value =
+            parse_json(r#"{"long key": "héllo", "n": [1, "", {"k": "😀"}], "raw": null}"#).unwrap();
+        assert_eq!(
+            value.text_stats(),
+            TextStats {
+                strings: 3,
+                bytes: 6 + 4,
+                chars: 5 + 1,
+            }
+        );
+        assert_eq!(
+            parse_json("[1, true]").unwrap().text_stats(),
+            TextStats::default()
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt::Write;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use super::value::JsonValue;
+
+/// Generates a value together with one well-formed JSON text that parses to
+/// it, for stress-testing the parser. The text varies in ways the serializer
+/// never produces: random whitespace between tokens, strings mixing raw
+/// characters with short and `\uXXXX` escapes (including surrogate pairs), and
+/// floats written in both plain and exponent form. Whole-valued floats may
+/// parse back as `Integer`s, which compare equal to them, so
+/// `parse_json(&text) == Ok(value)` holds; whole floats between 2^53 and 2^63
+/// are left out, since their shortest text names a neighbouring integer.
+pub fn json_strategy() -> impl Strategy<Value = (JsonValue, String)> {
+    (whitespace(), value_strategy(), whitespace())
+        .prop_map(|(before, (value, text), after)| (value, before + &text + &after))
+}
+
+fn value_strategy() -> impl Strategy<Value = (JsonValue, String)> {
+    let leaf = prop_oneof![
+        Just((JsonValue::Null, "null".to_string())),
+        any::<bool>().prop_map(|b| (JsonValue::Boolean(b), b.to_string())),
+        any::<i64>().prop_map(|i| (JsonValue::Integer(i), i.to_string())),
+        (any::<f64>(), any::<bool>())
+            .prop_filter("needs a float that reads back as itself", |(n, _)| {
+                round_trips(*n)
+            })
+            .prop_map(|(n, exponent)| {
+                let text = if exponent {
+                    format!("{:e}", n)
+                } else {
+                    n.to_string()
+                };
+                (JsonValue::Number(n), text)
+            }),
+        string_strategy().prop_map(|(s, text)| (JsonValue::String(s), text)),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            (
+                vec((whitespace(), inner.clone(), whitespace()), 0..8),
+                whitespace()
+            )
+                .prop_map(|(elements, empty)| {
+                    let values = elements.iter().map(|(_, (v, _), _)| v.clone()).collect();
+                    let items: Vec<_> = elements
+                        .into_iter()
+                        .map(|(before, (_, text), after)| before + &text + &after)
+                        .collect();
+                    (JsonValue::Array(values), container('[', items, empty, ']'))
+                }),
+            (
+                vec(
+                    (
+                        (whitespace(), string_strategy(), whitespace()),
+                        (whitespace(), inner, whitespace()),
+                    ),
+                    0..8
+                ),
+                whitespace()
+            )
+                .prop_map(|(members, empty)| {
+                    let entries = members
+                        .iter()
+                        .map(|((_, (key, _), _), (_, (v, _), _))| (key.clone(), v.clone()))
+                        .collect();
+                    let items: Vec<_> = members
+                        .into_iter()
+                        .map(|((a, (_, key), b), (c, (_, value), d))| {
+                            a + &key + &b + ":" + &c + &value + &d
+                        })
+                        .collect();
+                    (
+                        JsonValue::Object(entries),
+                        container('{', items, empty, '}'),
+                    )
+                }),
+        ]
+    })
+}
+
+fn container(open: char, items: Vec<String>, empty: String, close: char) -> String {
+    let inner = if items.is_empty() {
+        empty
+    } else {
+        items.join(",")
+    };
+    format!("{}{}{}", open, inner, close)
+}
+
+// Whether `n` reads back as itself, or as an `Integer` equal to it.
+fn round_trips(n: f64) -> bool {
+    n.is_finite() && (n.fract() != 0.0 || n.abs() <= 2f64.powi(53) || n.abs() >= 2f64.powi(63))
+}
+
+fn whitespace() -> impl Strategy<Value = String> {
+    "[ \t\n\r]{0,3}"
+}
+
+// A string and a quoted rendering of it, choosing per character between the
+// raw character (where JSON allows one), its short escape and `\uXXXX`.
+fn string_strategy() -> impl Strategy<Value = (String, String)> {
+    vec((any::<char>(), 0u8..3), 0..12).prop_map(|chars| {
+        let mut value = String::new();
+        let mut text = String::from("\"");
+        for (c, style) in chars {
+            value.push(c);
+            let raw = c >= ' ' && c != '"' && c != '\\';
+            let short = match c {
+                '"' => Some("\\\""),
+                '\\' => Some("\\\\"),
+                '/' => Some("\\/"),
+                '\u{8}' => Some("\\b"),
+                '\u{c}' => Some("\\f"),
+                '\n' => Some("\\n"),
+                '\r' => Some("\\r"),
+                '\t' => Some("\\t"),
+                _ => None,
+            };
+            match (style, short) {
+                (0, _) if raw => text.push(c),
+                (0 | 1, Some(escape)) => text.push_str(escape),
+                (1, None) if raw => text.push(c),
+                _ => {
+                    let mut units = [0; 2];
+                    for unit in c.encode_utf16(&mut units) {
+                        write!(text, "\\u{:04x}", unit).unwrap();
+                    }
+                }
+            }
+        }
+        text.push('"');
+        (value, text)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+    use crate::json_parser::serializer::{to_string, to_string_pretty};
+
+    proptest! {
+        #[test]
+        fn generated_text_parses_to_its_value((value, text) in json_strategy()) {
+            prop_assert_eq!(parse_json(&text), Ok(value));
+        }
+
+        #[test]
+        fn generated_values_serialize_and_reparse_equal((value, _) in json_strategy()) {
+            prop_assert_eq!(parse_json(&to_string(&value).unwrap()), Ok(value.clone()));
+            prop_assert_eq!(parse_json(&to_string_pretty(&value).unwrap()), Ok(value));
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use super::error::ParseError;
+use super::serializer::{to_string_with_options, NonFinitePolicy, SerializerOptions};
+use super::value::JsonValue;
+
+// The members of each element of an array of objects.
+fn records(value: &JsonValue) -> Result<Vec<&[(String, JsonValue)]>, ParseError> {
+    let records = value.as_array().ok_or_else(|| ParseError::WrongType {
+        key: String::new(),
+        expected: "an array",
+    })?;
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            record.as_object().ok_or_else(|| ParseError::WrongType {
+                key: i.to_string(),
+                expected: "an object",
+            })
+        })
+        .collect()
+}
+
+/// Lays out an array of objects as a table. The headers are every key used by
+/// any record, in order of first appearance; each row holds one record's
+/// values in header order, with `Null` for keys the record lacks.
+///
+/// Fails with `ParseError::WrongType` if `value` is not an array, or naming
+/// the index of the first element that is not an object.
+pub fn to_table(value: &JsonValue) -> Result<(Vec<String>, Vec<Vec<JsonValue>>), ParseError> {
+    let records = records(value)?;
+
+    let mut headers: Vec<String> = Vec::new();
+    for (key, _) in records.iter().copied().flatten() {
+        if !headers.contains(key) {
+            headers.push(key.clone());
+        }
+    }
+    let rows = records
+        .iter()
+        .map(|record| {
+            headers
+                .iter()
+                .map(|header| {
+                    record
+                        .iter()
+                        .find(|(k, _)| k == header)
+                        .map_or(JsonValue::Null, |(_, v)| v.clone())
+                })
+                .collect()
+        })
+        .collect();
+    Ok((headers, rows))
+}
+
+/// Groups an array of objects by the value of their `key` member, returning
+/// an object from each distinct value to the array of records that have it.
+/// Strings are used as they are and other values as their compact JSON text,
+/// so `"30"` and `30` share a group; records without `key` are grouped under
+/// `"null"`. Groups appear in order of first occurrence.
+///
+/// Fails like [`to_table`] if `array` is not an array of objects.
+pub fn group_by(array: &JsonValue, key: &str) -> Result<JsonValue, ParseError> {
+    let mut groups: Vec<(String, Vec<JsonValue>)> = Vec::new();
+    for (record, members) in array
+        .as_array()
+        .unwrap_or_default()
+        .iter()
+        .zip(records(array)?)
+    {
+        let group = match members.iter().find(|(k, _)| k == key).map(|(_, v)| v) {
+            Some(JsonValue::String(s)) => s.clone(),
+            // Only values nested past the serializer's depth limit fail here.
+            Some(value) => to_string_with_options(
+                value,
+                &SerializerOptions::default().non_finite(NonFinitePolicy::Null),
+            )
+            .unwrap_or_default(),
+            None => "null".to_string(),
+        };
+        match groups.iter_mut().find(|(g, _)| *g == group) {
+            Some((_, records)) => records.push(record.clone()),
+            None => groups.push((group, vec![record.clone()])),
+        }
+    }
+    Ok(groups
+        .into_iter()
+        .map(|(group, records)| (group, JsonValue::Array(records)))
+        .collect())
+}
+
+/// Counts how many objects in an array of objects contain each key, most
+/// common first, to tell required fields from optional ones. Keys with equal
+/// counts stay in order of first appearance, and a key repeated within one
+/// object counts once for it.
+///
+/// Fails like [`to_table`] if `array` is not an array of objects.
+pub fn key_histogram(array: &JsonValue) -> Result<Vec<(String, usize)>, ParseError> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for members in records(array)? {
+        for (i, (key, _)) in members.iter().enumerate() {
+            if members[..i].iter().any(|(k, _)| k == key) {
+                continue;
+            }
+            match counts.iter_mut().find(|(k, _)| k == key) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((key.clone(), 1)),
+            }
+        }
+    }
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+    Ok(counts)
+}
+
+/// Transposes an array of row objects into an object of columns: each key
+/// used by any row, in order of first appearance, maps to the array of that
+/// key's values, one per row, with `null` for rows that lack it.
+///
+/// Fails like [`to_table`] if `value` is not an array of objects.
+pub fn to_columnar(value: &JsonValue) -> Result<JsonValue, ParseError> {
+    let (headers, rows) = to_table(value)?;
+    let mut columns: Vec<Vec<JsonValue>> = vec![Vec::with_capacity(rows.len()); headers.len()];
+    for row in rows {
+        for (column, cell) in columns.iter_mut().zip(row) {
+            column.push(cell);
+        }
+    }
+    Ok(headers
+        .into_iter()
+        .zip(columns)
+        .map(|(header, column)| (header, JsonValue::Array(column)))
+        .collect())
+}
+
+/// The inverse of [`to_columnar`]: turns an object of column arrays into an
+/// array of row objects with the columns' keys in order. Shorter columns are
+/// padded with `null`, and every row has every key, so cells that were
+/// missing before `to_columnar` come back as `null` members.
+///
+/// Fails with `ParseError::WrongType` if `value` is not an object, or naming
+/// the first column that is not an array.
+pub fn from_columnar(value: &JsonValue) -> Result<JsonValue, ParseError> {
+    let columns = value.as_object().ok_or_else(|| ParseError::WrongType {
+        key: String::new(),
+        expected: "an object",
+    })?;
+    let columns = columns
+        .iter()
+        .map(|(key, column)| {
+            column
+                .as_array()
+                .map(|cells| (key, cells))
+                .ok_or_else(|| ParseError::WrongType {
+                    key: key.clone(),
+                    expected: "an array",
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let len = columns
+        .iter()
+        .map(|(_, cells)| cells.len())
+        .max()
+        .unwrap_or(0);
+    Ok((0..len)
+        .map(|i| {
+            JsonValue::Object(
+                columns
+                    .iter()
+                    .map(|(key, cells)| {
+                        let cell = cells.get(i).cloned().unwrap_or(JsonValue::Null);
+                        (key.to_string(), cell)
+                    })
+                    .collect(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn to_table_unions_headers_and_fills_gaps_with_null() {
+        let (headers, rows) =
+            to_table(&json(r#"[{"a": 1, "b": 2}, {"c": 3, "a": 4}, {}]"#)).unwrap();
+        assert_eq!(headers, ["a", "b", "c"]);
+        assert_eq!(
+            rows,
+            [
+                vec![json("1"), json("2"), json("null")],
+                vec![json("4"), json("null"), json("3")],
+                vec![json("null"), json("null"), json("null")],
+            ]
+        );
+        assert_eq!(to_table(&json("[]")), Ok((vec![], vec![])));
+    }
+
+    #[test]
+    fn to_table_rejects_non_tabular_values() {
+        assert_eq!(
+            to_table(&json(r#"{"a": 1}"#)),
+            Err(ParseError::WrongType {
+                key: String::new(),
+                expected: "an array"
+            })
+        );
+        assert_eq!(
+            to_table(&json(r#"[{"a": 1}, [1]]"#)),
+            Err(ParseError::WrongType {
+                key: "1".into(),
+                expected: "an object"
+            })
+        );
+    }
+
+    #[test]
+    fn group_by_collects_records_in_first_seen_order() {
+        let rows = json(
+            r#"[{"team": "red", "n": 1}, {"team": "blue", "n": 2}, {"team": "red", "n": 3},
+                {"n": 4}, {"team": 30, "n": 5}, {"team": "30", "n": 6}, {"team": null, "n": 7}]"#,
+        );
+        assert_eq!(
+            group_by(&rows, "team"),
+            Ok(json(
+                r#"{"red": [{"team": "red", "n": 1}, {"team": "red", "n": 3}],
+                    "blue": [{"team": "blue", "n": 2}],
+                    "null": [{"n": 4}, {"team": null, "n": 7}],
+                    "30": [{"team": 30, "n": 5}, {"team": "30", "n": 6}]}"#
+            ))
+        );
+        assert_eq!(group_by(&json("[]"), "team"), Ok(json("{}")));
+    }
+
+    #[test]
+    fn group_by_rejects_non_tabular_values() {
+        assert_eq!(
+            group_by(&json(r#"[{"team": "red"}, 5]"#), "team"),
+            Err(ParseError::WrongType {
+                key: "1".into(),
+                expected: "an object"
+            })
+        );
+        assert!(group_by(&json(r#"{"team": "red"}"#), "team").is_err());
+    }
+
+    #[test]
+    fn key_histogram_counts_keys_most_common_first() {
+        let rows =
+            json(r#"[{"b": 1, "a": 1}, {"a": 2, "c": 3}, {"a": 3, "b": 4}, {"d": 5, "c": 6}]"#);
+        assert_eq!(
+            key_histogram(&rows),
+            Ok(vec![
+                ("a".to_string(), 3),
+                ("b".to_string(), 2),
+                ("c".to_string(), 2),
+                ("d".to_string(), 1),
+            ])
+        );
+        assert_eq!(key_histogram(&json("[]")), Ok(vec![]));
+    }
+
+    #[test]
+    fn key_histogram_counts_repeated_keys_once_per_record() {
+        let rows = vec![
+            JsonValue::Object(vec![
+                ("a".to_string(), JsonValue::Integer(1)),
+                ("a".to_string(), JsonValue::Integer(2)),
+            ]),
+            json(r#"{"a": 3}"#),
+        ];
+        assert_eq!(
+            key_histogram(&JsonValue::Array(rows)),
+            Ok(vec![("a".to_string(), 2)])
+        );
+        assert!(key_histogram(&json("[1]")).is_err());
+    }
+
+    #[test]
+    fn to_columnar_transposes_rows_and_from_columnar_restores_them() {
+        let rows = json(r#"[{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]"#);
+        let columns = to_columnar(&rows).unwrap();
+        assert_eq!(columns, json(r#"{"id": [1, 2], "name": ["a", "b"]}"#));
+        assert_eq!(from_columnar(&columns), Ok(rows));
+        assert_eq!(to_columnar(&json("[]")), Ok(json("{}")));
+        assert_eq!(from_columnar(&json("{}")), Ok(json("[]")));
+    }
+
+    #[test]
+    fn columnar_round_trip_fills_missing_cells_with_null() {
+        let rows = json(r#"[{"a": 1}, {"b": 2, "a": 3}]"#);
+        let columns = to_columnar(&rows).unwrap();
+        assert_eq!(columns, json(r#"{"a": [1, 3], "b": [null, 2]}"#));
+        assert_eq!(
+            from_columnar(&columns),
+            Ok(json(r#"[{"a": 1, "b": null}, {"a": 3, "b": 2}]"#))
+        );
+        // Ragged columns are padded too.
+        assert_eq!(
+            from_columnar(&json(r#"{"a": [1, 2], "b": [true]}"#)),
+            Ok(json(r#"[{"a": 1, "b": true}, {"a": 2, "b": null}]"#))
+        );
+    }
+
+    #[test]
+    fn columnar_conversions_reject_wrong_shapes() {
+        assert_eq!(
+            to_columnar(&json(r#"[{"a": 1}, 2]"#)),
+            Err(ParseError::WrongType {
+                key: "1".into(),
+                expected: "an object"
+            })
+        );
+        assert_eq!(
+            from_columnar(&json("[1]")),
+            Err(ParseError::WrongType {
+                key: String::new(),
+                expected: "an object"
+            })
+        );
+        assert_eq!(
+            from_columnar(&json(r#"{"a": [1], "b": 2, "c": 3}"#)),
+            Err(ParseError::WrongType {
+                key: "b".into(),
+                expected: "an array"
+            })
+        );
+    }
+}
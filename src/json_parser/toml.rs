@@ -0,0 +1,190 @@
+use std::fmt;
+
+use super::pointer::escape_token;
+use super::value::JsonValue;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertError {
+    /// TOML documents are tables, so only a JSON object can become one.
+    NotAnObject,
+    /// TOML has no null; the pointer says where one was found.
+    Null(String),
+    /// A `RawNumber` that does not fit TOML's 64-bit integers or floats.
+    UnsupportedNumber(String),
+    /// The TOML input could not be parsed.
+    Toml(String),
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::NotAnObject => write!(f, "Only an object can be written as TOML"),
+            ConvertError::Null(path) => write!(f, "TOML cannot represent null at {}", path),
+            ConvertError::UnsupportedNumber(path) => {
+                write!(f, "TOML cannot represent the number at {}", path)
+            }
+            ConvertError::Toml(message) => write!(f, "Invalid TOML: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {}
+
+/// Writes an object as a TOML document, with nested objects as tables. Key
+/// order is kept, except that TOML puts a table's plain keys before its
+/// subtables, so nested objects move after their scalar and array siblings.
+///
+/// TOML has no null, so a `null` anywhere is an error rather than being
+/// silently dropped.
+pub fn to_toml(value: &JsonValue) -> Result<String, ConvertError> {
+    let JsonValue::Object(object) = value else {
+        return Err(ConvertError::NotAnObject);
+    };
+    Ok(to_table(object, &mut String::new())?.to_string())
+}
+
+/// Parses a TOML document into an object. Datetimes become strings in their
+/// TOML (RFC 3339) form.
+pub fn from_toml(input: &str) -> Result<JsonValue, ConvertError> {
+    let table: ::toml::Table = input
+        .parse()
+        .map_err(|e: ::toml::de::Error| ConvertError::Toml(e.message().to_string()))?;
+    Ok(from_table(table))
+}
+
+fn to_table(
+    object: &[(String, JsonValue)],
+    path: &mut String,
+) -> Result<::toml::Table, ConvertError> {
+    object
+        .iter()
+        .map(|(key, value)| {
+            let len = path.len();
+            path.push('/');
+            path.push_str(&escape_token(key));
+            let value = to_toml_value(value, path)?;
+            path.truncate(len);
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+fn to_toml_value(value: &JsonValue, path: &mut String) -> Result<::toml::Value, ConvertError> {
+    Ok(match value {
+        JsonValue::Null => return Err(ConvertError::Null(path.clone())),
+        JsonValue::Boolean(b) => ::toml::Value::Boolean(*b),
+        JsonValue::Integer(i) => ::toml::Value::Integer(*i),
+        JsonValue::Number(n) => ::toml::Value::Float(*n),
+        // Integers beyond i64 are refused rather than rounded to a float.
+        JsonValue::RawNumber(raw) => match raw.parse::<i64>() {
+            Ok(i) => ::toml::Value::Integer(i),
+            Err(_) if raw.contains(['.', 'e', 'E']) => match raw.parse::<f64>() {
+                Ok(n) if n.is_finite() => ::toml::Value::Float(n),
+                _ => return Err(ConvertError::UnsupportedNumber(path.clone())),
+            },
+            Err(_) => return Err(ConvertError::UnsupportedNumber(path.clone())),
+        },
+        JsonValue::String(s) => ::toml::Value::String(s.clone()),
+        JsonValue::Array(array) => {
+            let mut values = Vec::with_capacity(array.len());
+            for (i, element) in array.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                values.push(to_toml_value(element, path)?);
+                path.truncate(len);
+            }
+            ::toml::Value::Array(values)
+        }
+        JsonValue::Object(object) => ::toml::Value::Table(to_table(object, path)?),
+    })
+}
+
+fn from_table(table: ::toml::Table) -> JsonValue {
+    JsonValue::Object(
+        table
+            .into_iter()
+            .map(|(key, value)| (key, from_toml_value(value)))
+            .collect(),
+    )
+}
+
+fn from_toml_value(value: ::toml::Value) -> JsonValue {
+    match value {
+        ::toml::Value::String(s) => JsonValue::String(s),
+        ::toml::Value::Integer(i) => JsonValue::Integer(i),
+        ::toml::Value::Float(n) => JsonValue::Number(n),
+        ::toml::Value::Boolean(b) => JsonValue::Boolean(b),
+        ::toml::Value::Datetime(datetime) => JsonValue::String(datetime.to_string()),
+        ::toml::Value::Array(array) => {
+            JsonValue::Array(array.into_iter().map(from_toml_value).collect())
+        }
+        ::toml::Value::Table(table) => from_table(table),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn round_trips_objects_tables_and_arrays() {
+        let value = json(
+            r#"{"title": "demo", "port": 8080, "ratio": 0.5, "debug": false,
+                "matrix": [[1, 2], [3]], "mixed": [1, "two", 3.5, true, {"k": "v"}],
+                "server": {"host": "localhost", "tags": ["a", "b"]}}"#,
+        );
+        let toml = to_toml(&value).unwrap();
+        assert_eq!(from_toml(&toml), Ok(value));
+    }
+
+    #[test]
+    fn writes_subtables_after_plain_keys() {
+        let toml = to_toml(&json(r#"{"a": {"x": 1}, "b": 2}"#)).unwrap();
+        assert_eq!(from_toml(&toml), Ok(json(r#"{"b": 2, "a": {"x": 1}}"#)));
+    }
+
+    #[test]
+    fn reports_nulls_with_their_pointer() {
+        assert_eq!(
+            to_toml(&json(r#"{"a": null}"#)),
+            Err(ConvertError::Null("/a".to_string()))
+        );
+        assert_eq!(
+            to_toml(&json(r#"{"a": {"b/c": [1, [2, null]]}}"#)),
+            Err(ConvertError::Null("/a/b~1c/1/1".to_string()))
+        );
+        assert_eq!(
+            to_toml(&json(r#"{"list": [{"x": 1}, {"x": null}]}"#))
+                .unwrap_err()
+                .to_string(),
+            "TOML cannot represent null at /list/1/x"
+        );
+    }
+
+    #[test]
+    fn rejects_non_objects_and_unrepresentable_numbers() {
+        assert_eq!(to_toml(&json("[1, 2]")), Err(ConvertError::NotAnObject));
+        let big = JsonValue::Object(vec![(
+            "n".to_string(),
+            JsonValue::RawNumber("18446744073709551616".to_string()),
+        )]);
+        assert_eq!(
+            to_toml(&big),
+            Err(ConvertError::UnsupportedNumber("/n".to_string()))
+        );
+        assert!(matches!(from_toml("a = "), Err(ConvertError::Toml(_))));
+    }
+
+    #[test]
+    fn reads_datetimes_as_strings() {
+        assert_eq!(
+            from_toml("when = 1979-05-27T07:32:00Z\n"),
+            Ok(json(r#"{"when": "1979-05-27T07:32:00Z"}"#))
+        );
+    }
+}
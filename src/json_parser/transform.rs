@@ -0,0 +1,806 @@
+use super::compare::total_cmp;
+use super::parser::parse_json;
+use super::serializer::{format_number, to_string, SerializerOptions};
+use super::value::{JsonType, JsonValue};
+
+impl JsonValue {
+    /// Rebuilds the tree with `f` applied to every scalar leaf. Arrays, objects
+    /// and keys are kept as they are.
+    pub fn map_scalars<F: FnMut(JsonValue) -> JsonValue>(self, mut f: F) -> JsonValue {
+        self.map_scalars_with(&mut f)
+    }
+
+    fn map_scalars_with<F: FnMut(JsonValue) -> JsonValue>(self, f: &mut F) -> JsonValue {
+        match self {
+            JsonValue::Array(array) => {
+                JsonValue::Array(array.into_iter().map(|v| v.map_scalars_with(f)).collect())
+            }
+            JsonValue::Object(object) => JsonValue::Object(
+                object
+                    .into_iter()
+                    .map(|(k, v)| (k, v.map_scalars_with(f)))
+                    .collect(),
+            ),
+            scalar => f(scalar),
+        }
+    }
+
+    /// Wraps anything but an array in a one-element array, for APIs that send
+    /// a lone item instead of a list of one. Arrays are returned unchanged.
+    pub fn ensure_array(self) -> JsonValue {
+        match self {
+            JsonValue::Array(_) => self,
+            other => JsonValue::Array(vec![other]),
+        }
+    }
+
+    /// The inverse of [`ensure_array`](JsonValue::ensure_array): a one-element
+    /// array becomes its element, and anything else is returned unchanged.
+    /// Only the outer value is unwrapped, not nested arrays.
+    pub fn unwrap_single(self) -> JsonValue {
+        match self {
+            JsonValue::Array(mut array) if array.len() == 1 => array.pop().unwrap(),
+            other => other,
+        }
+    }
+
+    /// Replaces every object whose only member is `key` with that member's
+    /// value, at any depth, as with the wrappers left by XML conversion:
+    /// `{"item": {"a": 1}}` becomes `{"a": 1}` for `item`. A value exposed
+    /// this way is checked again, so nested wrappers collapse all at once,
+    /// and then its children are visited.
+    pub fn collapse_single_key(&mut self, key: &str) {
+        while let JsonValue::Object(object) = self {
+            match object.as_mut_slice() {
+                [(k, value)] if k == key => *self = std::mem::replace(value, JsonValue::Null),
+                _ => break,
+            }
+        }
+        match self {
+            JsonValue::Array(array) => array.iter_mut().for_each(|v| v.collapse_single_key(key)),
+            JsonValue::Object(object) => object
+                .iter_mut()
+                .for_each(|(_, v)| v.collapse_single_key(key)),
+            _ => {}
+        }
+    }
+
+    /// Trims leading and trailing ASCII whitespace from every string value, as
+    /// when importing user-entered data. Object keys are trimmed too when
+    /// `keys` is set. Other whitespace, such as U+00A0, is kept.
+    pub fn trim_strings(&mut self, keys: bool) {
+        fn trim(s: &mut String) {
+            let trimmed = s.trim_ascii();
+            if trimmed.len() != s.len() {
+                *s = trimmed.to_string();
+            }
+        }
+        match self {
+            JsonValue::String(s) => trim(s),
+            JsonValue::Array(array) => array.iter_mut().for_each(|v| v.trim_strings(keys)),
+            JsonValue::Object(object) => {
+                for (key, value) in object {
+                    if keys {
+                        trim(key);
+                    }
+                    value.trim_strings(keys);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// An object as an array of `[key, value]` pairs in member order, e.g.
+    /// `[["name", "John"], ["age", 30]]`, for systems whose maps lose order.
+    /// Only the outer object is converted; anything else is returned as is.
+    pub fn entries_to_array(&self) -> JsonValue {
+        match self {
+            JsonValue::Object(object) => object
+                .iter()
+                .map(|(key, value)| {
+                    JsonValue::Array(vec![JsonValue::from(key.as_str()), value.clone()])
+                })
+                .collect(),
+            other => other.clone(),
+        }
+    }
+
+    /// The inverse of [`entries_to_array`](JsonValue::entries_to_array): an
+    /// array of `[key, value]` pairs as an object. `None` unless `self` is an
+    /// array whose every element is a two-element array starting with a string.
+    pub fn array_to_object(&self) -> Option<JsonValue> {
+        self.as_array()?
+            .iter()
+            .map(|pair| match pair.as_array()? {
+                [JsonValue::String(key), value] => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replaces every number with a string of its JSON text, for consumers
+    /// such as JavaScript that would round large integers. This changes the
+    /// value types: `{"id": 9007199254740993}` becomes `{"id": "9007199254740993"}`.
+    /// Floats read as the serializer writes them, so `1e300` stays `"1e300"`.
+    /// Non-finite floats, which have no JSON text, become `null`.
+    pub fn numbers_to_strings(&mut self) {
+        match self {
+            JsonValue::Integer(i) => *self = JsonValue::String(i.to_string()),
+            JsonValue::Number(n) => {
+                *self = match format_number(*n, &SerializerOptions::default()) {
+                    Ok(text) => JsonValue::String(text),
+                    Err(_) => JsonValue::Null,
+                }
+            }
+            JsonValue::RawNumber(raw) => *self = JsonValue::String(std::mem::take(raw)),
+            JsonValue::Array(array) => array.iter_mut().for_each(JsonValue::numbers_to_strings),
+            JsonValue::Object(object) => {
+                object.iter_mut().for_each(|(_, v)| v.numbers_to_strings())
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces every string whose whole content is a JSON number with that
+    /// number, read the same way the parser reads it: `"30"` becomes the
+    /// integer `30` and `"2.5"` a float, with integers beyond `i64` becoming
+    /// floats. Other strings, including ones with surrounding whitespace or
+    /// numbers too large for `f64`, are left alone.
+    pub fn strings_to_numbers(&mut self) {
+        match self {
+            JsonValue::String(s)
+                if s.trim() == s && s.starts_with(|c: char| c == '-' || c.is_ascii_digit()) =>
+            {
+                match parse_json(s) {
+                    Ok(n @ JsonValue::Integer(_)) => *self = n,
+                    Ok(JsonValue::Number(n)) if n.is_finite() => *self = JsonValue::Number(n),
+                    _ => {}
+                }
+            }
+            JsonValue::Array(array) => array.iter_mut().for_each(JsonValue::strings_to_numbers),
+            JsonValue::Object(object) => {
+                object.iter_mut().for_each(|(_, v)| v.strings_to_numbers())
+            }
+            _ => {}
+        }
+    }
+
+    /// A copy with the same structure and keys in which every scalar is the
+    /// zero of its kind: `""`, `0` or `false`, with `null` kept. Useful
+    /// for turning a sample document into a config template.
+    pub fn skeleton(&self) -> JsonValue {
+        self.clone().map_scalars(|value| match value {
+            JsonValue::Boolean(_) => JsonValue::Boolean(false),
+            JsonValue::Integer(_) => JsonValue::Integer(0),
+            JsonValue::Number(_) => JsonValue::Number(0.0),
+            JsonValue::RawNumber(_) => JsonValue::RawNumber("0".to_string()),
+            JsonValue::String(_) => JsonValue::String(String::new()),
+            other => other,
+        })
+    }
+
+    /// Converts the member `key` of every object in an array to `to`, as
+    /// when importing CSV where every column is a string. Strings become
+    /// numbers as with [`strings_to_numbers`](JsonValue::strings_to_numbers)
+    /// or booleans when they read `true` or `false` in any case, and numbers
+    /// and booleans become strings of their JSON text. Values that cannot be
+    /// converted are left as they are, or set to `null` with
+    /// `null_on_failure`. Returns how many values were changed; elements
+    /// without the key and values already of type `to` are left alone.
+    pub fn coerce_column(&mut self, key: &str, to: JsonType, null_on_failure: bool) -> usize {
+        let Some(rows) = self.as_array_mut() else {
+            return 0;
+        };
+        let mut changed = 0;
+        for row in rows {
+            let Some(value) = row
+                .as_object_mut()
+                .and_then(|object| object.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v))
+            else {
+                continue;
+            };
+            if value.json_type() == to {
+                continue;
+            }
+            match coerced(value, to) {
+                Some(converted) => *value = converted,
+                None if null_on_failure => *value = JsonValue::Null,
+                None => continue,
+            }
+            changed += 1;
+        }
+        changed
+    }
+
+    /// Replaces every number `n` in the tree with `f(n)`. Results are stored
+    /// as `Number`, so integers become floats; use
+    /// [`normalize_integers`](JsonValue::normalize_integers) afterwards to turn
+    /// whole results back into `Integer`s.
+    pub fn map_numbers<F: FnMut(f64) -> f64>(&mut self, mut f: F) {
+        self.map_numbers_with(&mut f)
+    }
+
+    fn map_numbers_with<F: FnMut(f64) -> f64>(&mut self, f: &mut F) {
+        match self {
+            JsonValue::Array(array) => array.iter_mut().for_each(|v| v.map_numbers_with(f)),
+            JsonValue::Object(object) => object.iter_mut().for_each(|(_, v)| v.map_numbers_with(f)),
+            value => {
+                if let Some(n) = value.as_f64() {
+                    *value = JsonValue::Number(f(n));
+                }
+            }
+        }
+    }
+
+    /// Replaces every node equal to `from` with a copy of `to`, e.g. every
+    /// `"N/A"` with `null`, and returns how many were replaced. Replacements
+    /// are not searched again, so `to` may itself contain `from`.
+    pub fn replace_all(&mut self, from: &JsonValue, to: &JsonValue) -> usize {
+        if self == from {
+            *self = to.clone();
+            return 1;
+        }
+        match self {
+            JsonValue::Array(array) => array.iter_mut().map(|v| v.replace_all(from, to)).sum(),
+            JsonValue::Object(object) => object
+                .iter_mut()
+                .map(|(_, v)| v.replace_all(from, to))
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// Removes every object member whose value is `null`, at any depth, to
+    /// shrink payloads. With `in_arrays`, `null` array elements are removed
+    /// too, shifting later elements down. Containers left empty are kept, and
+    /// a `null` at the root is left alone.
+    pub fn prune_nulls(&mut self, in_arrays: bool) {
+        match self {
+            JsonValue::Array(array) => {
+                if in_arrays {
+                    array.retain(|v| *v != JsonValue::Null);
+                }
+                array.iter_mut().for_each(|v| v.prune_nulls(in_arrays));
+            }
+            JsonValue::Object(object) => {
+                object.retain(|(_, v)| *v != JsonValue::Null);
+                object
+                    .iter_mut()
+                    .for_each(|(_, v)| v.prune_nulls(in_arrays));
+            }
+            _ => {}
+        }
+    }
+
+    /// Multiplies every number by `factor`, e.g. 100 to turn dollars into
+    /// cents. See [`map_numbers`](JsonValue::map_numbers).
+    pub fn scale_numbers(&mut self, factor: f64) {
+        self.map_numbers(|n| n * factor)
+    }
+
+    /// Stably sorts an array by the value at `key_path` in each element, using
+    /// [`get_path`](JsonValue::get_path) syntax and [`total_cmp`] order.
+    /// Elements without that path sort last. Non-arrays are left alone.
+    pub fn sort_array_by_key(&mut self, key_path: &str) {
+        if let JsonValue::Array(array) = self {
+            array.sort_by(|a, b| match (a.get_path(key_path), b.get_path(key_path)) {
+                (Some(a), Some(b)) => total_cmp(a, b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            });
+        }
+    }
+
+    /// Sorts every array at any depth into [`total_cmp`] order, innermost
+    /// first, so documents that differ only in array order serialize the same
+    /// and can be hashed or diffed. This discards order that matters, such as
+    /// in a list of steps, so use it only where order is insignificant. Object
+    /// members keep their order.
+    pub fn sort_arrays(&mut self) {
+        match self {
+            JsonValue::Array(array) => {
+                array.iter_mut().for_each(JsonValue::sort_arrays);
+                array.sort_by(total_cmp);
+            }
+            JsonValue::Object(object) => object.iter_mut().for_each(|(_, v)| v.sort_arrays()),
+            _ => {}
+        }
+    }
+
+    /// Turns every `Number` holding a whole value within `i64` range into an
+    /// `Integer`, e.g. after arithmetic produced `90.0`. Negative zero becomes `0`.
+    pub fn normalize_integers(&mut self) {
+        match self {
+            JsonValue::Number(n)
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+            {
+                *self = JsonValue::Integer(*n as i64);
+            }
+            JsonValue::Array(array) => array.iter_mut().for_each(JsonValue::normalize_integers),
+            JsonValue::Object(object) => {
+                object.iter_mut().for_each(|(_, v)| v.normalize_integers())
+            }
+            _ => {}
+        }
+    }
+
+    /// A copy for display: containers nested `max_depth` levels below `self`
+    /// become the string `"..."`, and arrays longer than `max_array` keep their
+    /// first `max_array` elements followed by a `"... N more"` marker.
+    pub fn truncated(&self, max_depth: usize, max_array: usize) -> JsonValue {
+        self.truncated_at(0, max_depth, max_array)
+    }
+
+    fn truncated_at(&self, depth: usize, max_depth: usize, max_array: usize) -> JsonValue {
+        match self {
+            JsonValue::Array(_) | JsonValue::Object(_) if depth >= max_depth => {
+                JsonValue::from("...")
+            }
+            JsonValue::Array(array) => {
+                let mut truncated: Vec<_> = array
+                    .iter()
+                    .take(max_array)
+                    .map(|v| v.truncated_at(depth + 1, max_depth, max_array))
+                    .collect();
+                if array.len() > max_array {
+                    truncated.push(JsonValue::String(format!(
+                        "... {} more",
+                        array.len() - max_array
+                    )));
+                }
+                JsonValue::Array(truncated)
+            }
+            JsonValue::Object(object) => JsonValue::Object(
+                object
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.truncated_at(depth + 1, max_depth, max_array)))
+                    .collect(),
+            ),
+            scalar => scalar.clone(),
+        }
+    }
+}
+
+fn coerced(value: &JsonValue, to: JsonType) -> Option<JsonValue> {
+    match (value, to) {
+        (JsonValue::String(_), JsonType::Number) => {
+            let mut number = value.clone();
+            number.strings_to_numbers();
+            (number.json_type() == JsonType::Number).then_some(number)
+        }
+        (JsonValue::String(s), JsonType::Boolean) => match s.to_ascii_lowercase().as_str() {
+            "true" => Some(JsonValue::Boolean(true)),
+            "false" => Some(JsonValue::Boolean(false)),
+            _ => None,
+        },
+        (
+            JsonValue::Boolean(_)
+            | JsonValue::Integer(_)
+            | JsonValue::Number(_)
+            | JsonValue::RawNumber(_),
+            JsonType::String,
+        ) => to_string(value).ok().map(JsonValue::String),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn map_scalars_visits_only_leaves() {
+        let doc = json(r#"{"a": [1, "x", null], "b": {"c": true}, "d": []}"#);
+        let mut seen = Vec::new();
+        let mapped = doc.map_scalars(|v| {
+            seen.push(v.clone());
+            match v {
+                JsonValue::String(s) => JsonValue::String(s.to_uppercase()),
+                JsonValue::Null => JsonValue::Integer(0),
+                other => other,
+            }
+        });
+        assert_eq!(
+            mapped,
+            json(r#"{"a": [1, "X", 0], "b": {"c": true}, "d": []}"#)
+        );
+        assert_eq!(
+            seen,
+            [json("1"), json(r#""x""#), json("null"), json("true")]
+        );
+        assert_eq!(json("7").map_scalars(|_| JsonValue::Null), JsonValue::Null);
+    }
+
+    #[test]
+    fn integers_parse_exactly_within_i64() {
+        // `==` equates `Integer` and `Number` of the same value; Debug does not.
+        let repr = |input: &str| format!("{:?}", json(input));
+        assert_eq!(repr("9223372036854775807"), "Integer(9223372036854775807)");
+        assert_eq!(
+            repr("-9223372036854775808"),
+            "Integer(-9223372036854775808)"
+        );
+        assert_eq!(repr("9223372036854775808"), "Number(9.223372036854776e18)");
+        assert_eq!(repr("1.0"), "Number(1.0)");
+        assert_eq!(repr("1e2"), "Number(100.0)");
+    }
+
+    #[test]
+    fn normalize_integers_converts_only_lossless_whole_numbers() {
+        let mut doc = JsonValue::Array(vec![
+            JsonValue::Number(90.0),
+            JsonValue::Number(-0.0),
+            JsonValue::Number(2.5),
+            JsonValue::Number(9223372036854775808.0),
+            JsonValue::Number(f64::INFINITY),
+            JsonValue::Object(vec![("a".into(), JsonValue::Number(-3.0))]),
+            JsonValue::String("4.0".into()),
+        ]);
+        doc.normalize_integers();
+        let expected = JsonValue::Array(vec![
+            JsonValue::Integer(90),
+            JsonValue::Integer(0),
+            JsonValue::Number(2.5),
+            JsonValue::Number(9223372036854775808.0),
+            JsonValue::Number(f64::INFINITY),
+            JsonValue::Object(vec![("a".into(), JsonValue::Integer(-3))]),
+            JsonValue::String("4.0".into()),
+        ]);
+        assert_eq!(format!("{:?}", doc), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn truncated_elides_deep_containers_and_long_arrays() {
+        let doc = json(r#"{"a": {"b": {"c": 1}}, "list": [1, 2, 3, 4], "s": "x"}"#);
+        assert_eq!(
+            doc.truncated(2, 2),
+            json(r#"{"a": {"b": "..."}, "list": [1, 2, "... 2 more"], "s": "x"}"#)
+        );
+        assert_eq!(doc.truncated(0, 10), json(r#""...""#));
+        assert_eq!(doc.truncated(10, 10), doc);
+        assert_eq!(json("[[1], 2]").truncated(1, 0), json(r#"["... 2 more"]"#));
+        assert_eq!(json("5").truncated(0, 0), json("5"));
+    }
+
+    #[test]
+    fn numbers_to_strings_keeps_number_text() {
+        let mut doc = json(r#"{"id": 9007199254740993, "price": [2.5, -0.125], "name": "x"}"#);
+        doc.numbers_to_strings();
+        assert_eq!(
+            doc,
+            json(r#"{"id": "9007199254740993", "price": ["2.5", "-0.125"], "name": "x"}"#)
+        );
+        let mut odd = JsonValue::Array(vec![
+            JsonValue::Number(f64::NAN),
+            JsonValue::RawNumber("1.50".into()),
+            JsonValue::Boolean(true),
+        ]);
+        odd.numbers_to_strings();
+        assert_eq!(odd, json(r#"[null, "1.50", true]"#));
+    }
+
+    #[test]
+    fn numbers_to_strings_writes_floats_as_the_serializer_does() {
+        let mut doc = JsonValue::Array(vec![
+            JsonValue::Number(1e300),
+            JsonValue::Number(-2.5e-8),
+            JsonValue::Number(30.0),
+            JsonValue::Number(1e20),
+        ]);
+        let written = to_string(&doc).unwrap();
+        doc.numbers_to_strings();
+        assert_eq!(
+            doc,
+            json(r#"["1e300", "-2.5e-8", "30", "100000000000000000000"]"#)
+        );
+        let strings: Vec<&str> = doc
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .collect();
+        assert_eq!(format!("[{}]", strings.join(",")), written);
+    }
+
+    #[test]
+    fn strings_to_numbers_converts_only_whole_json_numbers() {
+        let mut doc = json(
+            r#"["30", "2.5", "-1e2", "9223372036854775808", " 1", "1 ", "01", "1.", "0x10",
+                "abc", "", "1e999", {"n": "7"}]"#,
+        );
+        doc.strings_to_numbers();
+        assert_eq!(
+            doc,
+            JsonValue::Array(vec![
+                JsonValue::Integer(30),
+                JsonValue::Number(2.5),
+                JsonValue::Number(-100.0),
+                JsonValue::Number(9223372036854775808.0),
+                json(r#"" 1""#),
+                json(r#""1 ""#),
+                json(r#""01""#),
+                json(r#""1.""#),
+                json(r#""0x10""#),
+                json(r#""abc""#),
+                json(r#""""#),
+                json(r#""1e999""#),
+                json(r#"{"n": 7}"#),
+            ])
+        );
+    }
+
+    #[test]
+    fn ensure_array_wraps_everything_but_arrays() {
+        assert_eq!(json(r#"{"a": 1}"#).ensure_array(), json(r#"[{"a": 1}]"#));
+        assert_eq!(json("null").ensure_array(), json("[null]"));
+        assert_eq!(json(r#""x""#).ensure_array(), json(r#"["x"]"#));
+        assert_eq!(json("[1, 2]").ensure_array(), json("[1, 2]"));
+        assert_eq!(json("[]").ensure_array(), json("[]"));
+    }
+
+    #[test]
+    fn map_numbers_applies_to_every_number_as_floats() {
+        let mut doc = json(r#"{"a": 1, "b": [2.5, "3", {"c": -4}], "d": true}"#);
+        doc.map_numbers(|n| n + 1.0);
+        assert_eq!(
+            doc,
+            json(r#"{"a": 2.0, "b": [3.5, "3", {"c": -3.0}], "d": true}"#)
+        );
+        assert!(matches!(doc.get("a"), Some(JsonValue::Number(n)) if *n == 2.0));
+        doc.normalize_integers();
+        assert!(matches!(doc.get("a"), Some(JsonValue::Integer(2))));
+    }
+
+    #[test]
+    fn scale_numbers_multiplies_by_the_factor() {
+        let mut doc = json(r#"{"price": 1.25, "items": [2, 0.5], "qty": 10}"#);
+        doc.scale_numbers(100.0);
+        doc.normalize_integers();
+        assert_eq!(
+            doc,
+            json(r#"{"price": 125, "items": [200, 50], "qty": 1000}"#)
+        );
+    }
+
+    #[test]
+    fn sort_array_by_key_orders_stably_with_missing_keys_last() {
+        let mut rows = json(
+            r#"[{"id": 1, "age": 40}, {"id": 2}, {"id": 3, "age": 25},
+                {"id": 4, "age": 40}, "scalar", {"id": 5, "meta": {"age": 1}}]"#,
+        );
+        rows.sort_array_by_key("age");
+        assert_eq!(
+            rows,
+            json(
+                r#"[{"id": 3, "age": 25}, {"id": 1, "age": 40}, {"id": 4, "age": 40},
+                    {"id": 2}, "scalar", {"id": 5, "meta": {"age": 1}}]"#
+            )
+        );
+    }
+
+    #[test]
+    fn sort_array_by_key_follows_nested_paths() {
+        let mut rows = json(r#"[{"u": {"name": "b"}}, {"u": {"name": "a"}}, {"u": {}}]"#);
+        rows.sort_array_by_key("u.name");
+        assert_eq!(
+            rows,
+            json(r#"[{"u": {"name": "a"}}, {"u": {"name": "b"}}, {"u": {}}]"#)
+        );
+        let mut object = json(r#"{"b": 1, "a": 2}"#);
+        object.sort_array_by_key("b");
+        assert_eq!(object, json(r#"{"b": 1, "a": 2}"#));
+    }
+
+    #[test]
+    fn replace_all_swaps_every_matching_node() {
+        let mut doc = json(r#"{"a": "N/A", "b": ["N/A", "n/a", {"c": "N/A"}], "N/A": 1}"#);
+        assert_eq!(doc.replace_all(&json(r#""N/A""#), &JsonValue::Null), 3);
+        assert_eq!(
+            doc,
+            json(r#"{"a": null, "b": [null, "n/a", {"c": null}], "N/A": 1}"#)
+        );
+        assert_eq!(doc.replace_all(&json("true"), &json("false")), 0);
+    }
+
+    #[test]
+    fn replace_all_matches_subtrees_and_does_not_revisit_replacements() {
+        let mut doc = json(r#"[[1], [[1]], 1]"#);
+        assert_eq!(doc.replace_all(&json("[1]"), &json("[[1]]")), 2);
+        assert_eq!(doc, json(r#"[[[1]], [[[1]]], 1]"#));
+        let mut whole = json(r#"{"x": 1}"#);
+        assert_eq!(whole.replace_all(&json(r#"{"x": 1}"#), &json("0")), 1);
+        assert_eq!(whole, json("0"));
+    }
+
+    #[test]
+    fn prune_nulls_drops_null_members_at_any_depth() {
+        let mut doc = json(
+            r#"{"a": null, "b": {"c": null, "d": 1}, "e": [null, {"f": null}], "g": {"h": null}}"#,
+        );
+        doc.prune_nulls(false);
+        assert_eq!(doc, json(r#"{"b": {"d": 1}, "e": [null, {}], "g": {}}"#));
+        let mut root = JsonValue::Null;
+        root.prune_nulls(true);
+        assert_eq!(root, JsonValue::Null);
+    }
+
+    #[test]
+    fn prune_nulls_in_arrays_shifts_later_elements() {
+        let mut doc = json(r#"{"e": [null, 1, null, [null, 2]], "x": null}"#);
+        doc.prune_nulls(true);
+        assert_eq!(doc, json(r#"{"e": [1, [2]]}"#));
+    }
+
+    #[test]
+    fn unwrap_single_unwraps_one_element_arrays_once() {
+        assert_eq!(json("[5]").unwrap_single(), json("5"));
+        assert_eq!(json("[[1]]").unwrap_single(), json("[1]"));
+        assert_eq!(json(r#"[{"a": 1}]"#).unwrap_single(), json(r#"{"a": 1}"#));
+        assert_eq!(json("[5]").ensure_array().unwrap_single(), json("5"));
+    }
+
+    #[test]
+    fn unwrap_single_leaves_other_values_alone() {
+        assert_eq!(json("[]").unwrap_single(), json("[]"));
+        assert_eq!(json("[1, 2]").unwrap_single(), json("[1, 2]"));
+        assert_eq!(json(r#"{"a": [1]}"#).unwrap_single(), json(r#"{"a": [1]}"#));
+        assert_eq!(json("null").unwrap_single(), json("null"));
+    }
+
+    #[test]
+    fn trim_strings_trims_values_at_any_depth() {
+        let mut doc =
+            json(r#"{" name ": "  Ann\t", "tags": [" a", "b \n", {"c": "\r\nx "}], "n": 1}"#);
+        doc.trim_strings(false);
+        assert_eq!(
+            doc,
+            json(r#"{" name ": "Ann", "tags": ["a", "b", {"c": "x"}], "n": 1}"#)
+        );
+        doc.trim_strings(true);
+        assert_eq!(
+            doc,
+            json(r#"{"name": "Ann", "tags": ["a", "b", {"c": "x"}], "n": 1}"#)
+        );
+    }
+
+    #[test]
+    fn trim_strings_keeps_inner_and_non_ascii_whitespace() {
+        let mut doc = json(r#"["a  b", "\u00a0x\u00a0", "   "]"#);
+        doc.trim_strings(true);
+        assert_eq!(doc, json(r#"["a  b", "\u00a0x\u00a0", ""]"#));
+    }
+
+    #[test]
+    fn entries_to_array_and_back_keep_member_order() {
+        let object = json(r#"{"name": "John", "age": 30, "nested": {"b": 1, "a": 2}}"#);
+        let pairs = object.entries_to_array();
+        assert_eq!(
+            pairs,
+            json(r#"[["name", "John"], ["age", 30], ["nested", {"b": 1, "a": 2}]]"#)
+        );
+        assert_eq!(pairs.array_to_object(), Some(object));
+        assert_eq!(json("{}").entries_to_array(), json("[]"));
+        assert_eq!(json("[1]").entries_to_array(), json("[1]"));
+    }
+
+    #[test]
+    fn array_to_object_rejects_malformed_pairs() {
+        assert_eq!(json("[]").array_to_object(), Some(json("{}")));
+        assert_eq!(
+            json(r#"[["a", 1], ["a", 2]]"#).array_to_object(),
+            Some(JsonValue::Object(vec![
+                ("a".to_string(), JsonValue::Integer(1)),
+                ("a".to_string(), JsonValue::Integer(2)),
+            ]))
+        );
+        for input in [
+            r#"[["a"]]"#,
+            r#"[["a", 1, 2]]"#,
+            r#"[[1, 2]]"#,
+            r#"[{"a": 1}]"#,
+            r#"{"a": 1}"#,
+        ] {
+            assert_eq!(json(input).array_to_object(), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn sort_arrays_orders_every_array_innermost_first() {
+        let mut doc =
+            json(r#"{"b": [3, "a", null, [2, 1], true, 1.5], "a": {"c": [[1, 0], [0]]}}"#);
+        doc.sort_arrays();
+        assert_eq!(
+            to_string(&doc).unwrap(),
+            r#"{"b":[null,true,1.5,3,"a",[1,2]],"a":{"c":[[0],[0,1]]}}"#
+        );
+    }
+
+    #[test]
+    fn sort_arrays_makes_reordered_documents_serialize_the_same() {
+        let mut a = json(r#"[{"id": 2, "tags": ["y", "x"]}, {"id": 1}, 1.0, 1]"#);
+        let mut b = json(r#"[1, {"id": 1}, 1.0, {"id": 2, "tags": ["x", "y"]}]"#);
+        assert_ne!(to_string(&a).unwrap(), to_string(&b).unwrap());
+        a.sort_arrays();
+        b.sort_arrays();
+        assert_eq!(
+            to_string(&a).unwrap(),
+            r#"[1,1,{"id":1},{"id":2,"tags":["x","y"]}]"#
+        );
+        assert_eq!(to_string(&a).unwrap(), to_string(&b).unwrap());
+    }
+
+    #[test]
+    fn coerce_column_converts_strings_and_counts_changes() {
+        let mut rows =
+            json(r#"[{"n": "12"}, {"n": "1.5"}, {"n": "abc"}, {"n": 3}, {"m": "7"}, 5]"#);
+        assert_eq!(rows.coerce_column("n", JsonType::Number, false), 2);
+        assert_eq!(
+            rows,
+            json(r#"[{"n": 12}, {"n": 1.5}, {"n": "abc"}, {"n": 3}, {"m": "7"}, 5]"#)
+        );
+
+        let mut flags = json(r#"[{"f": "TRUE"}, {"f": "false"}, {"f": "yes"}]"#);
+        assert_eq!(flags.coerce_column("f", JsonType::Boolean, true), 3);
+        assert_eq!(flags, json(r#"[{"f": true}, {"f": false}, {"f": null}]"#));
+    }
+
+    #[test]
+    fn coerce_column_to_string_uses_json_text() {
+        let mut rows = json(r#"[{"v": 1}, {"v": 2.5}, {"v": true}, {"v": "s"}, {"v": [1]}]"#);
+        assert_eq!(rows.coerce_column("v", JsonType::String, false), 3);
+        assert_eq!(
+            rows,
+            json(r#"[{"v": "1"}, {"v": "2.5"}, {"v": "true"}, {"v": "s"}, {"v": [1]}]"#)
+        );
+        let mut scalar = json("3");
+        assert_eq!(scalar.coerce_column("v", JsonType::String, true), 0);
+    }
+
+    #[test]
+    fn skeleton_zeroes_scalars_and_keeps_structure() {
+        let doc = json(
+            r#"{"name": "Ann", "age": 31, "ratio": 0.5, "ok": true, "none": null, "tags": ["a", 2], "nested": {"x": {}}}"#,
+        );
+        let skeleton = doc.skeleton();
+        assert_eq!(
+            skeleton,
+            json(
+                r#"{"name": "", "age": 0, "ratio": 0.0, "ok": false, "none": null, "tags": ["", 0], "nested": {"x": {}}}"#
+            )
+        );
+        assert_eq!(skeleton.pointer("/ratio"), Some(&JsonValue::Number(0.0)));
+        // Floats and integers both print as a plain zero.
+        assert_eq!(
+            to_string(&skeleton).unwrap(),
+            r#"{"name":"","age":0,"ratio":0,"ok":false,"none":null,"tags":["",0],"nested":{"x":{}}}"#
+        );
+        assert_eq!(
+            JsonValue::RawNumber("1e400".into()).skeleton(),
+            JsonValue::RawNumber("0".into())
+        );
+    }
+
+    #[test]
+    fn collapse_single_key_unwraps_nested_wrappers_at_any_depth() {
+        let mut doc = json(
+            r#"{"items": {"item": [{"item": {"item": {"a": 1}}}, {"item": 2, "b": 3}, {"other": 4}]}}"#,
+        );
+        doc.collapse_single_key("item");
+        assert_eq!(
+            doc,
+            json(r#"{"items": [{"a": 1}, {"item": 2, "b": 3}, {"other": 4}]}"#)
+        );
+
+        let mut root = json(r#"{"item": {"item": "x"}}"#);
+        root.collapse_single_key("item");
+        assert_eq!(root, json(r#""x""#));
+
+        let mut untouched = json(r#"{"a": {"b": 1}}"#);
+        untouched.collapse_single_key("item");
+        assert_eq!(untouched, json(r#"{"a": {"b": 1}}"#));
+    }
+}
@@ -0,0 +1,91 @@
+use unicode_normalization::UnicodeNormalization;
+
+use super::value::JsonValue;
+
+/// A Unicode normalization form, as defined by UAX #15.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Canonical composition: `e` followed by U+0301 becomes `é`.
+    Nfc,
+    /// Canonical decomposition, the inverse of `Nfc`.
+    Nfd,
+    /// Compatibility composition, which also folds variants such as `ﬁ` to `fi`.
+    Nfkc,
+    /// Compatibility decomposition.
+    Nfkd,
+}
+
+fn normalize(s: &mut String, form: NormalizationForm) {
+    let normalized: String = match form {
+        NormalizationForm::Nfc => s.nfc().collect(),
+        NormalizationForm::Nfd => s.nfd().collect(),
+        NormalizationForm::Nfkc => s.nfkc().collect(),
+        NormalizationForm::Nfkd => s.nfkd().collect(),
+    };
+    *s = normalized;
+}
+
+impl JsonValue {
+    /// Rewrites every string value in `form`, so that text which looks the
+    /// same but is encoded with different code points compares equal. Object
+    /// keys are normalized too when `keys` is set; keys that become equal
+    /// are kept as separate members.
+    pub fn normalize_unicode(&mut self, form: NormalizationForm, keys: bool) {
+        match self {
+            JsonValue::String(s) => normalize(s, form),
+            JsonValue::Array(array) => array
+                .iter_mut()
+                .for_each(|v| v.normalize_unicode(form, keys)),
+            JsonValue::Object(object) => {
+                for (key, value) in object {
+                    if keys {
+                        normalize(key, form);
+                    }
+                    value.normalize_unicode(form, keys);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn json(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    #[test]
+    fn nfc_composes_strings_so_equivalent_text_compares_equal() {
+        let mut decomposed = json(r#"["cafe\u0301", {"k": "A\u030a"}, 1]"#);
+        assert_ne!(decomposed, json(r#"["caf\u00e9", {"k": "\u00c5"}, 1]"#));
+        decomposed.normalize_unicode(NormalizationForm::Nfc, false);
+        assert_eq!(decomposed, json(r#"["caf\u00e9", {"k": "\u00c5"}, 1]"#));
+        // NFC keeps compatibility characters such as the `fi` ligature.
+        let mut ligature = json(r#""\ufb01""#);
+        ligature.normalize_unicode(NormalizationForm::Nfc, false);
+        assert_eq!(ligature, json(r#""\ufb01""#));
+        ligature.normalize_unicode(NormalizationForm::Nfkc, false);
+        assert_eq!(ligature, json(r#""fi""#));
+    }
+
+    #[test]
+    fn nfd_is_the_inverse_of_nfc() {
+        let mut value = json(r#""caf\u00e9""#);
+        value.normalize_unicode(NormalizationForm::Nfd, false);
+        assert_eq!(value, json(r#""cafe\u0301""#));
+    }
+
+    #[test]
+    fn keys_are_normalized_only_when_asked_and_collisions_are_kept() {
+        let input = r#"{"e\u0301": 1, "\u00e9": 2}"#;
+        let mut value = json(input);
+        value.normalize_unicode(NormalizationForm::Nfc, false);
+        assert_eq!(value, json(input));
+        value.normalize_unicode(NormalizationForm::Nfc, true);
+        assert_eq!(value, json(r#"{"\u00e9": 1, "\u00e9": 2}"#));
+    }
+}
@@ -1,9 +1,843 @@
-#[derive(Debug, PartialEq)]
+use std::borrow::Cow;
+use std::fmt;
+
+/// A parsed JSON document.
+///
+/// `==` compares numbers by value across `Integer` and `Number`, so
+/// `Integer(5) == Number(5.0)`: both are written as `5`, and the distinction
+/// does not survive a round trip through JSON text. `RawNumber` compares by
+/// its source text, and everything else structurally, including object key
+/// order.
+#[derive(Debug, Clone)]
 pub enum JsonValue {
     Null,
     Boolean(bool),
+    Integer(i64),
     Number(f64),
+    /// A number kept as its exact source text, for values `f64` cannot hold losslessly.
+    RawNumber(String),
     String(String),
     Array(Vec<JsonValue>),
     Object(Vec<(String, JsonValue)>),
 }
+
+impl PartialEq for JsonValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (JsonValue::Null, JsonValue::Null) => true,
+            (JsonValue::Boolean(a), JsonValue::Boolean(b)) => a == b,
+            (JsonValue::Integer(a), JsonValue::Integer(b)) => a == b,
+            (JsonValue::Number(a), JsonValue::Number(b)) => a == b,
+            (JsonValue::Integer(i), JsonValue::Number(n))
+            | (JsonValue::Number(n), JsonValue::Integer(i)) => integer_eq_f64(*i, *n),
+            (JsonValue::RawNumber(a), JsonValue::RawNumber(b)) => a == b,
+            (JsonValue::String(a), JsonValue::String(b)) => a == b,
+            (JsonValue::Array(a), JsonValue::Array(b)) => a == b,
+            (JsonValue::Object(a), JsonValue::Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+// Exact, unlike `i as f64 == n`, which rounds integers beyond 2^53.
+pub(crate) fn integer_eq_f64(i: i64, n: f64) -> bool {
+    n.fract() == 0.0 && n >= -(2f64.powi(63)) && n < 2f64.powi(63) && n as i64 == i
+}
+
+/// The kind of a JSON value, ignoring how numbers are represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum JsonType {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            JsonType::Null => "null",
+            JsonType::Boolean => "boolean",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        })
+    }
+}
+
+impl JsonValue {
+    pub fn json_type(&self) -> JsonType {
+        match self {
+            JsonValue::Null => JsonType::Null,
+            JsonValue::Boolean(_) => JsonType::Boolean,
+            JsonValue::Integer(_) | JsonValue::Number(_) | JsonValue::RawNumber(_) => {
+                JsonType::Number
+            }
+            JsonValue::String(_) => JsonType::String,
+            JsonValue::Array(_) => JsonType::Array,
+            JsonValue::Object(_) => JsonType::Object,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The string as a `Cow`, for APIs that accept either borrowed or owned
+    /// text. Always borrows, since strings are stored owned.
+    pub fn as_cow_str(&self) -> Option<Cow<'_, str>> {
+        self.as_str().map(Cow::Borrowed)
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Vec<(String, JsonValue)>> {
+        match self {
+            JsonValue::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Any number as `f64`, which may round large integers.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Integer(i) => Some(*i as f64),
+            JsonValue::Number(n) => Some(*n),
+            JsonValue::RawNumber(raw) => raw.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The source text of a `RawNumber`, as kept by
+    /// [`ParserOptions::preserve_number_text`](super::options::ParserOptions::preserve_number_text).
+    pub fn raw_number(&self) -> Option<&str> {
+        match self {
+            JsonValue::RawNumber(raw) => Some(raw),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(object) => object.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Array(array) => array.get(index),
+            _ => None,
+        }
+    }
+
+    /// Visits every node of the tree in depth-first pre-order, starting with `self`.
+    pub fn walk<F: FnMut(&JsonValue)>(&self, mut f: F) {
+        self.walk_with(&mut f);
+    }
+
+    fn walk_with<F: FnMut(&JsonValue)>(&self, f: &mut F) {
+        f(self);
+        match self {
+            JsonValue::Array(array) => array.iter().for_each(|v| v.walk_with(f)),
+            JsonValue::Object(object) => object.iter().for_each(|(_, v)| v.walk_with(f)),
+            _ => {}
+        }
+    }
+
+    /// Every node of type `ty` in the tree, in the same pre-order as
+    /// [`walk`](JsonValue::walk), including `self` and containers nested in
+    /// containers of the same type.
+    pub fn collect_type(&self, ty: JsonType) -> Vec<&JsonValue> {
+        let mut found = Vec::new();
+        self.collect_type_into(ty, &mut found);
+        found
+    }
+
+    fn collect_type_into<'a>(&'a self, ty: JsonType, found: &mut Vec<&'a JsonValue>) {
+        if self.json_type() == ty {
+            found.push(self);
+        }
+        match self {
+            JsonValue::Array(array) => array.iter().for_each(|v| v.collect_type_into(ty, found)),
+            JsonValue::Object(object) => object
+                .iter()
+                .for_each(|(_, v)| v.collect_type_into(ty, found)),
+            _ => {}
+        }
+    }
+
+    /// The number of array elements, object members or string bytes; `None`
+    /// for other values, which have no length.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            JsonValue::String(s) => Some(s.len()),
+            JsonValue::Array(array) => Some(array.len()),
+            JsonValue::Object(object) => Some(object.len()),
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Elements `start..end` of an array. Out-of-range bounds give `None`
+    /// rather than being clamped, as with `slice::get`.
+    pub fn slice(&self, start: usize, end: usize) -> Option<&[JsonValue]> {
+        self.as_array()?.get(start..end)
+    }
+
+    pub fn first(&self) -> Option<&JsonValue> {
+        self.as_array()?.first()
+    }
+
+    pub fn last(&self) -> Option<&JsonValue> {
+        self.as_array()?.last()
+    }
+
+    /// The first object member in source order.
+    pub fn first_entry(&self) -> Option<(&str, &JsonValue)> {
+        self.as_object()?.first().map(|(k, v)| (k.as_str(), v))
+    }
+
+    pub fn last_entry(&self) -> Option<(&str, &JsonValue)> {
+        self.as_object()?.last().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Looks up a value by a dotted path such as `address.city` or `grades[1]`.
+    ///
+    /// Segments are split on `.` and `[n]`. A backslash makes the next
+    /// character part of the key, so `a\.b` names the key `a.b`; see
+    /// [`escape_path_segment`]. An empty path returns `self`.
+    pub fn get_path(&self, path: &str) -> Option<&JsonValue> {
+        let mut current = self;
+        let mut chars = path.chars().peekable();
+        let mut key = String::new();
+        loop {
+            match chars.next() {
+                Some('\\') => key.push(chars.next()?),
+                Some('.') => current = enter_key(current, &mut key)?,
+                Some('[') => {
+                    current = enter_key(current, &mut key)?;
+                    let mut index = String::new();
+                    loop {
+                        match chars.next()? {
+                            ']' => break,
+                            c => index.push(c),
+                        }
+                    }
+                    current = current.get_index(index.parse().ok()?)?;
+                    if !matches!(chars.peek(), None | Some('.' | '[')) {
+                        return None;
+                    }
+                }
+                Some(c) => key.push(c),
+                None => return enter_key(current, &mut key),
+            }
+        }
+    }
+
+    /// Lazily yields every scalar leaf with its path in
+    /// [`get_path`](JsonValue::get_path) syntax, such as `address.city` or
+    /// `grades[1]`, in document order. Empty arrays and objects are skipped.
+    /// Keys are escaped with [`escape_path_segment`], so every path leads
+    /// back to its leaf.
+    pub fn dotted_leaves(&self) -> impl Iterator<Item = (String, &JsonValue)> {
+        let mut stack = vec![(String::new(), self)];
+        std::iter::from_fn(move || loop {
+            let (path, value) = stack.pop()?;
+            match value {
+                JsonValue::Array(array) => stack.extend(
+                    array
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, child)| (format!("{}[{}]", path, i), child)),
+                ),
+                JsonValue::Object(object) => stack.extend(object.iter().rev().map(
+                    |(key, child)| match path.is_empty() {
+                        true => (escape_path_segment(key), child),
+                        false => (format!("{}.{}", path, escape_path_segment(key)), child),
+                    },
+                )),
+                leaf => return Some((path, leaf)),
+            }
+        })
+    }
+
+    /// The `(key, value)` members of an object in source order. Any other
+    /// variant yields nothing.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &JsonValue)> {
+        self.as_object()
+            .unwrap_or(&[])
+            .iter()
+            .map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Like [`entries`](JsonValue::entries), but sorted by key, leaving the
+    /// object itself in source order. Repeated keys keep their source order.
+    pub fn sorted_entries(&self) -> Vec<(&str, &JsonValue)> {
+        let mut entries: Vec<_> = self.entries().collect();
+        entries.sort_by_key(|&(key, _)| key);
+        entries
+    }
+
+    /// Consumes an object and returns an iterator over its `(key, value)`
+    /// entries in source order. Any other variant yields nothing.
+    pub fn into_entries(self) -> std::vec::IntoIter<(String, JsonValue)> {
+        match self {
+            JsonValue::Object(object) => object.into_iter(),
+            _ => Vec::new().into_iter(),
+        }
+    }
+}
+
+/// Consumes the value and iterates over its children by value: the elements
+/// of an array or the values of an object (use [`JsonValue::into_entries`] to
+/// keep the keys). Scalars have no children, so they yield nothing rather
+/// than themselves.
+impl IntoIterator for JsonValue {
+    type Item = JsonValue;
+    type IntoIter = std::vec::IntoIter<JsonValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            JsonValue::Array(array) => array.into_iter(),
+            JsonValue::Object(object) => object
+                .into_iter()
+                .map(|(_, value)| value)
+                .collect::<Vec<_>>()
+                .into_iter(),
+            _ => Vec::new().into_iter(),
+        }
+    }
+}
+
+/// Appends elements to an array.
+///
+/// # Panics
+///
+/// Panics if `self` is not a `JsonValue::Array`.
+impl Extend<JsonValue> for JsonValue {
+    fn extend<I: IntoIterator<Item = JsonValue>>(&mut self, iter: I) {
+        match self {
+            JsonValue::Array(array) => array.extend(iter),
+            _ => panic!("cannot extend a non-array JsonValue with elements"),
+        }
+    }
+}
+
+// Moves into the member named by the key collected so far, if any, and
+// starts a new key.
+fn enter_key<'a>(current: &'a JsonValue, key: &mut String) -> Option<&'a JsonValue> {
+    if key.is_empty() {
+        return Some(current);
+    }
+    let child = current.get(key);
+    key.clear();
+    child
+}
+
+/// Escapes `\`, `.`, `[` and `]` in an object key with a backslash, so it
+/// can be used as one segment of a [`JsonValue::get_path`] path even when it
+/// contains dots: `a.b` becomes `a\.b`.
+pub fn escape_path_segment(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '\\' | '.' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The inverse of [`escape_path_segment`]: drops each escaping backslash. A
+/// trailing lone backslash is kept.
+pub fn unescape_path_segment(segment: &str) -> String {
+    let mut key = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => key.push(chars.next().unwrap_or('\\')),
+            c => key.push(c),
+        }
+    }
+    key
+}
+
+/// Appends entries to an object without checking for duplicate keys.
+///
+/// # Panics
+///
+/// Panics if `self` is not a `JsonValue::Object`.
+impl Extend<(String, JsonValue)> for JsonValue {
+    fn extend<I: IntoIterator<Item = (String, JsonValue)>>(&mut self, iter: I) {
+        match self {
+            JsonValue::Object(object) => object.extend(iter),
+            _ => panic!("cannot extend a non-object JsonValue with entries"),
+        }
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Boolean(b)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(i: i64) -> Self {
+        JsonValue::Integer(i)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(n: f64) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl FromIterator<JsonValue> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = JsonValue>>(iter: I) -> Self {
+        JsonValue::Array(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<(String, JsonValue)> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = (String, JsonValue)>>(iter: I) -> Self {
+        JsonValue::Object(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+
+    fn doc() -> JsonValue {
+        parse_json(
+            r#"{"name": "Ann", "address": {"city": "Paris"}, "grades": [85, [90, 92]],
+                "rows": [{"id": 7}]}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn get_path_follows_keys_and_indexes() {
+        let doc = doc();
+        assert_eq!(doc.get_path("name"), Some(&JsonValue::String("Ann".into())));
+        assert_eq!(
+            doc.get_path("address.city"),
+            Some(&JsonValue::String("Paris".into()))
+        );
+        assert_eq!(doc.get_path("grades[0]"), Some(&JsonValue::Integer(85)));
+        assert_eq!(doc.get_path("grades[1][1]"), Some(&JsonValue::Integer(92)));
+        assert_eq!(doc.get_path("rows[0].id"), Some(&JsonValue::Integer(7)));
+        assert_eq!(doc.get_path(""), Some(&doc));
+    }
+
+    #[test]
+    fn get_path_returns_none_for_missing_or_mistyped_segments() {
+        let doc = doc();
+        assert_eq!(doc.get_path("address.zip"), None);
+        assert_eq!(doc.get_path("grades[2]"), None);
+        assert_eq!(doc.get_path("name.first"), None);
+        assert_eq!(doc.get_path("address[0]"), None);
+        assert_eq!(doc.get_path("grades[x]"), None);
+        assert_eq!(doc.get_path("grades[0"), None);
+    }
+
+    #[test]
+    fn extend_appends_elements_and_entries() {
+        let mut array = parse_json("[1]").unwrap();
+        array.extend(vec![JsonValue::Integer(2), JsonValue::Null]);
+        assert_eq!(array, parse_json("[1, 2, null]").unwrap());
+
+        let mut object = parse_json(r#"{"a": 1}"#).unwrap();
+        object.extend(vec![("b".to_string(), JsonValue::Boolean(true))]);
+        assert_eq!(object, parse_json(r#"{"a": 1, "b": true}"#).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot extend a non-array JsonValue with elements")]
+    fn extend_panics_on_a_non_array() {
+        let mut object = parse_json("{}").unwrap();
+        object.extend(vec![JsonValue::Null]);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot extend a non-object JsonValue with entries")]
+    fn extend_panics_on_a_non_object() {
+        let mut array = parse_json("[]").unwrap();
+        array.extend(vec![("a".to_string(), JsonValue::Null)]);
+    }
+
+    #[test]
+    fn from_and_from_iterator_build_values() {
+        assert_eq!(JsonValue::from(true), JsonValue::Boolean(true));
+        assert_eq!(JsonValue::from(3i64), JsonValue::Integer(3));
+        assert_eq!(JsonValue::from(0.5), JsonValue::Number(0.5));
+        assert_eq!(JsonValue::from("a"), JsonValue::String("a".into()));
+        assert_eq!(
+            JsonValue::from(String::from("b")),
+            JsonValue::String("b".into())
+        );
+
+        let array: JsonValue = (1..=3i64).map(JsonValue::from).collect();
+        assert_eq!(array, parse_json("[1, 2, 3]").unwrap());
+        let object: JsonValue = [("b", 1i64), ("a", 2)]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), JsonValue::from(v)))
+            .collect();
+        // Entries keep their iteration order.
+        assert_eq!(object, parse_json(r#"{"b": 1, "a": 2}"#).unwrap());
+        assert_ne!(object, parse_json(r#"{"a": 2, "b": 1}"#).unwrap());
+        let empty: JsonValue = std::iter::empty::<JsonValue>().collect();
+        assert_eq!(empty, JsonValue::Array(Vec::new()));
+    }
+
+    #[test]
+    fn walk_visits_nodes_in_pre_order() {
+        let doc = parse_json(r#"{"a": [1, {"b": 2}], "c": 3}"#).unwrap();
+        let mut visited = Vec::new();
+        doc.walk(|value| visited.push(value.clone()));
+        assert_eq!(
+            visited,
+            [
+                doc.clone(),
+                parse_json(r#"[1, {"b": 2}]"#).unwrap(),
+                JsonValue::Integer(1),
+                parse_json(r#"{"b": 2}"#).unwrap(),
+                JsonValue::Integer(2),
+                JsonValue::Integer(3),
+            ]
+        );
+        let mut count = 0;
+        JsonValue::Null.walk(|_| count += 1);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn into_iter_and_into_entries_consume_children() {
+        let array = parse_json(r#"[1, "a", [2]]"#).unwrap();
+        let elements: Vec<_> = array.into_iter().collect();
+        assert_eq!(
+            elements,
+            [
+                JsonValue::Integer(1),
+                JsonValue::String("a".into()),
+                parse_json("[2]").unwrap(),
+            ]
+        );
+        let object = parse_json(r#"{"b": 1, "a": null}"#).unwrap();
+        assert_eq!(
+            object.clone().into_iter().collect::<Vec<_>>(),
+            [JsonValue::Integer(1), JsonValue::Null]
+        );
+        assert_eq!(
+            object.into_entries().collect::<Vec<_>>(),
+            [
+                ("b".to_string(), JsonValue::Integer(1)),
+                ("a".to_string(), JsonValue::Null),
+            ]
+        );
+        assert_eq!(JsonValue::Integer(1).into_iter().count(), 0);
+        assert_eq!(parse_json("[1]").unwrap().into_entries().count(), 0);
+    }
+
+    #[test]
+    fn first_and_last_read_array_ends_and_object_entries() {
+        let array = parse_json("[1, 2, 3]").unwrap();
+        assert_eq!(array.first(), Some(&JsonValue::Integer(1)));
+        assert_eq!(array.last(), Some(&JsonValue::Integer(3)));
+        assert_eq!(array.first_entry(), None);
+
+        let object = parse_json(r#"{"b": 1, "a": 2}"#).unwrap();
+        assert_eq!(object.first_entry(), Some(("b", &JsonValue::Integer(1))));
+        assert_eq!(object.last_entry(), Some(("a", &JsonValue::Integer(2))));
+        assert_eq!(object.first(), None);
+
+        let empty = parse_json("[]").unwrap();
+        assert_eq!(empty.first(), None);
+        assert_eq!(empty.last(), None);
+        assert_eq!(parse_json("{}").unwrap().last_entry(), None);
+        assert_eq!(JsonValue::String("ab".into()).first(), None);
+    }
+
+    #[test]
+    fn len_and_is_empty_cover_containers_and_strings() {
+        assert_eq!(parse_json("[1, [2, 3]]").unwrap().len(), Some(2));
+        assert_eq!(parse_json(r#"{"a": 1}"#).unwrap().len(), Some(1));
+        // Strings count bytes, not characters.
+        assert_eq!(JsonValue::String("é".into()).len(), Some(2));
+        assert_eq!(parse_json("[]").unwrap().is_empty(), Some(true));
+        assert_eq!(parse_json(r#""""#).unwrap().is_empty(), Some(true));
+        assert_eq!(parse_json("{}").unwrap().is_empty(), Some(true));
+        assert_eq!(parse_json("[null]").unwrap().is_empty(), Some(false));
+        for scalar in ["0", "null", "false"] {
+            let value = parse_json(scalar).unwrap();
+            assert_eq!(value.len(), None);
+            assert_eq!(value.is_empty(), None);
+        }
+    }
+
+    #[test]
+    fn slice_returns_none_out_of_range() {
+        let array = parse_json("[0, 1, 2, 3]").unwrap();
+        assert_eq!(
+            array.slice(1, 3),
+            Some(&[JsonValue::Integer(1), JsonValue::Integer(2)][..])
+        );
+        assert_eq!(array.slice(0, 4).map(<[_]>::len), Some(4));
+        assert_eq!(array.slice(4, 4), Some(&[][..]));
+        assert_eq!(array.slice(2, 5), None);
+        assert_eq!(array.slice(5, 6), None);
+        assert_eq!(array.slice(3, 1), None);
+        assert_eq!(parse_json(r#"{"a": 1}"#).unwrap().slice(0, 1), None);
+    }
+
+    #[test]
+    fn mutable_container_accessors_edit_in_place() {
+        let mut value = doc();
+        value
+            .pointer_mut("/grades")
+            .and_then(JsonValue::as_array_mut)
+            .unwrap()
+            .push(JsonValue::Integer(70));
+        value
+            .as_object_mut()
+            .unwrap()
+            .push(("active".to_string(), JsonValue::Boolean(true)));
+        assert_eq!(
+            value,
+            parse_json(
+                r#"{"name": "Ann", "address": {"city": "Paris"}, "grades": [85, [90, 92], 70],
+                    "rows": [{"id": 7}], "active": true}"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn mutable_container_accessors_reject_other_kinds() {
+        let mut value = doc();
+        assert!(value.as_array_mut().is_none());
+        assert!(value
+            .pointer_mut("/grades")
+            .unwrap()
+            .as_object_mut()
+            .is_none());
+        assert!(JsonValue::Null.as_array_mut().is_none());
+        assert!(JsonValue::String("{}".to_string())
+            .as_object_mut()
+            .is_none());
+    }
+
+    #[test]
+    fn as_cow_str_borrows_strings_only() {
+        let value = doc();
+        let name = value.get("name").unwrap().as_cow_str();
+        assert!(matches!(name, Some(Cow::Borrowed("Ann"))));
+        assert_eq!(value.as_cow_str(), None);
+        assert_eq!(JsonValue::Integer(1).as_cow_str(), None);
+        assert_eq!(JsonValue::RawNumber("1".to_string()).as_cow_str(), None);
+    }
+
+    #[test]
+    fn dotted_leaves_yields_get_path_paths_in_document_order() {
+        let value = doc();
+        let leaves: Vec<(String, JsonValue)> = value
+            .dotted_leaves()
+            .map(|(path, leaf)| (path, leaf.clone()))
+            .collect();
+        assert_eq!(
+            leaves,
+            [
+                ("name".to_string(), JsonValue::from("Ann")),
+                ("address.city".to_string(), JsonValue::from("Paris")),
+                ("grades[0]".to_string(), JsonValue::Integer(85)),
+                ("grades[1][0]".to_string(), JsonValue::Integer(90)),
+                ("grades[1][1]".to_string(), JsonValue::Integer(92)),
+                ("rows[0].id".to_string(), JsonValue::Integer(7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dotted_leaves_paths_lead_back_to_their_leaves() {
+        let value =
+            parse_json(r#"{"a.b": {"c[0]": 1}, "e": [], "f": {}, "g\\h": [null]}"#).unwrap();
+        let paths: Vec<String> = value.dotted_leaves().map(|(path, _)| path).collect();
+        assert_eq!(paths, [r"a\.b.c\[0\]", r"g\\h[0]"]);
+        for (path, leaf) in value.dotted_leaves() {
+            assert_eq!(value.get_path(&path), Some(leaf), "{path}");
+        }
+        let scalar = JsonValue::Integer(3);
+        assert_eq!(
+            scalar.dotted_leaves().collect::<Vec<_>>(),
+            [(String::new(), &scalar)]
+        );
+    }
+
+    #[test]
+    fn entries_yield_members_in_source_order() {
+        let value = parse_json(r#"{"b": 1, "a": [2], "c": null}"#).unwrap();
+        let keys: Vec<&str> = value.entries().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["b", "a", "c"]);
+        assert_eq!(
+            value.entries().nth(1),
+            Some(("a", &parse_json("[2]").unwrap()))
+        );
+        assert_eq!(parse_json("[1, 2]").unwrap().entries().count(), 0);
+        assert_eq!(JsonValue::Null.entries().count(), 0);
+    }
+
+    #[test]
+    fn sorted_entries_sort_stably_by_key() {
+        let value = JsonValue::Object(vec![
+            ("b".to_string(), JsonValue::Integer(1)),
+            ("a".to_string(), JsonValue::Integer(2)),
+            ("b".to_string(), JsonValue::Integer(0)),
+        ]);
+        assert_eq!(
+            value.sorted_entries(),
+            [
+                ("a", &JsonValue::Integer(2)),
+                ("b", &JsonValue::Integer(1)),
+                ("b", &JsonValue::Integer(0)),
+            ]
+        );
+        assert_eq!(value.entries().next().map(|(key, _)| key), Some("b"));
+        assert!(JsonValue::from("x").sorted_entries().is_empty());
+    }
+
+    #[test]
+    fn get_path_reads_backslash_escapes_in_keys() {
+        let value = parse_json(r#"{"a.b": {"x[1]": 2}, "a": {"b": 1}, "c\\d": 3}"#).unwrap();
+        assert_eq!(value.get_path(r"a\.b.x\[1\]"), Some(&JsonValue::Integer(2)));
+        assert_eq!(value.get_path("a.b"), Some(&JsonValue::Integer(1)));
+        assert_eq!(value.get_path(r"c\\d"), Some(&JsonValue::Integer(3)));
+        // Unescaped, the brackets are read as an index.
+        assert_eq!(value.get_path(r"a\.b.x[1]"), None);
+        // A trailing backslash escapes nothing.
+        assert_eq!(value.get_path("a\\"), None);
+    }
+
+    #[test]
+    fn escape_path_segment_round_trips_through_unescape() {
+        assert_eq!(escape_path_segment("a.b[0]"), r"a\.b\[0\]");
+        assert_eq!(escape_path_segment(r"c\d"), r"c\\d");
+        assert_eq!(escape_path_segment("plain"), "plain");
+        for key in ["a.b[0]", r"c\d", "", "..", r"\", "é.]"] {
+            assert_eq!(unescape_path_segment(&escape_path_segment(key)), key);
+        }
+        assert_eq!(unescape_path_segment(r"a\"), r"a\");
+        assert_eq!(unescape_path_segment(r"\x\\"), r"x\");
+    }
+
+    #[test]
+    fn collect_type_finds_nodes_in_pre_order_including_nested_ones() {
+        let value =
+            parse_json(r#"{"a": [1, 2.5, "x", [3]], "b": {"c": {"d": 4}}, "e": null}"#).unwrap();
+        assert_eq!(
+            value.collect_type(JsonType::Number),
+            [
+                &JsonValue::Integer(1),
+                &JsonValue::Number(2.5),
+                &JsonValue::Integer(3),
+                &JsonValue::Integer(4),
+            ]
+        );
+        let objects = value.collect_type(JsonType::Object);
+        assert_eq!(objects.len(), 3);
+        assert!(std::ptr::eq(objects[0], &value));
+        assert_eq!(objects[2], &parse_json(r#"{"d": 4}"#).unwrap());
+        let arrays = value.collect_type(JsonType::Array);
+        assert_eq!(arrays[1], &parse_json("[3]").unwrap());
+        assert_eq!(
+            value.collect_type(JsonType::Boolean),
+            Vec::<&JsonValue>::new()
+        );
+        assert_eq!(
+            JsonValue::Null.collect_type(JsonType::Null),
+            [&JsonValue::Null]
+        );
+    }
+
+    #[test]
+    fn integers_and_floats_of_the_same_value_are_equal() {
+        assert_eq!(JsonValue::Integer(5), JsonValue::Number(5.0));
+        assert_eq!(JsonValue::Number(-0.0), JsonValue::Integer(0));
+        assert_ne!(JsonValue::Integer(5), JsonValue::Number(5.5));
+        assert_ne!(JsonValue::Integer(5), JsonValue::RawNumber("5".into()));
+        assert_ne!(JsonValue::Number(f64::NAN), JsonValue::Number(f64::NAN));
+        // 2^53 + 1 rounds to 2^53 as an f64, but the values differ.
+        let big = 9_007_199_254_740_993;
+        assert_ne!(JsonValue::Integer(big), JsonValue::Number(big as f64));
+        assert_ne!(
+            JsonValue::Integer(i64::MAX),
+            JsonValue::Number(2f64.powi(63))
+        );
+        assert_eq!(
+            JsonValue::Integer(i64::MIN),
+            JsonValue::Number(-(2f64.powi(63)))
+        );
+        assert_eq!(
+            JsonValue::Array(vec![JsonValue::Integer(1)]),
+            JsonValue::Array(vec![JsonValue::Number(1.0)])
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use super::serializer::{escape_string, format_number, SerializerOptions};
+use super::value::JsonValue;
+
+/// Writes `value` as block-style YAML. JSON is already valid YAML, but block
+/// style is easier to read and edit: objects become `key: value` lines and
+/// arrays `- item` lines, with empty containers written as `{}` and `[]`.
+///
+/// Strings are left unquoted only when YAML would read them back as the same
+/// string; anything that could be mistaken for a null, boolean, number or
+/// YAML syntax is written double-quoted with JSON escapes, which YAML shares.
+pub fn to_yaml(value: &JsonValue) -> String {
+    let mut out = String::new();
+    match value {
+        JsonValue::Object(object) if !object.is_empty() => write_map(&mut out, object, 0, false),
+        JsonValue::Array(array) if !array.is_empty() => write_seq(&mut out, array, 0, false),
+        scalar => {
+            write_scalar(&mut out, scalar);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// With `inline`, the first line continues one already started by a `- `.
+fn write_map(out: &mut String, object: &[(String, JsonValue)], indent: usize, inline: bool) {
+    for (i, (key, value)) in object.iter().enumerate() {
+        if i > 0 || !inline {
+            out.extend(std::iter::repeat_n(' ', indent));
+        }
+        write_string(out, key);
+        out.push(':');
+        match value {
+            JsonValue::Object(object) if !object.is_empty() => {
+                out.push('\n');
+                write_map(out, object, indent + 2, false);
+            }
+            JsonValue::Array(array) if !array.is_empty() => {
+                out.push('\n');
+                write_seq(out, array, indent + 2, false);
+            }
+            scalar => {
+                out.push(' ');
+                write_scalar(out, scalar);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn write_seq(out: &mut String, array: &[JsonValue], indent: usize, inline: bool) {
+    for (i, value) in array.iter().enumerate() {
+        if i > 0 || !inline {
+            out.extend(std::iter::repeat_n(' ', indent));
+        }
+        out.push_str("- ");
+        match value {
+            JsonValue::Object(object) if !object.is_empty() => {
+                write_map(out, object, indent + 2, true)
+            }
+            JsonValue::Array(array) if !array.is_empty() => write_seq(out, array, indent + 2, true),
+            scalar => {
+                write_scalar(out, scalar);
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn write_scalar(out: &mut String, value: &JsonValue) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonValue::Integer(i) => out.push_str(&i.to_string()),
+        JsonValue::Number(n) if n.is_nan() => out.push_str(".nan"),
+        JsonValue::Number(n) if n.is_infinite() => {
+            out.push_str(if *n > 0.0 { ".inf" } else { "-.inf" })
+        }
+        JsonValue::Number(n) => write_float(out, *n),
+        JsonValue::RawNumber(raw) => out.push_str(raw),
+        JsonValue::String(s) => write_string(out, s),
+        JsonValue::Array(_) => out.push_str("[]"),
+        JsonValue::Object(_) => out.push_str("{}"),
+    }
+}
+
+// Writes the JSON text of a finite float, with exponent forms such as `1e300`
+// given a point and a signed exponent, `1.0e+300`, which YAML 1.1 needs to
+// read them as floats rather than strings.
+fn write_float(out: &mut String, n: f64) {
+    let text = format_number(n, &SerializerOptions::default()).unwrap_or_default();
+    match text.split_once('e') {
+        Some((mantissa, exponent)) => {
+            out.push_str(mantissa);
+            if !mantissa.contains('.') {
+                out.push_str(".0");
+            }
+            out.push('e');
+            if !exponent.starts_with('-') {
+                out.push('+');
+            }
+            out.push_str(exponent);
+        }
+        None => out.push_str(&text),
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    if is_plain_safe(s) {
+        out.push_str(s);
+    } else {
+        out.push_str(&escape_string(s));
+    }
+}
+
+// Whether `s` reads back as the same string when written as a plain scalar,
+// under both YAML 1.1 and 1.2 rules.
+fn is_plain_safe(s: &str) -> bool {
+    const RESERVED: &[&str] = &[
+        "null", "~", "true", "false", "yes", "no", "on", "off", "y", "n",
+    ];
+    let Some(first) = s.chars().next() else {
+        return false;
+    };
+    !(first.is_ascii_digit()
+        || "-?:,[]{}#&*!|>'\"%@`+. ".contains(first)
+        || s.ends_with([' ', ':'])
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars()
+            .any(|c| c.is_control() || matches!(c, '\u{2028}' | '\u{2029}' | '\u{FEFF}'))
+        || RESERVED.iter().any(|word| s.eq_ignore_ascii_case(word)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_parser::parser::parse_json;
+    use serde_yaml::Value as Yaml;
+
+    fn from_yaml(yaml: Yaml) -> JsonValue {
+        match yaml {
+            Yaml::Null => JsonValue::Null,
+            Yaml::Bool(b) => JsonValue::Boolean(b),
+            Yaml::Number(n) => match n.as_i64() {
+                Some(i) => JsonValue::Integer(i),
+                None => JsonValue::Number(n.as_f64().unwrap()),
+            },
+            Yaml::String(s) => JsonValue::String(s),
+            Yaml::Sequence(items) => JsonValue::Array(items.into_iter().map(from_yaml).collect()),
+            Yaml::Mapping(map) => JsonValue::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key.as_str().unwrap().to_string(), from_yaml(value)))
+                    .collect(),
+            ),
+            Yaml::Tagged(tagged) => from_yaml(tagged.value),
+        }
+    }
+
+    fn round_trip(value: &JsonValue) -> JsonValue {
+        from_yaml(serde_yaml::from_str(&to_yaml(value)).unwrap())
+    }
+
+    #[test]
+    fn round_trips_through_serde_yaml() {
+        let value = parse_json(
+            r#"{"name": "widget", "tags": ["a", "b c"], "size": {"w": 3, "h": 1.5},
+                "nested": [[1, 2], {"k": null}], "empty": {}, "none": [], "ok": true}"#,
+        )
+        .unwrap();
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn quotes_strings_yaml_would_retype() {
+        let value = parse_json(
+            r##"["yes", "No", "null", "~", "1.0", "42", "-", "-x", "- item", "a: b", "# c", ""]"##,
+        )
+        .unwrap();
+        let yaml = to_yaml(&value);
+        for quoted in [
+            "\"yes\"", "\"No\"", "\"null\"", "\"1.0\"", "\"-\"", "\"-x\"", "\"\"",
+        ] {
+            assert!(
+                yaml.contains(&format!("- {quoted}\n")),
+                "{quoted} in {yaml}"
+            );
+        }
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn leaves_ordinary_strings_plain() {
+        let value = parse_json(r#"{"greeting": "hello world", "path": "a/b-c"}"#).unwrap();
+        assert_eq!(to_yaml(&value), "greeting: hello world\npath: a/b-c\n");
+        assert_eq!(round_trip(&value), value);
+    }
+
+    #[test]
+    fn writes_extreme_floats_in_exponent_form() {
+        let value = JsonValue::Array(vec![
+            JsonValue::Number(1e300),
+            JsonValue::Number(-1.25e-7),
+            JsonValue::Number(2.5),
+        ]);
+        assert_eq!(to_yaml(&value), "- 1.0e+300\n- -1.25e-7\n- 2.5\n");
+        assert_eq!(round_trip(&value), value);
+    }
+}
@@ -0,0 +1 @@
+pub mod json_parser;
@@ -1,6 +1,4 @@
-use json_parser::parser::parse_json;
-
-mod json_parser;
+use rust_playground::json_parser::parser::parse_json;
 
 fn main() {
     let json = r#"